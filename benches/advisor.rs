@@ -0,0 +1,70 @@
+//! One point update plus one prefix sum on each backend of `fenwick::advisor` , to compare the
+//! actual crossovers on a machine with the ones of the cost model.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fenwick::advisor::{recommend, Workload};
+use fenwick::array::{prefix_sum, update};
+use rand::prelude::*;
+
+/// Values plus sums of blocks of `block` values.
+struct SqrtDecomposition {
+    block: usize,
+    values: Vec<i64>,
+    sums: Vec<i64>,
+}
+
+impl SqrtDecomposition {
+    fn new(len: usize) -> Self {
+        let block = (1..).find(|b| b * b >= len).unwrap();
+        Self { block, values: vec![0; len], sums: vec![0; len.div_ceil(block)] }
+    }
+
+    fn update(&mut self, i: usize, delta: i64) {
+        self.values[i] += delta;
+        self.sums[i / self.block] += delta;
+    }
+
+    fn prefix_sum(&self, i: usize) -> i64 {
+        let b = i / self.block;
+        self.sums[..b].iter().sum::<i64>() + self.values[b * self.block..=i].iter().sum::<i64>()
+    }
+}
+
+fn balanced(c: &mut Criterion) {
+    let mut group = c.benchmark_group("advisor");
+    for len in [1 << 6, 1 << 10, 1 << 14, 1 << 18] {
+        let workload = Workload { len, updates: 1.0, queries: 1.0, element_size: 8 };
+        eprintln!("len {}: recommended {:?}", len, recommend(&workload));
+        let mut rng = StdRng::seed_from_u64(1158);
+
+        let mut fenwick = vec![0i64; len];
+        group.bench_with_input(BenchmarkId::new("fenwick", len), &len, |b, &len| {
+            b.iter(|| {
+                update(&mut fenwick, rng.gen_range(0..len), 1);
+                prefix_sum(&fenwick, rng.gen_range(0..len))
+            })
+        });
+
+        let mut prefix = vec![0i64; len];
+        group.bench_with_input(BenchmarkId::new("prefix_array", len), &len, |b, &len| {
+            b.iter(|| {
+                for x in &mut prefix[rng.gen_range(0..len)..] {
+                    *x += 1;
+                }
+                prefix[rng.gen_range(0..len)]
+            })
+        });
+
+        let mut sqrt = SqrtDecomposition::new(len);
+        group.bench_with_input(BenchmarkId::new("sqrt_decomposition", len), &len, |b, &len| {
+            b.iter(|| {
+                sqrt.update(rng.gen_range(0..len), 1);
+                sqrt.prefix_sum(rng.gen_range(0..len))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, balanced);
+criterion_main!(benches);
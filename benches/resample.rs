@@ -0,0 +1,60 @@
+//! Resampling after setting a few weights, on a Fenwick tree versus rebuilding a cumulative array
+//! and binary searching it.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fenwick::array::{build, get, update};
+use fenwick::resample::systematic;
+use rand::prelude::*;
+
+/// Weights updated between resampling steps.
+const UPDATES: usize = 16;
+
+/// Particles drawn per resampling step.
+const DRAWS: usize = 64;
+
+fn resample(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resample");
+    for len in [1 << 8, 1 << 12, 1 << 16] {
+        let mut rng = StdRng::seed_from_u64(1151);
+        let weights: Vec<f64> = (0..len).map(|_| rng.gen_range(0.5..1.5)).collect();
+
+        let mut fenwick = weights.clone();
+        build(&mut fenwick);
+        group.bench_with_input(BenchmarkId::new("fenwick", len), &len, |b, &len| {
+            b.iter(|| {
+                for _ in 0..UPDATES {
+                    let i = rng.gen_range(0..len);
+                    let delta = rng.gen_range(0.5..1.5) - get(&fenwick, i);
+                    update(&mut fenwick, i, delta);
+                }
+                systematic(&fenwick, DRAWS, &mut rng)
+            })
+        });
+
+        let mut naive = weights.clone();
+        let mut cumulative = vec![0.0; len];
+        group.bench_with_input(BenchmarkId::new("naive", len), &len, |b, &len| {
+            b.iter(|| {
+                for _ in 0..UPDATES {
+                    naive[rng.gen_range(0..len)] = rng.gen_range(0.5..1.5);
+                }
+                let mut sum = 0.0;
+                for (c, w) in cumulative.iter_mut().zip(&naive) {
+                    sum += w;
+                    *c = sum;
+                }
+                let offset: f64 = rng.gen();
+                (0..DRAWS)
+                    .map(|j| {
+                        let target = (j as f64 + offset) / DRAWS as f64 * sum;
+                        cumulative.partition_point(|&c| c <= target).min(len - 1)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, resample);
+criterion_main!(benches);
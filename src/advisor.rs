@@ -0,0 +1,187 @@
+//! Rough guidance on whether a Fenwick tree is worth it for a given workload.
+//!
+//! [`recommend`] compares the estimated cost of a mix of point updates and prefix sum queries on
+//! three data structures:
+//!
+//! - a Fenwick tree: both operations touch `O(log(N))` scattered nodes;
+//! - a plain prefix sum array: a query reads one element, but an update rewrites the whole suffix;
+//! - square-root decomposition (values plus sums of blocks of `sqrt(N)` values): an update writes
+//!   two elements, but a query scans `O(sqrt(N))` consecutive block sums and values.
+//!
+//! The cost model counts element accesses, where a sequential access costs a fraction of a
+//! scattered one (several elements share a cache line, and scans vectorize), and scattered accesses
+//! get more expensive once the data no longer fits in cache. The constants are coarse guesses, not
+//! calibrated against measurements, so the estimates are only meant to tell clear cases apart:
+//! measure when the estimates are close (see [`estimated_cost`]). The `advisor` bench of this crate
+//! (`cargo bench --bench advisor`) times the three data structures on a balanced workload, to
+//! compare the actual crossovers on a machine with the recommendations.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::advisor::{recommend, Backend, Workload};
+//!
+//! // as many updates as queries: the Fenwick tree wins from moderate sizes on
+//! let balanced = Workload { len: 100_000, updates: 1.0, queries: 1.0, element_size: 8 };
+//! assert_eq!(recommend(&balanced), Backend::Fenwick);
+//!
+//! // almost only queries: a prefix sum array is hard to beat
+//! let read_mostly = Workload { len: 200, updates: 1.0, queries: 1000.0, element_size: 8 };
+//! assert_eq!(recommend(&read_mostly), Backend::PrefixArray);
+//! ```
+//!
+
+/// Relative cost of accessing one element in a sequential scan, per 8 bytes of element.
+const SEQUENTIAL_COST: f64 = 0.25;
+
+/// Relative cost of a scattered access to data that does not fit in cache.
+const CACHE_MISS_COST: f64 = 4.0;
+
+/// Size of data (in bytes) assumed to stay in cache.
+const CACHE_SIZE: usize = 1 << 20;
+
+/// Data structure answering prefix sums over an array with point updates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Fenwick tree, e.g. [`crate::array`] .
+    Fenwick,
+    /// Array of prefix sums, rebuilt from the updated element on.
+    PrefixArray,
+    /// Values plus sums of blocks of `sqrt(N)` values.
+    SqrtDecomposition,
+}
+
+/// Expected workload: array length, relative frequency of operations and element size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Workload {
+    /// Number of elements.
+    pub len: usize,
+    /// Relative number of point updates, e.g. per second or as a fraction.
+    pub updates: f64,
+    /// Relative number of prefix sum queries, in the same unit as `updates` .
+    pub queries: f64,
+    /// Size of one element in bytes, e.g. `size_of::<T>()` .
+    pub element_size: usize,
+}
+
+impl Workload {
+    /// Cost of one element access in a sequential scan.
+    fn sequential(&self) -> f64 {
+        SEQUENTIAL_COST * self.element_size.max(1).div_ceil(8) as f64
+    }
+
+    /// Cost of one scattered element access.
+    fn scattered(&self) -> f64 {
+        match self.len.checked_mul(self.element_size) {
+            Some(bytes) if bytes <= CACHE_SIZE => 1.0,
+            _ => CACHE_MISS_COST,
+        }
+    }
+}
+
+/// Returns the estimated cost of `workload` on `backend` , in arbitrary units proportional to
+/// time. Only the ratios between backends for the same workload are meaningful.
+///
+/// # Panics
+///
+/// Panics if `updates` or `queries` is negative or not finite.
+///
+pub fn estimated_cost(workload: &Workload, backend: Backend) -> f64 {
+    assert!(workload.updates.is_finite() && workload.updates >= 0.0);
+    assert!(workload.queries.is_finite() && workload.queries >= 0.0);
+    let n = workload.len.max(1);
+    let (seq, scattered) = (workload.sequential(), workload.scattered());
+    let (update, query) = match backend {
+        Backend::Fenwick => {
+            // about half of the `log(N)` bits of a random index are set
+            let nodes = (n.ilog2() + 1) as f64 / 2.0 + 1.0;
+            (nodes * scattered, nodes * scattered)
+        }
+        // on average half of the array is after the updated element
+        Backend::PrefixArray => (scattered + (n / 2) as f64 * seq, scattered),
+        Backend::SqrtDecomposition => {
+            // on average half of the blocks, then half of the values in a block
+            (2.0 * scattered, scattered + isqrt(n) as f64 * seq)
+        }
+    };
+    workload.updates * update + workload.queries * query
+}
+
+/// Returns the backend with the lowest [`estimated_cost`] for `workload` , preferring the Fenwick
+/// tree on ties.
+///
+/// # Panics
+///
+/// Panics if `updates` or `queries` is negative or not finite.
+///
+pub fn recommend(workload: &Workload) -> Backend {
+    [Backend::Fenwick, Backend::PrefixArray, Backend::SqrtDecomposition]
+        .into_iter()
+        .map(|backend| (backend, estimated_cost(workload, backend)))
+        .fold(None, |best: Option<(Backend, f64)>, (backend, cost)| match best {
+            Some((_, best_cost)) if best_cost <= cost => best,
+            _ => Some((backend, cost)),
+        })
+        .unwrap()
+        .0
+}
+
+/// Integer square root of `n` , rounded down, by Newton's method.
+fn isqrt(n: usize) -> usize {
+    if n < 2 {
+        return n;
+    }
+    // start above the root, from where the iteration decreases monotonically
+    let mut x = 1usize << (n.ilog2() / 2 + 1);
+    loop {
+        let next = (x + n / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workload(len: usize, updates: f64, queries: f64) -> Workload {
+        Workload { len, updates, queries, element_size: 8 }
+    }
+
+    #[test]
+    fn crossovers() {
+        // no updates: prefix sums; no queries: cheap updates
+        for len in [200, 4096, 1 << 20] {
+            assert_eq!(recommend(&workload(len, 0.0, 1.0)), Backend::PrefixArray);
+            assert_eq!(recommend(&workload(len, 1.0, 0.0)), Backend::SqrtDecomposition);
+        }
+        // balanced: the Fenwick tree wins for large arrays, and keeps winning
+        let mut fenwick_from = None;
+        for shift in 0..30 {
+            let wins = recommend(&workload(1 << shift, 1.0, 1.0)) == Backend::Fenwick;
+            match fenwick_from {
+                None if wins => fenwick_from = Some(shift),
+                Some(_) => assert!(wins),
+                None => {}
+            }
+        }
+        assert!(fenwick_from.is_some_and(|shift| shift <= 16));
+        // larger elements make scans more expensive
+        let small = Workload { element_size: 4, ..workload(4096, 1.0, 1.0) };
+        let large = Workload { element_size: 64, ..small };
+        for backend in [Backend::PrefixArray, Backend::SqrtDecomposition] {
+            assert!(estimated_cost(&small, backend) < estimated_cost(&large, backend));
+        }
+    }
+
+    #[test]
+    fn isqrt_values() {
+        for n in (0..10000).chain([usize::MAX - 1, usize::MAX, 1 << 40, (1 << 40) - 1]) {
+            let root = isqrt(n);
+            assert!(root * root <= n);
+            assert!((root + 1).checked_mul(root + 1).map_or(true, |square| square > n));
+        }
+    }
+}
@@ -1,5 +1,19 @@
 //! Operations on an 1D Fenwick tree stored in a zero-based slice.
 //!
+//! Elements of an additive tree may be of any type `T: AddAssign + Clone + Default` , where
+//! `T::default()` is the additive identity (zero). This covers primitive numbers as well as
+//! third-party numeric types that are not `Copy` (e.g. big integers, decimals or unit wrappers).
+//! Multiplicative trees similarly require `T: MulAssign + Clone` .
+//!
+//! For counters that may overflow 64 bits (e.g. long-running telemetry aggregates), `i128` and
+//! `u128` work like any other primitive integer, in every function of this module. As with other
+//! integers, overflow panics in debug builds and wraps in release builds; the `checked_` functions
+//! only check bounds.
+//!
+//! Functions taking a range of indices (here and in the owned types of this crate) accept any
+//! `RangeBounds<usize>` , e.g. `2..5` , `2..=4` , `2..` or `..` , with the same semantics as slice
+//! indexing: a range whose start is after its end, or whose end is after the length, panics.
+//!
 //! # Examples
 //!
 //! ```
@@ -24,8 +38,9 @@
 //! ```
 //!
 
-use core::ops::AddAssign;
+use core::ops::{AddAssign, MulAssign, Range, RangeBounds, SubAssign};
 
+use crate::index::to_range;
 use crate::index::zero_based::{down as seq_dn, up as seq_up};
 
 /// Updates one element in the Fenwick tree stored in a borrowed slice (zero-based).
@@ -42,10 +57,10 @@ use crate::index::zero_based::{down as seq_dn, up as seq_up};
 ///
 pub fn update<T>(fenwick: &mut [T], i: usize, delta: T)
 where
-    T: AddAssign + Copy + Default
+    T: AddAssign + Clone + Default
 {
     for ii in seq_up(i, fenwick.len()) {
-        fenwick[ii] += delta;
+        fenwick[ii] += delta.clone();
     }
 }
 
@@ -64,49 +79,1692 @@ where
 ///
 pub fn prefix_sum<T>(fenwick: &[T], i: usize) -> T
 where
-    T: AddAssign + Copy + Default
+    T: AddAssign + Clone + Default
 {
     let mut sum = T::default();
     for ii in seq_dn(i) {
-        sum += fenwick[ii];
+        sum += fenwick[ii].clone();
     }
     sum
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    extern crate std;
+/// Non-panicking version of [`update`] : returns `None` without modifying the tree if `fenwick[i]`
+/// is out of bound.
+///
+/// Never indexes out of bound, so the only way this can panic is through `T::add_assign` (e.g.
+/// integer overflow in debug builds).
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{checked_update, checked_prefix_sum};
+///
+/// let fw = &mut [0i32; 4];
+/// assert_eq!(checked_update(fw, 1, 5), Some(()));
+/// assert_eq!(checked_update(fw, 4, 5), None);
+/// assert_eq!(checked_prefix_sum(fw, 3), Some(5));
+/// assert_eq!(checked_prefix_sum(fw, 4), None);
+/// ```
+///
+pub fn checked_update<T>(fenwick: &mut [T], i: usize, delta: T) -> Option<()>
+where
+    T: AddAssign + Clone + Default
+{
+    if i >= fenwick.len() {
+        return None;
+    }
+    for ii in seq_up(i, fenwick.len()) {
+        if let Some(x) = fenwick.get_mut(ii) {
+            *x += delta.clone();
+        }
+    }
+    Some(())
+}
 
-    use itertools::Itertools;
-    use rand::prelude::*;
+/// Non-panicking version of [`prefix_sum`] : returns `None` if `fenwick[i]` is out of bound.
+///
+/// Never indexes out of bound, so the only way this can panic is through `T::add_assign` (e.g.
+/// integer overflow in debug builds).
+///
+/// # Examples
+///
+/// See [`checked_update`] .
+///
+pub fn checked_prefix_sum<T>(fenwick: &[T], i: usize) -> Option<T>
+where
+    T: AddAssign + Clone + Default
+{
+    if i >= fenwick.len() {
+        return None;
+    }
+    let mut sum = T::default();
+    for ii in seq_dn(i) {
+        sum += fenwick.get(ii)?.clone();
+    }
+    Some(sum)
+}
 
-    #[test]
-    fn randoms() {
-        let mut rng = thread_rng();
-        for len in 0..256 {
-            random_one(&mut rng, len);
+/// Calculates one element in the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually returns `a[i]` on the original array `a` . This subtracts the children of node
+/// `i` from it, which is cheaper than subtracting two prefix sums.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, get};
+///
+/// let fw = &mut [0i32; 8];
+/// update(fw, 3, 5);
+/// update(fw, 2, 1);
+/// assert_eq!(get(fw, 2), 1);
+/// assert_eq!(get(fw, 3), 5);
+/// assert_eq!(get(fw, 7), 0);
+/// ```
+///
+pub fn get<T>(fenwick: &[T], i: usize) -> T
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    let mut value = fenwick[i].clone();
+    // node `i` covers `lo..=i` , and its children cover `lo..i`
+    let lo = i & (i + 1);
+    if i > lo {
+        for ii in seq_dn(i - 1).take_while(|&ii| ii >= lo) {
+            value -= fenwick[ii].clone();
         }
     }
+    value
+}
 
-    fn random_one<TRng: Rng>(rng: &mut TRng, len: usize) {
-        let dist = rand::distributions::Uniform::new_inclusive(-100, 100);
-        let data = rng.sample_iter(dist).take(len).collect_vec();
-        let psum = data.iter().scan(0, |s, x| {
-            *s += x;
-            Some(*s)
-        }).collect_vec();
+/// Swaps two elements in the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually performs `a.swap(i, j)` on the original array `a` , using two point updates.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` or `fenwick[j]` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, get, swap};
+///
+/// let fw = &mut [0i32; 8];
+/// update(fw, 1, 5);
+/// update(fw, 6, 2);
+/// swap(fw, 1, 6);
+/// assert_eq!(get(fw, 1), 2);
+/// assert_eq!(get(fw, 6), 5);
+/// ```
+///
+pub fn swap<T>(fenwick: &mut [T], i: usize, j: usize)
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    let a = get(fenwick, i);
+    let mut delta = get(fenwick, j);
+    delta -= a;
+    update(fenwick, i, delta.clone());
+    let mut neg = T::default();
+    neg -= delta;
+    update(fenwick, j, neg);
+}
 
-        let mut fenwick = std::vec![0i32; len];
+/// Moves one element onto another in the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually performs `a[to] += a[from]; a[from] = 0` on the original array `a` , using two
+/// point updates. Prefix sums outside of the range between `from` and `to` are unaffected.
+///
+/// # Panics
+///
+/// Panics if `fenwick[from]` or `fenwick[to]` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, get, move_value};
+///
+/// let fw = &mut [0i32; 8];
+/// update(fw, 1, 5);
+/// update(fw, 6, 2);
+/// move_value(fw, 1, 6);
+/// assert_eq!(get(fw, 1), 0);
+/// assert_eq!(get(fw, 6), 7);
+/// ```
+///
+pub fn move_value<T>(fenwick: &mut [T], from: usize, to: usize)
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    if from == to {
+        return;
+    }
+    let value = get(fenwick, from);
+    update(fenwick, to, value.clone());
+    let mut neg = T::default();
+    neg -= value;
+    update(fenwick, from, neg);
+}
 
-        let mut ops = data.iter().enumerate().collect_vec();
-        ops.shuffle(rng);
-        for (i, x) in ops {
-            update(&mut fenwick, i, *x);
+/// Permutes the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually replaces each `a[i]` with `a[perm[i]]` on the original array `a` (all at once).
+///
+/// If few elements are moved, this performs one point update per moved element. Otherwise the
+/// tree is converted back into the original array, permuted and rebuilt in `O(N)` .
+///
+/// # Panics
+///
+/// Panics if `perm` is not a permutation of `0..fenwick.len()` .
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, get, permute};
+///
+/// let fw = &mut [10, 20, 30, 40];
+/// build(fw);
+/// permute(fw, &[3, 0, 1, 2]);
+/// assert_eq!(get(fw, 0), 40);
+/// assert_eq!(get(fw, 1), 10);
+/// assert_eq!(get(fw, 3), 30);
+/// ```
+///
+#[cfg(feature = "alloc")]
+pub fn permute<T>(fenwick: &mut [T], perm: &[usize])
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    let len = fenwick.len();
+    assert_eq!(perm.len(), len);
+    let mut seen = vec![false; len];
+    for &p in perm {
+        assert!(!core::mem::replace(&mut seen[p], true));
+    }
+
+    let moved = perm.iter().enumerate().filter(|&(i, &p)| i != p).count();
+    let log = (usize::BITS - len.leading_zeros()) as usize;
+    if moved * log < len {
+        let deltas: Vec<_> = perm.iter().enumerate()
+            .filter(|&(i, &p)| i != p)
+            .map(|(i, &p)| {
+                let mut delta = get(fenwick, p);
+                delta -= get(fenwick, i);
+                (i, delta)
+            })
+            .collect();
+        for (i, delta) in deltas {
+            update(fenwick, i, delta);
+        }
+    } else {
+        unbuild(fenwick);
+        let values: Vec<_> = perm.iter().map(|&p| fenwick[p].clone()).collect();
+        for (x, v) in fenwick.iter_mut().zip(values) {
+            *x = v;
         }
+        build(fenwick);
+    }
+}
 
-        for (i, s) in psum.iter().enumerate() {
-            assert_eq!(prefix_sum(&fenwick, i), *s);
+/// Resets a range of elements to zero in the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually performs `a[i] = 0` for every `i` in `range` on the original array `a` .
+///
+/// If the range is short, this performs one point update per element in `O(M log(N))` for `M`
+/// elements. Otherwise it reconstructs the original array in place, zeroes the range and rebuilds
+/// the tree in `O(N)` .
+///
+/// # Panics
+///
+/// Panics if `range` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, prefix_sum, reset_range};
+///
+/// let fw = &mut [1, 2, 3, 4, 5, 6];
+/// build(fw);
+/// reset_range(fw, 1..4);
+/// assert_eq!(prefix_sum(fw, 3), 1);
+/// assert_eq!(prefix_sum(fw, 5), 12);
+/// ```
+///
+pub fn reset_range<T>(fenwick: &mut [T], range: impl RangeBounds<usize>)
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    let len = fenwick.len();
+    let Range { start, end } = to_range(&range, len);
+    let log = (usize::BITS - len.leading_zeros()) as usize;
+    let rebuild = (end - start) * log >= len;
+    span!("reset_range", len, elements = end - start, rebuild);
+    if !rebuild {
+        for i in start..end {
+            let value = get(fenwick, i);
+            for ii in seq_up(i, len) {
+                fenwick[ii] -= value.clone();
+            }
+        }
+    } else {
+        unbuild(fenwick);
+        for x in &mut fenwick[start..end] {
+            *x = T::default();
+        }
+        build(fenwick);
+    }
+}
+
+/// Appends an element to the Fenwick tree stored in a `Vec` (zero-based).
+///
+/// Conceptually performs `a.push(value)` on the original array `a` . Appending never changes
+/// existing nodes, and the new node is computed from its `O(log(N))` children. Together with the
+/// geometric growth of `Vec` , this builds a tree from a stream of unknown length in
+/// `O(log(N))` amortized per element, while the tree remains queryable at every step.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{push, pop, prefix_sum};
+///
+/// let mut fw = Vec::new();
+/// for x in [3, 1, 4, 1, 5] {
+///     push(&mut fw, x);
+/// }
+/// assert_eq!(prefix_sum(&fw, 2), 8);
+/// assert_eq!(prefix_sum(&fw, 4), 14);
+/// assert_eq!(pop(&mut fw), Some(5));
+/// assert_eq!(fw.len(), 4);
+/// assert_eq!(prefix_sum(&fw, 3), 9);
+/// ```
+///
+#[cfg(feature = "alloc")]
+pub fn push<T>(fenwick: &mut alloc::vec::Vec<T>, value: T)
+where
+    T: AddAssign + Clone + Default
+{
+    let i = fenwick.len();
+    fenwick.push(value);
+    // node `i` covers `lo..=i` , and its children cover `lo..i`
+    let lo = i & (i + 1);
+    if i > lo {
+        for ii in seq_dn(i - 1).take_while(|&ii| ii >= lo) {
+            let x = fenwick[ii].clone();
+            fenwick[i] += x;
+        }
+    }
+}
+
+/// Removes the last element from the Fenwick tree stored in a `Vec` (zero-based) and returns it,
+/// or `None` if empty.
+///
+/// Conceptually performs `a.pop()` on the original array `a` . Takes `O(log(N))` .
+///
+/// # Examples
+///
+/// See [`push`].
+///
+#[cfg(feature = "alloc")]
+pub fn pop<T>(fenwick: &mut alloc::vec::Vec<T>) -> Option<T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    let value = get(fenwick, fenwick.len().checked_sub(1)?);
+    fenwick.pop();
+    Some(value)
+}
+
+/// Converts the Fenwick tree stored in a borrowed slice (zero-based) into the traditional one-based
+/// layout, as used by [`crate::index::one_based`] and most literature.
+///
+/// Zero-based node `i` covers the same elements as one-based node `i + 1` (see
+/// [`node_range`](crate::index::zero_based::node_range)), so the nodes are equal and only shift by
+/// one: the result has an unused zero node at index `0` , followed by a copy of `fenwick` . No
+/// values need to be reconstructed. Takes `O(N)` .
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, from_one_based, to_one_based};
+/// use fenwick::index::one_based::down;
+///
+/// let fw = &mut [3, 1, 4, 1, 5];
+/// build(fw);
+/// let tree = to_one_based(fw);
+/// assert_eq!(tree, [0, 3, 4, 4, 9, 5]);
+/// assert_eq!(down(4).map(|i| tree[i]).sum::<i32>(), 9); // prefix sum of 4 elements
+/// assert_eq!(from_one_based(&tree), Some(&fw[..]));
+/// ```
+///
+#[cfg(feature = "alloc")]
+pub fn to_one_based<T>(fenwick: &[T]) -> alloc::vec::Vec<T>
+where
+    T: Clone + Default
+{
+    let mut tree = alloc::vec::Vec::with_capacity(fenwick.len() + 1);
+    tree.push(T::default());
+    tree.extend_from_slice(fenwick);
+    tree
+}
+
+/// Converts a Fenwick tree in the traditional one-based layout back into the zero-based layout
+/// used by this module, without copying. See [`to_one_based`] .
+///
+/// Returns `None` if `tree` is empty or its unused node `tree[0]` is not zero, which indicates that
+/// it is not a one-based tree (e.g. it is already zero-based).
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{from_one_based, prefix_sum};
+///
+/// let tree = [0, 3, 4, 4, 9, 5]; // one-based tree over [3, 1, 4, 1, 5]
+/// let fw = from_one_based(&tree).unwrap();
+/// assert_eq!(prefix_sum(fw, 3), 9);
+/// assert_eq!(from_one_based(&tree[1..]), None);
+/// assert_eq!(from_one_based::<i32>(&[]), None);
+/// ```
+///
+pub fn from_one_based<T>(tree: &[T]) -> Option<&[T]>
+where
+    T: Default + PartialEq
+{
+    match tree.split_first() {
+        Some((zero, fenwick)) if *zero == T::default() => Some(fenwick),
+        _ => None,
+    }
+}
+
+/// Finds the indices of the `k` largest elements in the Fenwick tree stored in a borrowed slice
+/// (zero-based), in decreasing order of value (ties broken by smaller index).
+///
+/// A Fenwick tree does not order its elements by value, so this reconstructs the original array
+/// `a` in a copy and selects from it, taking `O(N + k log(k))` time and `O(N)` extra space. Returns
+/// all indices if `k >= a.len()` .
+///
+/// Elements that are not comparable to themselves (i.e. NaN) are not supported: they are skipped,
+/// so fewer than `k` indices may be returned. The other elements must be totally ordered by
+/// `PartialOrd` .
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, top_k};
+///
+/// let fw = &mut [5, 1, 9, 3, 9, 0];
+/// build(fw);
+/// assert_eq!(top_k(fw, 3), [2, 4, 0]);
+/// assert_eq!(top_k(fw, 0), []);
+///
+/// let fw = &mut [1.5, f64::NAN, 0.5];
+/// build(fw);
+/// assert_eq!(top_k(fw, 3), [0, 2]);
+/// ```
+///
+#[cfg(feature = "alloc")]
+pub fn top_k<T>(fenwick: &[T], k: usize) -> alloc::vec::Vec<usize>
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialOrd
+{
+    use alloc::vec::Vec;
+    use core::cmp::Ordering;
+
+    let mut values = fenwick.to_vec();
+    unbuild(&mut values);
+    let cmp = |&i: &usize, &j: &usize| {
+        values[j].partial_cmp(&values[i]).unwrap_or(Ordering::Equal).then(i.cmp(&j))
+    };
+    // skip NaN, so that the comparison is a total order
+    let mut indices: Vec<usize> =
+        (0..values.len()).filter(|&i| values[i].partial_cmp(&values[i]).is_some()).collect();
+    if k < indices.len() {
+        if k > 0 {
+            indices.select_nth_unstable_by(k - 1, cmp);
+        }
+        indices.truncate(k);
+    }
+    indices.sort_unstable_by(cmp);
+    indices
+}
+
+/// Finds the maximal ranges of consecutive elements in the Fenwick tree stored in a borrowed slice
+/// (zero-based) that each exceed `threshold` , in increasing order.
+///
+/// Conceptually returns the runs of `a[i] > threshold` on the original array `a` . All elements
+/// must be non-negative: the search descends from the whole array into halves, skipping any half
+/// whose sum does not exceed `threshold` , since it cannot contain such an element. With
+/// `threshold == theta * total` , at most `1 / theta` halves are visited at each level, so this
+/// takes `O(log(N))` range sums per heavy element, or `O(log(N)^2 / theta)` overall, instead of
+/// scanning all `N` elements.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, heavy_ranges};
+///
+/// let fw = &mut [1, 0, 30, 25, 2, 0, 0, 40, 1, 1];
+/// build(fw);
+/// assert_eq!(heavy_ranges(fw, 10), [2..4, 7..8]);
+/// assert_eq!(heavy_ranges(fw, 100), []);
+/// ```
+///
+#[cfg(feature = "alloc")]
+pub fn heavy_ranges<T>(fenwick: &[T], threshold: T) -> alloc::vec::Vec<Range<usize>>
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialOrd
+{
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let n = fenwick.len();
+    if n == 0 {
+        return ranges;
+    }
+    // ranges still to be searched with their sums; the left half is popped first
+    let mut stack = vec![(0..n, prefix_sum(fenwick, n - 1))];
+    while let Some((range, sum)) = stack.pop() {
+        if sum <= threshold {
+            continue;
+        }
+        if range.len() == 1 {
+            let i = range.start;
+            match ranges.last_mut() {
+                Some(last) if last.end == i => last.end = i + 1,
+                _ => ranges.push(i..i + 1),
+            }
+            continue;
+        }
+        let mid = range.start + range.len() / 2;
+        let mut left = prefix_sum(fenwick, mid - 1);
+        if range.start > 0 {
+            left -= prefix_sum(fenwick, range.start - 1);
+        }
+        let mut right = sum;
+        right -= left.clone();
+        stack.push((mid..range.end, right));
+        stack.push((range.start..mid, left));
+    }
+    ranges
+}
+
+/// Adds the Fenwick tree stored in `other` into the Fenwick tree stored in `fenwick` (zero-based).
+///
+/// Conceptually performs `a[i] += b[i]` for every `i` on the original arrays `a` and `b` . Since
+/// each node is a sum over the original array, this is done node by node in `O(N)` .
+///
+/// # Panics
+///
+/// Panics if the two slices have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, prefix_sum, merge_add};
+///
+/// let a = &mut [0i32; 6];
+/// let b = &mut [0i32; 6];
+/// update(a, 1, 4);
+/// update(b, 1, 1);
+/// update(b, 3, 2);
+/// merge_add(a, b);
+/// assert_eq!(prefix_sum(a, 2), 5);
+/// assert_eq!(prefix_sum(a, 5), 7);
+/// ```
+///
+pub fn merge_add<T>(fenwick: &mut [T], other: &[T])
+where
+    T: AddAssign + Clone + Default
+{
+    assert_eq!(fenwick.len(), other.len());
+    span!("merge_add", len = fenwick.len());
+    for (x, y) in fenwick.iter_mut().zip(other.iter()) {
+        *x += y.clone();
+    }
+}
+
+/// Converts the original array stored in a borrowed slice into a Fenwick tree in place
+/// (zero-based).
+///
+/// Takes `O(N)` , compared to `O(N log(N))` for calling [`update`] on each element of a zeroed
+/// slice. Since no extra memory is needed, a tree can be constructed from an iterator by
+/// collecting it directly into the backing storage and building in place, without an
+/// intermediate buffer.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, unbuild, prefix_sum};
+///
+/// let fw = &mut [3, 1, 4, 1, 5, 9, 2, 6]; // original array
+/// build(fw); // backing array of Fenwick tree
+/// assert_eq!(prefix_sum(fw, 0), 3);
+/// assert_eq!(prefix_sum(fw, 4), 14);
+/// assert_eq!(prefix_sum(fw, 7), 31);
+/// unbuild(fw); // original array again
+/// assert_eq!(fw, &[3, 1, 4, 1, 5, 9, 2, 6]);
+/// ```
+///
+/// Constructing from an `ExactSizeIterator` with a single allocation:
+///
+/// ```
+/// use fenwick::array::{build, prefix_sum};
+///
+/// let mut fw: Vec<u64> = (1..=1000u64).collect(); // allocates exactly once
+/// build(&mut fw);
+/// assert_eq!(prefix_sum(&fw, 999), 500500);
+/// ```
+///
+pub fn build<T>(fenwick: &mut [T])
+where
+    T: AddAssign + Clone + Default
+{
+    let len = fenwick.len();
+    span!("build", len);
+    for i in 0..len {
+        let parent = i | (i + 1);
+        if parent < len {
+            let x = fenwick[i].clone();
+            fenwick[parent] += x;
+        }
+    }
+}
+
+/// Converts the Fenwick tree stored in a borrowed slice back into the original array in place
+/// (zero-based). This is the inverse of [`build`] .
+///
+/// Takes `O(N)` .
+///
+/// # Examples
+///
+/// See [`build`].
+///
+pub fn unbuild<T>(fenwick: &mut [T])
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    let len = fenwick.len();
+    span!("unbuild", len);
+    for i in (0..len).rev() {
+        let parent = i | (i + 1);
+        if parent < len {
+            let x = fenwick[i].clone();
+            fenwick[parent] -= x;
+        }
+    }
+}
+
+/// Updates one element in the Fenwick tree stored in a borrowed slice (zero-based), taking `delta`
+/// by reference.
+///
+/// Same as [`update`] , but avoids cloning `delta` for each visited node. Useful for element types
+/// that are expensive to clone (e.g. arbitrary-precision numbers) and implement `AddAssign<&T>` .
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// See [`prefix_sum_ref`].
+///
+pub fn update_ref<T>(fenwick: &mut [T], i: usize, delta: &T)
+where
+    T: for<'a> AddAssign<&'a T>
+{
+    for ii in seq_up(i, fenwick.len()) {
+        fenwick[ii] += delta;
+    }
+}
+
+/// Calculates the prefix sum up to and including `i` in the Fenwick tree stored in a borrowed slice
+/// (zero-based), adding nodes by reference.
+///
+/// Same as [`prefix_sum`] , but avoids cloning each visited node. Useful for element types that are
+/// expensive to clone (e.g. arbitrary-precision numbers) and implement `AddAssign<&T>` .
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update_ref, prefix_sum_ref};
+///
+/// // primitive numbers implement `AddAssign<&T>` too
+/// let fw = &mut [0i64; 4];
+/// update_ref(fw, 1, &7);
+/// update_ref(fw, 3, &-2);
+/// assert_eq!(prefix_sum_ref(fw, 0), 0);
+/// assert_eq!(prefix_sum_ref(fw, 2), 7);
+/// assert_eq!(prefix_sum_ref(fw, 3), 5);
+/// ```
+///
+pub fn prefix_sum_ref<T>(fenwick: &[T], i: usize) -> T
+where
+    T: Default + for<'a> AddAssign<&'a T>
+{
+    let mut sum = T::default();
+    for ii in seq_dn(i) {
+        sum += &fenwick[ii];
+    }
+    sum
+}
+
+/// Multiplies one element by `factor` in the multiplicative Fenwick tree stored in a borrowed
+/// slice (zero-based).
+///
+/// Conceptually performs `a[i] *= factor` on the original array `a`.
+///
+/// A multiplicative Fenwick tree works the same way as an additive one with multiplication in
+/// place of addition, which requires multiplication to be associative and commutative. Unlike
+/// addition, there is no `Default` identity, so the backing array must be initialized with ones
+/// (i.e. the original array is all ones). Types with a multiplicative inverse (e.g. floats or
+/// nonzero integers modulo a prime) can also undo an update or query the product over a range by
+/// dividing two prefix products.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update_mul, prefix_product};
+///
+/// let fw = &mut [1.0f64; 8]; // original array: all ones
+/// update_mul(fw, 2, 3.0); // original array: [1, 1, 3, 1, 1, 1, 1, 1]
+/// update_mul(fw, 5, 0.5); // original array: [1, 1, 3, 1, 1, 0.5, 1, 1]
+/// assert_eq!(prefix_product(fw, 1), 1.0);
+/// assert_eq!(prefix_product(fw, 4), 3.0);
+/// assert_eq!(prefix_product(fw, 7), 1.5);
+/// assert_eq!(prefix_product(fw, 7) / prefix_product(fw, 2), 0.5); // product over 3..=7
+/// ```
+///
+pub fn update_mul<T>(fenwick: &mut [T], i: usize, factor: T)
+where
+    T: MulAssign + Clone
+{
+    for ii in seq_up(i, fenwick.len()) {
+        fenwick[ii] *= factor.clone();
+    }
+}
+
+/// Calculates the prefix product up to and including `i` in the multiplicative Fenwick tree
+/// stored in a borrowed slice (zero-based).
+///
+/// Conceptually calculates `a[0] * ... * a[i]` on the original array `a`.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// See [`update_mul`].
+///
+pub fn prefix_product<T>(fenwick: &[T], i: usize) -> T
+where
+    T: MulAssign + Clone
+{
+    let mut seq = seq_dn(i);
+    // `down` always yields at least `i` itself, so no multiplicative identity is needed
+    let mut product = fenwick[seq.next().unwrap()].clone();
+    for ii in seq {
+        product *= fenwick[ii].clone();
+    }
+    product
+}
+
+/// Finds the smallest index `i` such that the prefix sum up to and including `i` is no less than
+/// `target` in the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually finds the smallest `i` such that `a[0] + ... + a[i] >= target` on the original
+/// array `a`, returning `a.len()` if there is no such index. This takes `O(log(N))` instead of the
+/// `O(log(N)^2)` of a binary search over [`prefix_sum`].
+///
+/// The result is only meaningful if all elements of the original array are non-negative (i.e. the
+/// prefix sums are non-decreasing).
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, lower_bound};
+///
+/// let fw = &mut [0u32; 8];
+/// update(fw, 1, 2); // original array: [0, 2, 0, 0, 0, 0, 0, 0]
+/// update(fw, 4, 3); // original array: [0, 2, 0, 0, 3, 0, 0, 0]
+/// assert_eq!(lower_bound(fw, 0), 0);
+/// assert_eq!(lower_bound(fw, 1), 1);
+/// assert_eq!(lower_bound(fw, 2), 1);
+/// assert_eq!(lower_bound(fw, 3), 4);
+/// assert_eq!(lower_bound(fw, 5), 4);
+/// assert_eq!(lower_bound(fw, 6), 8);
+/// ```
+///
+pub fn lower_bound<T>(fenwick: &[T], target: T) -> usize
+where
+    T: AddAssign + Clone + Default + PartialOrd
+{
+    partition_point(fenwick, |sum| *sum < target)
+}
+
+/// Finds the smallest index `i` such that the prefix sum up to and including `i` is greater than
+/// `target` in the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually finds the smallest `i` such that `a[0] + ... + a[i] > target` on the original
+/// array `a`, returning `a.len()` if there is no such index. Takes `O(log(N))` .
+///
+/// The result is only meaningful if all elements of the original array are non-negative (i.e. the
+/// prefix sums are non-decreasing).
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, upper_bound};
+///
+/// let fw = &mut [0u32; 8];
+/// update(fw, 1, 2); // original array: [0, 2, 0, 0, 0, 0, 0, 0]
+/// update(fw, 4, 3); // original array: [0, 2, 0, 0, 3, 0, 0, 0]
+/// assert_eq!(upper_bound(fw, 0), 1);
+/// assert_eq!(upper_bound(fw, 1), 1);
+/// assert_eq!(upper_bound(fw, 2), 4);
+/// assert_eq!(upper_bound(fw, 4), 4);
+/// assert_eq!(upper_bound(fw, 5), 8);
+/// ```
+///
+pub fn upper_bound<T>(fenwick: &[T], target: T) -> usize
+where
+    T: AddAssign + Clone + Default + PartialOrd
+{
+    partition_point(fenwick, |sum| *sum <= target)
+}
+
+/// Returns the number of leading prefix sums satisfying `pred` , assuming `pred` holds for some
+/// prefix of them and not after. Takes `O(log(N))` by descending the implicit tree.
+fn partition_point<T, P>(fenwick: &[T], pred: P) -> usize
+where
+    T: AddAssign + Clone + Default,
+    P: Fn(&T) -> bool
+{
+    let len = fenwick.len();
+    // `pos` is the number of elements known to have prefix sum satisfying `pred`
+    let mut pos = 0usize;
+    let mut sum = T::default();
+    let mut step = if len == 0 { 0 } else { 1usize << (usize::BITS - 1 - len.leading_zeros()) };
+    while step > 0 {
+        if pos + step <= len {
+            let mut next = sum.clone();
+            next += fenwick[pos + step - 1].clone();
+            if pred(&next) {
+                sum = next;
+                pos += step;
+            }
+        }
+        step >>= 1;
+    }
+    pos
+}
+
+/// Finds the smallest index `i` such that `a[i]` is non-zero in the Fenwick tree stored in a
+/// borrowed slice (zero-based), or `None` if all elements are zero. Takes `O(log(N))` .
+///
+/// The result is only meaningful if all elements of the original array are non-negative (e.g.
+/// counts).
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{update, first_nonzero, next_nonzero_after, prev_nonzero_before};
+///
+/// // set of integers {2, 5, 6} in 0..8
+/// let fw = &mut [0u32; 8];
+/// assert_eq!(first_nonzero(fw), None);
+/// update(fw, 2, 1);
+/// update(fw, 5, 1);
+/// update(fw, 6, 1);
+/// assert_eq!(first_nonzero(fw), Some(2));
+/// assert_eq!(next_nonzero_after(fw, 2), Some(5));
+/// assert_eq!(next_nonzero_after(fw, 3), Some(5));
+/// assert_eq!(next_nonzero_after(fw, 6), None);
+/// assert_eq!(prev_nonzero_before(fw, 5), Some(2));
+/// assert_eq!(prev_nonzero_before(fw, 7), Some(6));
+/// assert_eq!(prev_nonzero_before(fw, 2), None);
+/// ```
+///
+pub fn first_nonzero<T>(fenwick: &[T]) -> Option<usize>
+where
+    T: AddAssign + Clone + Default + PartialOrd
+{
+    let i = upper_bound(fenwick, T::default());
+    (i < fenwick.len()).then_some(i)
+}
+
+/// Finds the smallest index `j > i` such that `a[j]` is non-zero in the Fenwick tree stored in a
+/// borrowed slice (zero-based), or `None` if there is none. Takes `O(log(N))` .
+///
+/// The result is only meaningful if all elements of the original array are non-negative (e.g.
+/// counts).
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// See [`first_nonzero`] .
+///
+pub fn next_nonzero_after<T>(fenwick: &[T], i: usize) -> Option<usize>
+where
+    T: AddAssign + Clone + Default + PartialOrd
+{
+    let j = upper_bound(fenwick, prefix_sum(fenwick, i));
+    (j < fenwick.len()).then_some(j)
+}
+
+/// Finds the largest index `j < i` such that `a[j]` is non-zero in the Fenwick tree stored in a
+/// borrowed slice (zero-based), or `None` if there is none. Takes `O(log(N))` .
+///
+/// The result is only meaningful if all elements of the original array are non-negative (e.g.
+/// counts).
+///
+/// # Panics
+///
+/// Panics if `i > fenwick.len()` .
+///
+/// # Examples
+///
+/// See [`first_nonzero`] .
+///
+pub fn prev_nonzero_before<T>(fenwick: &[T], i: usize) -> Option<usize>
+where
+    T: AddAssign + Clone + Default + PartialOrd
+{
+    assert!(i <= fenwick.len());
+    if i == 0 {
+        return None;
+    }
+    let sum = prefix_sum(fenwick, i - 1);
+    if sum <= T::default() {
+        return None;
+    }
+    // the last non-zero element up to `i - 1` is where the prefix sum first reaches `sum`
+    Some(lower_bound(fenwick, sum))
+}
+
+/// Finds the shortest range ending at index `end` (inclusive) whose sum is at least `s` in the
+/// Fenwick tree stored in a borrowed slice (zero-based), or `None` if there is none. Takes
+/// `O(log(N))` .
+///
+/// Conceptually finds the largest `start` such that `a[start] + ... + a[end] >= s` on the original
+/// array `a` , returning `start..end + 1` . If `s <= 0` , returns the empty range ending there.
+///
+/// The result is only meaningful if all elements of the original array are non-negative (i.e. the
+/// prefix sums are non-decreasing).
+///
+/// # Panics
+///
+/// Panics if `fenwick[end]` is out of bound.
+///
+/// # Examples
+///
+/// See [`shortest_subarray_with_sum_at_least`] .
+///
+pub fn shortest_subarray_ending_at<T>(fenwick: &[T], end: usize, s: T) -> Option<Range<usize>>
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialOrd
+{
+    if s <= T::default() {
+        assert!(end < fenwick.len());
+        return Some(end + 1..end + 1);
+    }
+    let mut target = prefix_sum(fenwick, end);
+    if target < s {
+        return None;
+    }
+    target -= s;
+    // the prefix sum before `start` is at most `target` , and `start <= end` since `s > 0`
+    Some(upper_bound(fenwick, target)..end + 1)
+}
+
+/// Finds the shortest range whose sum is at least `s` in the Fenwick tree stored in a borrowed
+/// slice (zero-based), or `None` if there is none; ties are broken by the smallest end. Takes
+/// `O(N log(N))` , i.e. one [`shortest_subarray_ending_at`] for each end.
+///
+/// If `s <= 0` , returns the empty range `0..0` . The longest such range is trivially the whole
+/// array (if any range qualifies), since all elements are non-negative.
+///
+/// The result is only meaningful if all elements of the original array are non-negative (i.e. the
+/// prefix sums are non-decreasing).
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, shortest_subarray_ending_at, shortest_subarray_with_sum_at_least};
+///
+/// let fw = &mut [2, 1, 0, 3, 1, 4, 0, 1];
+/// build(fw);
+/// assert_eq!(shortest_subarray_with_sum_at_least(fw, 4), Some(5..6));
+/// assert_eq!(shortest_subarray_with_sum_at_least(fw, 5), Some(4..6));
+/// assert_eq!(shortest_subarray_with_sum_at_least(fw, 13), None);
+/// assert_eq!(shortest_subarray_ending_at(fw, 3, 4), Some(1..4));
+/// assert_eq!(shortest_subarray_ending_at(fw, 1, 4), None);
+/// ```
+///
+pub fn shortest_subarray_with_sum_at_least<T>(fenwick: &[T], s: T) -> Option<Range<usize>>
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialOrd
+{
+    if s <= T::default() {
+        return Some(0..0);
+    }
+    let first = lower_bound(fenwick, s.clone());
+    (first..fenwick.len())
+        .filter_map(|end| shortest_subarray_ending_at(fenwick, end, s.clone()))
+        .min_by_key(|range| range.len())
+}
+
+/// Creates a view of the sub-range `range` of the Fenwick tree stored in a borrowed slice
+/// (zero-based), indexed relative to the start of `range` .
+///
+/// # Panics
+///
+/// Panics if `range` is out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, range_view};
+///
+/// let fw = &mut [1, 2, 3, 4, 5, 6, 7, 8];
+/// build(fw);
+/// let mut view = range_view(fw, 2..6); // original array: [3, 4, 5, 6]
+/// assert_eq!(view.len(), 4);
+/// assert_eq!(view.prefix_sum(0), 3);
+/// assert_eq!(view.prefix_sum(3), 18);
+/// view.update(1, 10); // original array: [1, 2, 3, 14, 5, 6, 7, 8]
+/// assert_eq!(view.prefix_sum(1), 17);
+/// ```
+///
+pub fn range_view<T>(fenwick: &mut [T], range: impl RangeBounds<usize>) -> RangeView<'_, T> {
+    let range = to_range(&range, fenwick.len());
+    RangeView {
+        fenwick,
+        start: range.start,
+        len: range.end - range.start,
+    }
+}
+
+/// View of a sub-range of a Fenwick tree stored in a borrowed slice. See [`range_view`] .
+///
+/// Index `k` of the view is index `start + k` of the original array; prefix sums of the view only
+/// include elements from `start` onwards.
+#[derive(Debug)]
+pub struct RangeView<'a, T> {
+    fenwick: &'a mut [T],
+    start: usize,
+    len: usize,
+}
+
+impl<T> RangeView<'_, T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Returns the number of elements in the view.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the view is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Conceptually performs `a[start + k] += delta` on the original array `a` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= self.len()` .
+    ///
+    pub fn update(&mut self, k: usize, delta: T) {
+        assert!(k < self.len);
+        update(self.fenwick, self.start + k, delta);
+    }
+
+    /// Conceptually calculates `a[start] + ... + a[start + k]` on the original array `a` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, k: usize) -> T {
+        assert!(k < self.len);
+        let mut sum = prefix_sum(self.fenwick, self.start + k);
+        if self.start > 0 {
+            sum -= prefix_sum(self.fenwick, self.start - 1);
+        }
+        sum
+    }
+
+    /// Conceptually returns `a[start + k]` on the original array `a` . See [`get`] .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k >= self.len()` .
+    ///
+    pub fn get(&self, k: usize) -> T {
+        assert!(k < self.len);
+        get(self.fenwick, self.start + k)
+    }
+}
+
+/// Creates a read-only view of the whole Fenwick tree stored in a borrowed slice (zero-based),
+/// which can be split recursively into parts aligned to node boundaries, e.g. to aggregate the
+/// parts in parallel with `rayon::join` or scoped threads.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::{build, split_view, SplitView};
+///
+/// fn par_sum(view: SplitView<'_, i64>) -> i64 {
+///     if view.len() <= 4 {
+///         return view.values().sum();
+///     }
+///     let (left, right) = view.split().unwrap();
+///     std::thread::scope(|s| {
+///         let left = s.spawn(|| par_sum(left));
+///         par_sum(right) + left.join().unwrap()
+///     })
+/// }
+///
+/// let mut fw: Vec<i64> = (1..=100).collect();
+/// build(&mut fw);
+/// assert_eq!(par_sum(split_view(&fw)), 5050);
+///
+/// let (left, right) = split_view(&fw).split().unwrap();
+/// assert_eq!((left.range(), right.range()), (0..64, 64..100));
+/// assert_eq!(right.sum(), (65..=100).sum());
+/// ```
+///
+pub fn split_view<T>(fenwick: &[T]) -> SplitView<'_, T> {
+    SplitView {
+        fenwick,
+        start: 0,
+        end: fenwick.len(),
+    }
+}
+
+/// Read-only view of a part of a Fenwick tree stored in a borrowed slice, aligned to node
+/// boundaries. See [`split_view`] .
+///
+/// Each node belongs to the part containing its index, so parts obtained by [`SplitView::split`]
+/// never share a node, and since parts only read the tree, traversing them concurrently cannot
+/// observe a broken invariant.
+#[derive(Debug)]
+pub struct SplitView<'a, T> {
+    fenwick: &'a [T],
+    start: usize,
+    end: usize,
+}
+
+impl<T> Clone for SplitView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SplitView<'_, T> {}
+
+impl<'a, T> SplitView<'a, T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Returns the range of indices of the part.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the number of elements in the part.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the part is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the nodes belonging to the part, i.e. `fenwick[self.range()]` . Node `i` covers
+    /// elements `node_range(i)` (see [`crate::index`]), which may start before the part.
+    pub fn nodes(&self) -> &'a [T] {
+        &self.fenwick[self.start..self.end]
+    }
+
+    /// Splits the part into two non-empty parts at a node boundary, or returns `None` if it has
+    /// fewer than two elements.
+    ///
+    /// The left part has the largest power of two elements less than `self.len()` , exactly those
+    /// covered by its last node, and the right part has the rest, which is at most as many. Parts
+    /// are thus balanced when the length is a power of two; otherwise splitting further balances
+    /// the work.
+    pub fn split(self) -> Option<(Self, Self)> {
+        let len = self.len();
+        if len < 2 {
+            return None;
+        }
+        let mid = self.start + (1 << (usize::BITS - 1 - (len - 1).leading_zeros()));
+        Some((Self { end: mid, ..self }, Self { start: mid, ..self }))
+    }
+
+    /// Conceptually calculates the sum of `a[i]` over the part on the original array `a` . Takes
+    /// `O(log(N))` .
+    pub fn sum(&self) -> T {
+        if self.is_empty() {
+            return T::default();
+        }
+        let mut sum = prefix_sum(self.fenwick, self.end - 1);
+        if self.start > 0 {
+            sum -= prefix_sum(self.fenwick, self.start - 1);
+        }
+        sum
+    }
+
+    /// Iterates over `a[i]` on the original array `a` for each `i` in the part, in order. Takes
+    /// `O(N)` amortized in total. See [`get`] .
+    pub fn values(&self) -> impl Iterator<Item = T> + 'a {
+        let fenwick = self.fenwick;
+        self.range().map(move |i| get(fenwick, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 0..256 {
+            random_one(&mut rng, len);
+        }
+    }
+
+    fn random_one<TRng: Rng>(rng: &mut TRng, len: usize) {
+        let dist = rand::distributions::Uniform::new_inclusive(-100, 100);
+        let data = rng.sample_iter(dist).take(len).collect_vec();
+        let psum = data.iter().scan(0, |s, x| {
+            *s += x;
+            Some(*s)
+        }).collect_vec();
+
+        let mut fenwick = std::vec![0i32; len];
+
+        let mut ops = data.iter().enumerate().collect_vec();
+        ops.shuffle(rng);
+        for (i, x) in ops {
+            update(&mut fenwick, i, *x);
+        }
+
+        for (i, s) in psum.iter().enumerate() {
+            assert_eq!(prefix_sum(&fenwick, i), *s);
+            assert_eq!(checked_prefix_sum(&fenwick, i), Some(*s));
+        }
+        assert_eq!(checked_prefix_sum(&fenwick, len), None);
+        let before = fenwick.clone();
+        assert_eq!(checked_update(&mut fenwick, len, 1), None);
+        assert_eq!(fenwick, before);
+        if len > 0 {
+            assert_eq!(checked_update(&mut fenwick, len - 1, 1), Some(()));
+            assert_eq!(checked_prefix_sum(&fenwick, len - 1), Some(psum[len - 1] + 1));
+        }
+    }
+
+    #[test]
+    fn wide_integer_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..48 {
+            // values whose prefix sums overflow 64 bits
+            let data = (0..len).map(|_| rng.gen_range(-(1i128 << 100)..=1 << 100)).collect_vec();
+            let mut fw = std::vec![0i128; len];
+            for (i, &x) in data.iter().enumerate() {
+                update(&mut fw, i, x);
+            }
+            let mut built = data.clone();
+            build(&mut built);
+            assert_eq!(built, fw);
+            let mut sum = 0;
+            for (i, &x) in data.iter().enumerate() {
+                sum += x;
+                assert_eq!(prefix_sum(&fw, i), sum);
+                assert_eq!(checked_prefix_sum(&fw, i), Some(sum));
+                assert_eq!(get(&fw, i), x);
+            }
+            unbuild(&mut built);
+            assert_eq!(built, data);
+
+            let data = (0..len).map(|_| rng.gen_range(0..1u128 << 120)).collect_vec();
+            let mut fw = data.clone();
+            build(&mut fw);
+            let mut sum = 0;
+            for (i, &x) in data.iter().enumerate() {
+                sum += x;
+                assert_eq!(prefix_sum(&fw, i), sum);
+                let last_positive = data[..=i].iter().rposition(|&x| x > 0);
+                assert_eq!(lower_bound(&fw, sum), last_positive.unwrap_or(0));
+                if sum > 0 {
+                    assert_eq!(upper_bound(&fw, sum - 1), lower_bound(&fw, sum));
+                }
+            }
+            assert_eq!(checked_update(&mut fw, len, 1), None);
+        }
+    }
+
+    #[test]
+    fn build_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..256 {
+            let dist = rand::distributions::Uniform::new_inclusive(-100, 100);
+            let data = (&mut rng).sample_iter(dist).take(len).collect_vec();
+            let mut expected = std::vec![0i32; len];
+            for (i, x) in data.iter().enumerate() {
+                update(&mut expected, i, *x);
+            }
+            let mut fenwick = data.clone();
+            build(&mut fenwick);
+            assert_eq!(fenwick, expected);
+            unbuild(&mut fenwick);
+            assert_eq!(fenwick, data);
+        }
+    }
+
+    #[test]
+    fn move_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let dist = rand::distributions::Uniform::new_inclusive(-100, 100);
+            let mut naive = (&mut rng).sample_iter(dist).take(len).collect_vec();
+            let mut fenwick = naive.clone();
+            build(&mut fenwick);
+            for _ in 0..len {
+                let i = rng.gen_range(0..len);
+                let j = rng.gen_range(0..len);
+                if rng.gen() {
+                    swap(&mut fenwick, i, j);
+                    naive.swap(i, j);
+                } else {
+                    move_value(&mut fenwick, i, j);
+                    let x = core::mem::take(&mut naive[i]);
+                    naive[j] += x;
+                }
+                for (k, x) in naive.iter().enumerate() {
+                    assert_eq!(get(&fenwick, k), *x);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn permute_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..256 {
+            let dist = rand::distributions::Uniform::new_inclusive(-100, 100);
+            let data = (&mut rng).sample_iter(dist).take(len).collect_vec();
+            // both a full shuffle (rebuild) and a single transposition (point updates)
+            let mut full = (0..len).collect_vec();
+            full.shuffle(&mut rng);
+            let mut one = (0..len).collect_vec();
+            if len >= 2 {
+                one.swap(0, len - 1);
+            }
+            for perm in [full, one] {
+                let mut fenwick = data.clone();
+                build(&mut fenwick);
+                permute(&mut fenwick, &perm);
+                unbuild(&mut fenwick);
+                assert_eq!(fenwick, perm.iter().map(|&p| data[p]).collect_vec());
+            }
+        }
+    }
+
+    #[test]
+    fn range_view_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..64 {
+            let dist = rand::distributions::Uniform::new_inclusive(-100, 100);
+            let mut naive = (&mut rng).sample_iter(dist).take(len).collect_vec();
+            let mut fenwick = naive.clone();
+            build(&mut fenwick);
+            let a = rng.gen_range(0..=len);
+            let b = rng.gen_range(0..=len);
+            let range = a.min(b)..a.max(b);
+            let mut view = range_view(&mut fenwick, range.clone());
+            assert_eq!(view.len(), range.len());
+            for _ in 0..view.len() {
+                let k = rng.gen_range(0..view.len());
+                let x = rng.gen_range(-100..=100);
+                view.update(k, x);
+                naive[range.start + k] += x;
+            }
+            let mut sum = 0;
+            for k in 0..view.len() {
+                sum += naive[range.start + k];
+                assert_eq!(view.prefix_sum(k), sum);
+                assert_eq!(view.get(k), naive[range.start + k]);
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn push_pop_randoms() {
+        let mut rng = thread_rng();
+        let mut fenwick = std::vec![];
+        let mut naive = std::vec![];
+        for _ in 0..4096 {
+            if rng.gen_ratio(2, 3) {
+                let x = rng.gen_range(-100..=100);
+                push(&mut fenwick, x);
+                naive.push(x);
+            } else {
+                assert_eq!(pop(&mut fenwick), naive.pop());
+            }
+            let mut expected = naive.clone();
+            build(&mut expected);
+            assert_eq!(fenwick, expected);
+        }
+    }
+
+    /// Stand-in for an arbitrary-precision number that is `Clone` but not `Copy` .
+    #[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+    struct Big(std::boxed::Box<i64>);
+
+    impl AddAssign for Big {
+        fn add_assign(&mut self, rhs: Self) {
+            *self.0 += *rhs.0;
+        }
+    }
+
+    impl AddAssign<&Big> for Big {
+        fn add_assign(&mut self, rhs: &Self) {
+            *self.0 += *rhs.0;
+        }
+    }
+
+    fn big(x: i64) -> Big {
+        Big(std::boxed::Box::new(x))
+    }
+
+    #[test]
+    fn non_copy() {
+        let mut fenwick = std::vec![Big::default(); 10];
+        update(&mut fenwick, 2, big(5));
+        update(&mut fenwick, 7, big(-1));
+        assert_eq!(prefix_sum(&fenwick, 1), big(0));
+        assert_eq!(prefix_sum(&fenwick, 2), big(5));
+        assert_eq!(prefix_sum(&fenwick, 9), big(4));
+        assert_eq!(lower_bound(&fenwick, big(3)), 2);
+    }
+
+    #[test]
+    fn non_copy_ref() {
+        let mut fenwick = std::vec![Big::default(); 10];
+        update_ref(&mut fenwick, 2, &big(5));
+        update_ref(&mut fenwick, 7, &big(-1));
+        assert_eq!(prefix_sum_ref(&fenwick, 1), big(0));
+        assert_eq!(prefix_sum_ref(&fenwick, 2), big(5));
+        assert_eq!(prefix_sum_ref(&fenwick, 9), big(4));
+        assert_eq!(prefix_sum_ref(&fenwick, 9), prefix_sum(&fenwick, 9));
+    }
+
+    #[test]
+    fn product_randoms() {
+        /// Integers modulo a prime, where every nonzero element has a multiplicative inverse.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Mod(u64);
+        const P: u64 = 1_000_000_007;
+        impl MulAssign for Mod {
+            fn mul_assign(&mut self, rhs: Self) {
+                self.0 = self.0 * rhs.0 % P;
+            }
+        }
+
+        let mut rng = thread_rng();
+        for len in 1..256 {
+            let dist = rand::distributions::Uniform::new(1, P);
+            let data = (&mut rng).sample_iter(dist).take(len).map(Mod).collect_vec();
+            let mut fenwick = std::vec![Mod(1); len];
+            for (i, x) in data.iter().enumerate() {
+                update_mul(&mut fenwick, i, *x);
+            }
+            let mut product = Mod(1);
+            for (i, x) in data.iter().enumerate() {
+                product *= *x;
+                assert_eq!(prefix_product(&fenwick, i), product);
+            }
+        }
+    }
+
+    #[test]
+    fn reset_range_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..128 {
+            for _ in 0..8 {
+                let mut data = (0..len).map(|_| rng.gen_range(-100..=100)).collect_vec();
+                let mut fenwick = data.clone();
+                build(&mut fenwick);
+                let a = rng.gen_range(0..=len);
+                // mostly short ranges, to cover both strategies
+                let b = (a + rng.gen_range(0..=len / 4 + 1)).min(len);
+                let b = if rng.gen_bool(0.3) { len } else { b };
+                reset_range(&mut fenwick, a..b);
+                data[a..b].fill(0);
+                build(&mut data);
+                assert_eq!(fenwick, data);
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn one_based_randoms() {
+        use crate::index::one_based;
+
+        let mut rng = thread_rng();
+        for len in 0..128 {
+            let data = (0..len).map(|_| rng.gen_range(-100..=100)).collect_vec();
+            let mut fenwick = data.clone();
+            build(&mut fenwick);
+            let tree = to_one_based(&fenwick);
+            assert_eq!(tree.len(), len + 1);
+            for i in 1..=len {
+                let range = one_based::node_range(i);
+                assert_eq!(tree[i], data[range.start - 1..range.end - 1].iter().sum());
+                let expected: i32 = data[..i].iter().sum();
+                assert_eq!(one_based::down(i).map(|ii| tree[ii]).sum::<i32>(), expected);
+            }
+            assert_eq!(from_one_based(&tree), Some(&fenwick[..]));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn top_k_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..64 {
+            let data = (0..len).map(|_| rng.gen_range(0..10)).collect_vec();
+            let mut fenwick = data.clone();
+            build(&mut fenwick);
+            let expected = (0..len)
+                .sorted_by_key(|&i| (std::cmp::Reverse(data[i]), i))
+                .collect_vec();
+            for k in 0..=len + 1 {
+                assert_eq!(top_k(&fenwick, k), expected[..k.min(len)]);
+            }
+        }
+        // NaN (which spreads to the nodes covering it) is skipped
+        for len in 1..32 {
+            let mut fenwick = (0..len).map(|_| rng.gen_range(0..10) as f64).collect_vec();
+            fenwick[rng.gen_range(0..len)] = f64::NAN;
+            build(&mut fenwick);
+            let mut data = fenwick.clone();
+            unbuild(&mut data);
+            let expected = (0..len)
+                .filter(|&i| !data[i].is_nan())
+                .sorted_by_key(|&i| (std::cmp::Reverse(data[i] as i64), i))
+                .collect_vec();
+            for k in 0..=len {
+                assert_eq!(top_k(&fenwick, k), expected[..k.min(expected.len())]);
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn heavy_ranges_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..64 {
+            let data = (0..len).map(|_| rng.gen_range(0..20u32)).collect_vec();
+            let mut fenwick = data.clone();
+            build(&mut fenwick);
+            for threshold in 0..=20 {
+                let mut expected: std::vec::Vec<Range<usize>> = std::vec![];
+                for (i, &x) in data.iter().enumerate() {
+                    if x > threshold {
+                        match expected.last_mut() {
+                            Some(last) if last.end == i => last.end = i + 1,
+                            _ => expected.push(i..i + 1),
+                        }
+                    }
+                }
+                assert_eq!(heavy_ranges(&fenwick, threshold), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn split_view_randoms() {
+        use crate::index::zero_based::node_range;
+
+        fn check(
+            view: SplitView<'_, i32>,
+            fenwick: &[i32],
+            data: &[i32],
+            leaves: &mut std::vec::Vec<usize>,
+        ) {
+            let range = view.range();
+            assert_eq!(view.nodes(), &fenwick[range.clone()]);
+            assert_eq!(view.sum(), data[range.clone()].iter().sum());
+            assert!(view.values().eq(data[range.clone()].iter().copied()));
+            match view.split() {
+                Some((left, right)) => {
+                    // the left part is exactly the elements covered by its last node
+                    assert_eq!(node_range(left.range().end - 1), left.range());
+                    assert!(!right.is_empty() && right.len() <= left.len());
+                    assert_eq!(left.range().start, range.start);
+                    assert_eq!(left.range().end, right.range().start);
+                    assert_eq!(right.range().end, range.end);
+                    check(left, fenwick, data, leaves);
+                    check(right, fenwick, data, leaves);
+                }
+                None => {
+                    assert!(view.len() < 2);
+                    leaves.extend(range);
+                }
+            }
+        }
+
+        let mut rng = thread_rng();
+        for len in 0..128 {
+            let data = (0..len).map(|_| rng.gen_range(-100..=100)).collect_vec();
+            let mut fenwick = data.clone();
+            build(&mut fenwick);
+            let mut leaves = std::vec![];
+            check(split_view(&fenwick), &fenwick, &data, &mut leaves);
+            assert_eq!(leaves, (0..len).collect_vec());
+        }
+    }
+
+    #[test]
+    fn lower_bound_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..256 {
+            let dist = rand::distributions::Uniform::new_inclusive(0, 3);
+            let data: std::vec::Vec<u32> = (&mut rng).sample_iter(dist).take(len).collect_vec();
+            let mut fenwick = std::vec![0u32; len];
+            for (i, x) in data.iter().enumerate() {
+                update(&mut fenwick, i, *x);
+            }
+            let total: u32 = data.iter().sum();
+            for target in 0..=total + 1 {
+                let expected = data.iter().scan(0, |s, x| {
+                    *s += x;
+                    Some(*s)
+                }).position(|s| s >= target).unwrap_or(len);
+                assert_eq!(lower_bound(&fenwick, target), expected);
+                let expected = data.iter().scan(0, |s, x| {
+                    *s += x;
+                    Some(*s)
+                }).position(|s| s > target).unwrap_or(len);
+                assert_eq!(upper_bound(&fenwick, target), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn shortest_subarray_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..48 {
+            let a: std::vec::Vec<u32> = (0..len).map(|_| rng.gen_range(0..4)).collect();
+            let mut fw = a.clone();
+            build(&mut fw);
+            for s in 0..len as u32 * 2 {
+                for end in 0..len {
+                    let expected = (0..=end).rev()
+                        .find(|&start| a[start..=end].iter().sum::<u32>() >= s)
+                        .map(|start| start..end + 1);
+                    let expected = if s == 0 { Some(end + 1..end + 1) } else { expected };
+                    assert_eq!(shortest_subarray_ending_at(&fw, end, s), expected);
+                }
+                let expected = (0..=len)
+                    .flat_map(|n| (0..=len - n).map(move |start| start..start + n))
+                    .find(|range| a[range.clone()].iter().sum::<u32>() >= s);
+                assert_eq!(shortest_subarray_with_sum_at_least(&fw, s), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn nonzero_randoms() {
+        let mut rng = thread_rng();
+        for len in 0..128 {
+            let dist = rand::distributions::Uniform::new_inclusive(0, 1);
+            let data: std::vec::Vec<u32> = (&mut rng).sample_iter(dist).take(len).collect_vec();
+            let mut fenwick = data.clone();
+            build(&mut fenwick);
+            assert_eq!(first_nonzero(&fenwick), data.iter().position(|&x| x > 0));
+            for i in 0..len {
+                let expected = (i + 1..len).find(|&j| data[j] > 0);
+                assert_eq!(next_nonzero_after(&fenwick, i), expected);
+            }
+            for i in 0..=len {
+                let expected = (0..i).rev().find(|&j| data[j] > 0);
+                assert_eq!(prev_nonzero_before(&fenwick, i), expected);
+            }
         }
     }
 }
@@ -0,0 +1,109 @@
+//! Conversions between Fenwick trees and Arrow primitive arrays, e.g. for cumulative sums with
+//! updates over columnar data.
+//!
+//! Trees are plain `Vec` backing arrays, as used by the [`array`](crate::array) module. Null
+//! entries are treated as the additive identity (zero). Converting back takes ownership of the
+//! backing array and wraps it in an Arrow buffer without copying.
+//!
+//! # Examples
+//!
+//! ```
+//! use arrow_array::Int64Array;
+//! use arrow_array::types::Int64Type;
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::arrow::{from_primitive_array, into_primitive_array, prefix_sum_array};
+//!
+//! let column = Int64Array::from(vec![Some(3), None, Some(4), Some(1)]);
+//! let mut fw = from_primitive_array(&column);
+//! assert_eq!(prefix_sum(&fw, 3), 8);
+//! update(&mut fw, 1, 10);
+//! assert_eq!(prefix_sum_array::<Int64Type>(&fw), Int64Array::from(vec![3, 13, 17, 18]));
+//! assert_eq!(into_primitive_array::<Int64Type>(fw), Int64Array::from(vec![3, 10, 4, 1]));
+//! ```
+//!
+
+use core::ops::{AddAssign, SubAssign};
+
+use alloc::vec::Vec;
+
+use arrow_array::{Array, ArrowPrimitiveType, PrimitiveArray};
+
+use crate::array::{build, unbuild};
+
+/// Creates the backing array of a Fenwick tree from the original array `a` , with null entries
+/// treated as zero. Takes `O(N)` .
+pub fn from_primitive_array<P>(a: &PrimitiveArray<P>) -> Vec<P::Native>
+where
+    P: ArrowPrimitiveType,
+    P::Native: AddAssign
+{
+    let mut fenwick = a.values().to_vec();
+    if let Some(nulls) = a.nulls() {
+        for i in nulls.iter().enumerate().filter(|(_, valid)| !valid).map(|(i, _)| i) {
+            fenwick[i] = P::Native::default();
+        }
+    }
+    build(&mut fenwick);
+    fenwick
+}
+
+/// Converts the backing array of a Fenwick tree back into the original array, without nulls.
+/// Takes `O(N)` and does not copy.
+pub fn into_primitive_array<P>(mut fenwick: Vec<P::Native>) -> PrimitiveArray<P>
+where
+    P: ArrowPrimitiveType,
+    P::Native: AddAssign + SubAssign
+{
+    unbuild(&mut fenwick);
+    PrimitiveArray::new(fenwick.into(), None)
+}
+
+/// Returns the prefix sums `a[0] + ... + a[i]` at every index `i` , without nulls. Takes `O(N)` .
+pub fn prefix_sum_array<P>(fenwick: &[P::Native]) -> PrimitiveArray<P>
+where
+    P: ArrowPrimitiveType,
+    P::Native: AddAssign
+{
+    let mut sums = fenwick.to_vec();
+    for i in 0..sums.len() {
+        // prefix sum at `i` is node `i` plus prefix sum at `(i & (i + 1)) - 1`
+        let start = i & (i + 1);
+        if start > 0 {
+            let x = sums[start - 1];
+            sums[i] += x;
+        }
+    }
+    PrimitiveArray::new(sums.into(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use arrow_array::types::Int32Type;
+    use arrow_array::Int32Array;
+    use rand::prelude::*;
+
+    use crate::array::prefix_sum;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 0..48 {
+            let values: std::vec::Vec<Option<i32>> = (0..len)
+                .map(|_| rng.gen_bool(0.8).then(|| rng.gen_range(-100..=100)))
+                .collect();
+            let column = Int32Array::from(values.clone());
+            let fw = from_primitive_array(&column);
+            let original: std::vec::Vec<i32> = values.iter().map(|x| x.unwrap_or(0)).collect();
+            let mut sum = 0;
+            for (i, x) in original.iter().enumerate() {
+                sum += x;
+                assert_eq!(prefix_sum(&fw, i), sum);
+                assert_eq!(prefix_sum_array::<Int32Type>(&fw).value(i), sum);
+            }
+            assert_eq!(into_primitive_array::<Int32Type>(fw), Int32Array::from(original));
+        }
+    }
+}
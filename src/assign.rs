@@ -0,0 +1,232 @@
+//! Array supporting range assignment (setting all elements in a range to one value) and prefix
+//! sums.
+//!
+//! A plain Fenwick tree cannot express range assignment, since the change to each element depends
+//! on its current value. Here the array is kept as an ordered map of runs of equal values, next to
+//! a pair of Fenwick trees supporting range addition. Assigning a range adds the difference over
+//! each run it overwrites; each assignment creates at most three runs, so this takes amortized
+//! `O(log(N))` per assignment. Prefix sums take `O(log(N))` .
+//!
+//! Elements only need addition and subtraction, including of negative differences (e.g. signed
+//! integers or floats, but not unsigned integers): the second tree, which is weighted by index,
+//! multiplies by repeated doubling. With floating-point elements, prefix sums accumulate rounding
+//! errors from the differences, while [`RangeAssign::get`] is exact.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::assign::RangeAssign;
+//!
+//! let mut a = RangeAssign::new(10);
+//! a.assign(2..8, 3); // [0, 0, 3, 3, 3, 3, 3, 3, 0, 0]
+//! a.assign(5..10, -1); // [0, 0, 3, 3, 3, -1, -1, -1, -1, -1]
+//! assert_eq!(a.get(4), 3);
+//! assert_eq!(a.get(5), -1);
+//! assert_eq!(a.prefix_sum(4), 9);
+//! assert_eq!(a.prefix_sum(9), 4);
+//! ```
+//!
+
+use core::ops::{AddAssign, Range, RangeBounds, SubAssign};
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, update};
+use crate::index::to_range;
+
+/// Array supporting range assignment and prefix sums.
+///
+/// Two arrays are equal if and only if they have the same elements.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RangeAssign<T> {
+    /// Value of each run of equal values, keyed by the start of the run. Each run extends up to the
+    /// start of the next one.
+    runs: BTreeMap<usize, T>,
+    /// Fenwick tree over the difference array `d` , where `a[i] == d[0] + ... + d[i]` .
+    diff: Vec<T>,
+    /// Fenwick tree over `i * d[i]` .
+    weighted: Vec<T>,
+}
+
+impl<T> RangeAssign<T>
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialEq
+{
+    /// Creates an array of `len` zeros.
+    pub fn new(len: usize) -> Self {
+        let mut runs = BTreeMap::new();
+        if len > 0 {
+            runs.insert(0, T::default());
+        }
+        Self {
+            runs,
+            diff: vec![T::default(); len],
+            weighted: vec![T::default(); len],
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.diff.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.diff.is_empty()
+    }
+
+    /// Adds `delta` to `a[range]` .
+    fn add(&mut self, range: Range<usize>, delta: T) {
+        let Range { start, end } = range;
+        update(&mut self.diff, start, delta.clone());
+        update(&mut self.weighted, start, times(delta.clone(), start));
+        if end < self.len() {
+            let mut negated = T::default();
+            negated -= delta;
+            update(&mut self.diff, end, negated.clone());
+            update(&mut self.weighted, end, times(negated, end));
+        }
+    }
+
+    /// Makes sure a run starts at `i` , unless `i` is the end of the array.
+    fn split(&mut self, i: usize) {
+        if i < self.len() && !self.runs.contains_key(&i) {
+            let (_, value) = self.runs.range(..i).next_back().unwrap();
+            self.runs.insert(i, value.clone());
+        }
+    }
+
+    /// Sets all elements in `range` to `value` . Takes amortized `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn assign(&mut self, range: impl RangeBounds<usize>, value: T) {
+        let Range { start, end } = to_range(&range, self.len());
+        if start == end {
+            return;
+        }
+        self.split(start);
+        self.split(end);
+        let overwritten: Vec<(usize, T)> = self.runs.range(start..end)
+            .map(|(&s, v)| (s, v.clone()))
+            .collect();
+        for (k, (s, old)) in overwritten.iter().enumerate() {
+            let e = overwritten.get(k + 1).map_or(end, |&(next, _)| next);
+            let mut delta = value.clone();
+            delta -= old.clone();
+            self.add(*s..e, delta);
+            self.runs.remove(s);
+        }
+        // merge with neighboring runs of the same value, so that the runs (and equality) are
+        // canonical
+        if self.runs.get(&end) == Some(&value) {
+            self.runs.remove(&end);
+        }
+        if self.runs.range(..start).next_back().is_some_and(|(_, v)| *v == value) {
+            self.runs.remove(&start);
+        } else {
+            self.runs.insert(start, value);
+        }
+    }
+
+    /// Returns `a[i]` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn get(&self, i: usize) -> T {
+        assert!(i < self.len());
+        self.runs.range(..=i).next_back().unwrap().1.clone()
+    }
+
+    /// Calculates `a[0] + ... + a[i]` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        // sum over j <= i of (i + 1 - j) * d[j]
+        let mut sum = times(prefix_sum(&self.diff, i), i + 1);
+        sum -= prefix_sum(&self.weighted, i);
+        sum
+    }
+}
+
+/// Returns `n * x` by repeated doubling, in `O(log(n))` additions.
+fn times<T: AddAssign + Clone + Default>(x: T, mut n: usize) -> T {
+    let mut product = T::default();
+    let mut power = x;
+    while n > 0 {
+        if n & 1 == 1 {
+            product += power.clone();
+        }
+        n >>= 1;
+        if n > 0 {
+            let double = power.clone();
+            power += double;
+        }
+    }
+    product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut a = RangeAssign::new(len);
+            let mut naive = std::vec![0i64; len];
+            for _ in 0..len * 2 {
+                let x = rng.gen_range(0..=len);
+                let y = rng.gen_range(0..=len);
+                let range = x.min(y)..x.max(y);
+                let value = rng.gen_range(-100..=100);
+                a.assign(range.clone(), value);
+                naive[range].fill(value);
+                assert!(a.runs.len() <= len);
+            }
+            let mut sum = 0;
+            for (i, &x) in naive.iter().enumerate() {
+                sum += x;
+                assert_eq!(a.get(i), x);
+                assert_eq!(a.prefix_sum(i), sum);
+            }
+            let mut b = RangeAssign::new(len);
+            for (i, &x) in naive.iter().enumerate() {
+                b.assign(i..i + 1, x);
+            }
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn generic_elements() {
+        for n in [0, 1, 2, 3, 7, 8, 1000, usize::MAX] {
+            assert_eq!(times(3u128, n), 3 * n as u128);
+        }
+        let mut a = RangeAssign::new(6);
+        a.assign(1..5, 0.5);
+        a.assign(3.., -2.25);
+        assert_eq!(a.get(2), 0.5);
+        assert_eq!(a.prefix_sum(5), 1.0 - 3.0 * 2.25);
+        let mut a = RangeAssign::new(4);
+        a.assign(..3, 7i8);
+        a.assign(1..2, 1);
+        assert_eq!(a.prefix_sum(3), 15);
+    }
+}
@@ -0,0 +1,201 @@
+//! Bitmap index of rows by category with cumulative counts, e.g. for filtered counts in a query
+//! engine.
+//!
+//! A [`CumulativeBitmapIndex`] assigns each row id in `0..N` at most one category. Each category
+//! that has been used keeps a [`BitSet`] of its rows, i.e. one bit per row plus a Fenwick tree of
+//! counts per 64 rows, found through an ordered map from category to bitmap. Counting the rows of a
+//! category up to a given row id, inserting and deleting rows all take `O(log(C) + log(N / 64))`
+//! for `C` categories.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::bitmap::CumulativeBitmapIndex;
+//!
+//! let mut index = CumulativeBitmapIndex::new(1000);
+//! for (row, color) in [(3, "red"), (10, "blue"), (64, "red"), (500, "red"), (999, "blue")] {
+//!     index.insert(row, color);
+//! }
+//! assert_eq!(index.count_up_to(&"red", 64), 2);
+//! assert_eq!(index.count_up_to(&"red", 999), 3);
+//! assert_eq!(index.count_up_to(&"green", 999), 0);
+//!
+//! assert_eq!(index.insert(64, "blue"), Some("red"));
+//! assert_eq!(index.remove(999), Some("blue"));
+//! assert_eq!(index.count_up_to(&"red", 999), 2);
+//! assert_eq!(index.count(&"blue"), 2);
+//! assert_eq!(index.nth_row(&"blue", 1), Some(64));
+//! ```
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::bitset::BitSet;
+
+/// Bitmap index of rows `0..N` by category, with cumulative counts.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CumulativeBitmapIndex<C> {
+    /// Slot of each category in `bitmaps` .
+    slots: BTreeMap<C, usize>,
+    /// Category and bitmap of the rows of each slot.
+    bitmaps: Vec<(C, BitSet)>,
+    /// Slot of the category of each row, if any.
+    rows: Vec<Option<usize>>,
+    count: usize,
+}
+
+impl<C: Ord + Clone> CumulativeBitmapIndex<C> {
+    /// Creates an index over row ids `0..len` , with no rows.
+    pub fn new(len: usize) -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            bitmaps: Vec::new(),
+            rows: vec![None; len],
+            count: 0,
+        }
+    }
+
+    /// Returns the number of row ids, i.e. `N` .
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns `true` if there are no row ids, i.e. `N == 0` .
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Returns the number of rows, of any category.
+    pub fn rows(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the category of `row` , or `None` if there is no such row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row >= self.len()` .
+    ///
+    pub fn category(&self, row: usize) -> Option<&C> {
+        self.rows[row].map(|slot| &self.bitmaps[slot].0)
+    }
+
+    /// Inserts `row` with `category` , returning its previous category if the row existed. Takes
+    /// `O(log(C) + log(N / 64))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row >= self.len()` .
+    ///
+    pub fn insert(&mut self, row: usize, category: C) -> Option<C> {
+        assert!(row < self.len());
+        let len = self.len();
+        let next = self.bitmaps.len();
+        let slot = *self.slots.entry(category.clone()).or_insert(next);
+        if slot == next {
+            self.bitmaps.push((category.clone(), BitSet::new(len)));
+        }
+        let old = match self.rows[row] {
+            Some(old) if old == slot => return Some(category),
+            Some(old) => {
+                self.bitmaps[old].1.remove(row);
+                Some(self.bitmaps[old].0.clone())
+            }
+            None => {
+                self.count += 1;
+                None
+            }
+        };
+        self.bitmaps[slot].1.insert(row);
+        self.rows[row] = Some(slot);
+        old
+    }
+
+    /// Deletes `row` , returning its category if the row existed. Takes `O(log(N / 64))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row >= self.len()` .
+    ///
+    pub fn remove(&mut self, row: usize) -> Option<C> {
+        let slot = self.rows[row].take()?;
+        let (category, bitmap) = &mut self.bitmaps[slot];
+        bitmap.remove(row);
+        self.count -= 1;
+        Some(category.clone())
+    }
+
+    /// Returns the bitmap of the rows of `category` , if it has ever had rows.
+    fn bitmap(&self, category: &C) -> Option<&BitSet> {
+        self.slots.get(category).map(|&slot| &self.bitmaps[slot].1)
+    }
+
+    /// Returns the number of rows of `category` .
+    pub fn count(&self, category: &C) -> usize {
+        self.bitmap(category).map_or(0, BitSet::count)
+    }
+
+    /// Returns the number of rows of `category` with id at most `row` . Takes
+    /// `O(log(C) + log(N / 64))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row >= self.len()` .
+    ///
+    pub fn count_up_to(&self, category: &C, row: usize) -> usize {
+        assert!(row < self.len());
+        self.bitmap(category).map_or(0, |bitmap| bitmap.rank(row + 1))
+    }
+
+    /// Returns the `k`-th smallest (zero-based) row id of `category` , or `None` if
+    /// `k >= self.count(category)` . Takes `O(log(C) + log(N / 64))` .
+    pub fn nth_row(&self, category: &C, k: usize) -> Option<usize> {
+        self.bitmap(category)?.select(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in [0, 1, 63, 64, 65, 200] {
+            let mut index = CumulativeBitmapIndex::new(len);
+            let mut naive: std::vec::Vec<Option<u8>> = std::vec![None; len];
+            for _ in 0..len * 3 {
+                let row = rng.gen_range(0..len);
+                if rng.gen_bool(0.7) {
+                    let category = rng.gen_range(0..4);
+                    assert_eq!(index.insert(row, category), naive[row].replace(category));
+                } else {
+                    assert_eq!(index.remove(row), naive[row].take());
+                }
+            }
+            assert_eq!(index.rows(), naive.iter().flatten().count());
+            for category in 0..5 {
+                let rows: std::vec::Vec<usize> =
+                    (0..len).filter(|&row| naive[row] == Some(category)).collect();
+                assert_eq!(index.count(&category), rows.len());
+                for (row, expected_category) in naive.iter().enumerate() {
+                    let expected = rows.iter().filter(|&&r| r <= row).count();
+                    assert_eq!(index.count_up_to(&category, row), expected);
+                    assert_eq!(index.category(row), expected_category.as_ref());
+                }
+                for (k, &row) in rows.iter().enumerate() {
+                    assert_eq!(index.nth_row(&category, k), Some(row));
+                }
+                assert_eq!(index.nth_row(&category, rows.len()), None);
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Dynamic set of integers in `0..N` with rank and select, backed by a packed bitset and a Fenwick
+//! tree of per-word counts.
+//!
+//! Membership takes one bit per integer, plus one `usize` count per 64 integers for the Fenwick
+//! tree. Inserting, removing, ranking and selecting all take `O(log(N / 64))` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::bitset::BitSet;
+//!
+//! let mut set = BitSet::new(1000);
+//! for i in [3, 64, 65, 500, 999] {
+//!     assert!(set.insert(i));
+//! }
+//! assert!(!set.insert(64));
+//! assert!(set.remove(65));
+//! assert!(!set.contains(65));
+//! assert_eq!(set.count(), 4);
+//! assert_eq!(set.rank(64), 1);
+//! assert_eq!(set.rank(65), 2);
+//! assert_eq!(set.select(2), Some(500));
+//! assert_eq!(set.select(4), None);
+//! assert!(set.iter().eq([3, 64, 500, 999]));
+//! ```
+//!
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, update, upper_bound};
+use crate::index::zero_based::up;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Dynamic set of integers in `0..N` with rank and select.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    len: usize,
+    words: Vec<u64>,
+    /// Fenwick tree over the number of set bits in each word.
+    fenwick: Vec<usize>,
+}
+
+impl BitSet {
+    /// Creates an empty set of integers in `0..len` .
+    pub fn new(len: usize) -> Self {
+        let words = len.div_ceil(WORD_BITS);
+        Self {
+            len,
+            words: vec![0; words],
+            fenwick: vec![0; words],
+        }
+    }
+
+    /// Returns the number of integers the set can hold, i.e. `N` .
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set can hold no integers, i.e. `N == 0` .
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of integers in the set. Takes `O(log(N / 64))` .
+    pub fn count(&self) -> usize {
+        self.rank(self.len)
+    }
+
+    /// Returns `true` if `i` is in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn contains(&self, i: usize) -> bool {
+        assert!(i < self.len);
+        self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    /// Inserts `i` into the set, returning `true` if it was not in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn insert(&mut self, i: usize) -> bool {
+        if self.contains(i) {
+            return false;
+        }
+        self.words[i / WORD_BITS] |= 1 << (i % WORD_BITS);
+        update(&mut self.fenwick, i / WORD_BITS, 1);
+        true
+    }
+
+    /// Removes `i` from the set, returning `true` if it was in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn remove(&mut self, i: usize) -> bool {
+        if !self.contains(i) {
+            return false;
+        }
+        self.words[i / WORD_BITS] &= !(1 << (i % WORD_BITS));
+        for ii in up(i / WORD_BITS, self.fenwick.len()) {
+            self.fenwick[ii] -= 1;
+        }
+        true
+    }
+
+    /// Returns the number of integers in the set that are less than `i` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i > self.len()` .
+    ///
+    pub fn rank(&self, i: usize) -> usize {
+        assert!(i <= self.len);
+        let (w, b) = (i / WORD_BITS, i % WORD_BITS);
+        let mut rank = if w == 0 { 0 } else { prefix_sum(&self.fenwick, w - 1) };
+        if b > 0 {
+            rank += (self.words[w] & ((1 << b) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    /// Returns the `k`-th smallest (zero-based) integer in the set, or `None` if
+    /// `k >= self.count()` . Takes `O(log(N / 64))` .
+    pub fn select(&self, k: usize) -> Option<usize> {
+        let w = upper_bound(&self.fenwick, k);
+        if w == self.words.len() {
+            return None;
+        }
+        let before = if w == 0 { 0 } else { prefix_sum(&self.fenwick, w - 1) };
+        let mut word = self.words[w];
+        for _ in 0..k - before {
+            word &= word - 1; // clear lowest set bit
+        }
+        Some(w * WORD_BITS + word.trailing_zeros() as usize)
+    }
+
+    /// Iterates over the integers in the set in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut word = word;
+            core::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let b = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(w * WORD_BITS + b)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in [0, 1, 63, 64, 65, 200, 1000] {
+            let mut set = BitSet::new(len);
+            let mut naive = std::collections::BTreeSet::new();
+            for _ in 0..len * 2 {
+                let i = rng.gen_range(0..len);
+                if rng.gen_bool(0.6) {
+                    assert_eq!(set.insert(i), naive.insert(i));
+                } else {
+                    assert_eq!(set.remove(i), naive.remove(&i));
+                }
+            }
+            assert_eq!(set.count(), naive.len());
+            assert!(set.iter().eq(naive.iter().copied()));
+            for i in 0..len {
+                assert_eq!(set.contains(i), naive.contains(&i));
+            }
+            for i in 0..=len {
+                assert_eq!(set.rank(i), naive.range(..i).count());
+            }
+            for (k, &i) in naive.iter().enumerate() {
+                assert_eq!(set.select(k), Some(i));
+            }
+            assert_eq!(set.select(naive.len()), None);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Booking counter over a fixed number of time slots, counting how many bookings overlap each slot.
+//!
+//! Booking a range of slots is a range update on the original array of overlap counts, which is
+//! implemented as two point updates on a Fenwick tree over its difference array. The overlap count
+//! of one slot is then a prefix sum.
+//!
+//! A Fenwick tree cannot maintain prefix maximums under range updates, so bookings also go to a
+//! segment tree of maximum overlap counts, where each node keeps the bookings covering all of its
+//! slots. Booking and the maximum overlap up to a slot then both take `O(log(N))` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::booking::BookingCounter;
+//!
+//! let mut cal = BookingCounter::new(24);
+//! cal.book(9..12);
+//! cal.book(10..14);
+//! cal.book(11..12);
+//! assert_eq!(cal.overlap_at(8), 0);
+//! assert_eq!(cal.overlap_at(9), 1);
+//! assert_eq!(cal.overlap_at(11), 3);
+//! assert_eq!(cal.overlap_at(12), 1);
+//! assert_eq!(cal.overlap_at(14), 0);
+//! assert_eq!(cal.max_overlap_upto(10), 2);
+//! assert_eq!(cal.max_overlap_upto(23), 3);
+//! ```
+//!
+
+use core::hash::{Hash, Hasher};
+use core::ops::{Range, RangeBounds};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, update};
+use crate::index::to_range;
+
+/// Booking counter over a fixed number of time slots.
+///
+/// Two counters are equal if and only if they have the same overlap count at every slot.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default)]
+pub struct BookingCounter {
+    /// Fenwick tree over the difference array of overlap counts.
+    fenwick: Vec<isize>,
+    /// Segment tree (node `1` is the root, node `n` has children `2n` and `2n + 1`) of the maximum
+    /// overlap count over the slots of each node, counting only bookings added at the node or
+    /// below.
+    max: Vec<usize>,
+    /// Number of bookings covering all slots of each node but not of its parent.
+    add: Vec<usize>,
+}
+
+impl BookingCounter {
+    /// Creates a booking counter with `slots` empty slots.
+    pub fn new(slots: usize) -> Self {
+        Self {
+            fenwick: vec![0; slots],
+            max: vec![0; 4 * slots],
+            add: vec![0; 4 * slots],
+        }
+    }
+
+    /// Returns the number of slots.
+    pub fn slots(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Books all slots in `range` . Takes `O(log(N))`.
+    ///
+    /// Booking an empty range has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn book(&mut self, range: impl RangeBounds<usize>) {
+        let Range { start, end } = to_range(&range, self.slots());
+        if start == end {
+            return;
+        }
+        update(&mut self.fenwick, start, 1);
+        if end < self.slots() {
+            update(&mut self.fenwick, end, -1);
+        }
+        self.book_node(1, 0..self.slots(), start..end);
+    }
+
+    /// Adds a booking of `range` to node `n` covering `slots` , and to its descendants.
+    fn book_node(&mut self, n: usize, slots: Range<usize>, range: Range<usize>) {
+        if range.end <= slots.start || slots.end <= range.start {
+            return;
+        }
+        if range.start <= slots.start && slots.end <= range.end {
+            self.add[n] += 1;
+            self.max[n] += 1;
+            return;
+        }
+        let mid = slots.start + (slots.end - slots.start) / 2;
+        self.book_node(2 * n, slots.start..mid, range.clone());
+        self.book_node(2 * n + 1, mid..slots.end, range);
+        self.max[n] = self.add[n] + self.max[2 * n].max(self.max[2 * n + 1]);
+    }
+
+    /// Returns the number of bookings that overlap slot `i`. Takes `O(log(N))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bound.
+    ///
+    pub fn overlap_at(&self, i: usize) -> usize {
+        prefix_sum(&self.fenwick, i) as usize
+    }
+
+    /// Returns the maximum number of bookings that overlap any one slot up to and including `i`.
+    /// Takes `O(log(N))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bound.
+    ///
+    pub fn max_overlap_upto(&self, i: usize) -> usize {
+        assert!(i < self.slots());
+        self.max_node(1, 0..self.slots(), i + 1)
+    }
+
+    /// Returns the maximum overlap over the slots of node `n` (covering `slots`) before `end` ,
+    /// counting only bookings added at the node or below.
+    fn max_node(&self, n: usize, slots: Range<usize>, end: usize) -> usize {
+        if end <= slots.start {
+            // no slots, and overlap counts are never negative
+            return 0;
+        }
+        if slots.end <= end {
+            return self.max[n];
+        }
+        let mid = slots.start + (slots.end - slots.start) / 2;
+        let left = self.max_node(2 * n, slots.start..mid, end);
+        let right = self.max_node(2 * n + 1, mid..slots.end, end);
+        self.add[n] + left.max(right)
+    }
+}
+
+impl PartialEq for BookingCounter {
+    fn eq(&self, other: &Self) -> bool {
+        // the segment tree depends on how bookings were split, not only on the overlap counts
+        self.fenwick == other.fenwick
+    }
+}
+
+impl Eq for BookingCounter {}
+
+impl Hash for BookingCounter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fenwick.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for slots in 1..64 {
+            let mut cal = BookingCounter::new(slots);
+            let mut naive = std::vec![0usize; slots];
+            for _ in 0..slots {
+                let a = rng.gen_range(0..=slots);
+                let b = rng.gen_range(0..=slots);
+                let range = a.min(b)..a.max(b);
+                cal.book(range.clone());
+                for x in &mut naive[range] {
+                    *x += 1;
+                }
+            }
+            // the same overlap counts, booked one slot at a time
+            let mut same = BookingCounter::new(slots);
+            for (i, &overlap) in naive.iter().enumerate() {
+                for _ in 0..overlap {
+                    same.book(i..=i);
+                }
+            }
+            assert_eq!(cal, same);
+            for i in 0..slots {
+                assert_eq!(cal.overlap_at(i), naive[i]);
+                assert_eq!(cal.max_overlap_upto(i), *naive[..=i].iter().max().unwrap());
+            }
+        }
+    }
+}
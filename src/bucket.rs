@@ -0,0 +1,239 @@
+//! Quantization of continuous `f64` keys (e.g. latencies) into buckets, for the bucketed counting
+//! types such as [`Histogram`](crate::histogram::Histogram) and
+//! [`Leaderboard`](crate::leaderboard::Leaderboard).
+//!
+//! A [`BucketedKey`] divides the keys `min..max` into half-open buckets, either of equal width
+//! ([`BucketedKey::linear`]) or growing geometrically by a fixed ratio ([`BucketedKey::log`]),
+//! which bounds the relative error instead. Each bucket `b` covers exactly the keys in
+//! [`range(b)`](BucketedKey::range), including its start and excluding its end, with no rounding
+//! gaps or overlaps between buckets. The range is also the inverse mapping for reporting.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::bucket::BucketedKey;
+//! use fenwick::histogram::Histogram;
+//!
+//! // latencies from 1ms to 10s, with buckets 10% apart
+//! let key = BucketedKey::log(1.0, 10_000.0, 1.1);
+//! let mut h = Histogram::new(key.buckets());
+//! for ms in [1.5, 2.0, 2.1, 3.7, 250.0, 9_999.0, 12_000.0] {
+//!     h.record(key.bucket_clamped(ms));
+//! }
+//! let p50 = key.range(h.percentile(50.0).unwrap());
+//! assert!(p50.contains(&3.7));
+//! assert!(p50.end / p50.start <= 1.1 + 1e-9);
+//! assert_eq!(key.bucket(0.5), None);
+//!
+//! let key = BucketedKey::linear(0.0, 1.0, 10);
+//! assert_eq!(key.bucket(0.3), Some(3));
+//! assert_eq!(key.bucket(1.0), None);
+//! assert_eq!(key.bucket_clamped(1.0), 9);
+//! assert_eq!(key.range(3), 0.3..0.4);
+//! ```
+//!
+
+use core::ops::Range;
+
+use alloc::vec::Vec;
+
+use crate::leaderboard::ScoreToBucket;
+
+/// Mapping from `f64` keys to buckets, with linear or logarithmic scale.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketedKey {
+    scale: Scale,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Scale {
+    Linear { min: f64, max: f64, buckets: usize },
+    /// Start of each bucket, followed by the end of the last bucket.
+    Log { bounds: Vec<f64> },
+}
+
+impl BucketedKey {
+    /// Divides `min..max` into `buckets` buckets of equal width.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` or `max` is not finite, or `min >= max` , or `buckets == 0` .
+    ///
+    pub fn linear(min: f64, max: f64, buckets: usize) -> Self {
+        assert!(min.is_finite() && max.is_finite() && min < max);
+        assert!(buckets > 0);
+        Self {
+            scale: Scale::Linear { min, max, buckets },
+        }
+    }
+
+    /// Divides `min..max` into buckets where each starts `growth` times the start of the previous
+    /// one, i.e. with a relative width of `growth - 1` ; the last bucket ends at `max` and may be
+    /// narrower. Takes `O(B)` for `B` buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` or `max` is not finite, or `min <= 0` , or `min >= max` , or `growth` is not
+    /// finite, or `growth <= 1` .
+    ///
+    pub fn log(min: f64, max: f64, growth: f64) -> Self {
+        assert!(min.is_finite() && max.is_finite() && 0.0 < min && min < max);
+        assert!(growth.is_finite() && growth > 1.0);
+        let mut bounds = Vec::new();
+        let mut start = min;
+        while start < max {
+            bounds.push(start);
+            // a `growth` very close to `1` may round back to `start` , so step to at least the next
+            // float above it
+            start = (start * growth).max(f64::from_bits(start.to_bits() + 1));
+        }
+        bounds.push(max);
+        Self {
+            scale: Scale::Log { bounds },
+        }
+    }
+
+    /// Returns the number of buckets.
+    pub fn buckets(&self) -> usize {
+        match &self.scale {
+            Scale::Linear { buckets, .. } => *buckets,
+            Scale::Log { bounds } => bounds.len() - 1,
+        }
+    }
+
+    /// Returns the range of keys covered by all buckets.
+    pub fn key_range(&self) -> Range<f64> {
+        self.start(0)..self.start(self.buckets())
+    }
+
+    /// Returns the start of bucket `b` , or the end of the last bucket if `b == self.buckets()` .
+    fn start(&self, b: usize) -> f64 {
+        match &self.scale {
+            Scale::Linear { min, max, buckets } => {
+                if b == *buckets {
+                    *max
+                } else {
+                    min + (max - min) * (b as f64 / *buckets as f64)
+                }
+            }
+            Scale::Log { bounds } => bounds[b],
+        }
+    }
+
+    /// Returns the bucket containing `key` , or `None` if `key` is outside of
+    /// [`self.key_range()`](BucketedKey::key_range) or is NaN. Takes `O(1)` for linear scale, or
+    /// `O(log(B))` for logarithmic scale.
+    pub fn bucket(&self, key: f64) -> Option<usize> {
+        if !self.key_range().contains(&key) {
+            return None;
+        }
+        let bucket = match &self.scale {
+            Scale::Linear { min, max, buckets } => {
+                // the estimate may be off by one due to rounding, so correct it against the bounds
+                let mut b = (((key - min) / (max - min)) * *buckets as f64) as usize;
+                b = b.min(buckets - 1);
+                while b > 0 && key < self.start(b) {
+                    b -= 1;
+                }
+                while b + 1 < *buckets && key >= self.start(b + 1) {
+                    b += 1;
+                }
+                b
+            }
+            Scale::Log { bounds } => bounds.partition_point(|&start| start <= key) - 1,
+        };
+        Some(bucket)
+    }
+
+    /// Returns the bucket containing `key` , where keys below the range are counted in the first
+    /// bucket and keys above it (including the end) in the last bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is NaN.
+    ///
+    pub fn bucket_clamped(&self, key: f64) -> usize {
+        assert!(!key.is_nan());
+        let range = self.key_range();
+        if key < range.start {
+            0
+        } else if key >= range.end {
+            self.buckets() - 1
+        } else {
+            self.bucket(key).unwrap()
+        }
+    }
+
+    /// Returns the range of keys in bucket `b` , e.g. for reporting.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b >= self.buckets()` .
+    ///
+    pub fn range(&self, b: usize) -> Range<f64> {
+        assert!(b < self.buckets());
+        self.start(b)..self.start(b + 1)
+    }
+}
+
+/// Maps scores with [`BucketedKey::bucket_clamped`] .
+impl ScoreToBucket for BucketedKey {
+    type Score = f64;
+
+    fn buckets(&self) -> usize {
+        self.buckets()
+    }
+
+    fn bucket(&self, score: &f64) -> usize {
+        self.bucket_clamped(*score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    fn check(key: &BucketedKey, rng: &mut impl Rng) {
+        let n = key.buckets();
+        for b in 0..n {
+            let range = key.range(b);
+            assert!(range.start < range.end);
+            // bounds belong to exactly one bucket
+            assert_eq!(key.bucket(range.start), Some(b));
+            assert_eq!(key.bucket(range.end.next_down()), Some(b));
+            if b + 1 < n {
+                assert_eq!(key.range(b + 1).start, range.end);
+            }
+        }
+        let range = key.key_range();
+        assert_eq!(key.bucket(range.end), None);
+        assert_eq!(key.bucket(f64::NAN), None);
+        assert_eq!(key.bucket_clamped(range.end), n - 1);
+        assert_eq!(key.bucket_clamped(f64::NEG_INFINITY), 0);
+        for _ in 0..1000 {
+            let x = rng.gen_range(range.clone());
+            let b = key.bucket(x).unwrap();
+            assert!(key.range(b).contains(&x));
+            assert_eq!(key.bucket_clamped(x), b);
+        }
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            let min = rng.gen_range(-1e3..1e3);
+            let max = min + rng.gen_range(1e-3..1e3);
+            check(&BucketedKey::linear(min, max, rng.gen_range(1..500)), &mut rng);
+            let min = rng.gen_range(1e-6..1e3);
+            let max = min * rng.gen_range(1.01..1e3);
+            check(&BucketedKey::log(min, max, rng.gen_range(1.001..2.0)), &mut rng);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Zero-copy byte views of Fenwick trees of plain-old-data elements, e.g. for placing a tree in
+//! memory shared between processes.
+//!
+//! Trees are backing arrays, as used by the [`array`](crate::array) module, of element types
+//! implementing [`Pod`] (primitive integers and floats, and `#[repr(C)]` types built from them).
+//! Any array of nodes is a valid Fenwick tree, so viewing bytes as a tree only requires the bytes
+//! to be suitably aligned and sized, which the safe functions check.
+//!
+//! # Byte order and layout
+//!
+//! Views neither convert nor record byte order: elements are in the native byte order (and
+//! layout) of the target, so the bytes are only meaningful to processes running on targets of the
+//! same endianness, with the same element type. Use `cfg!(target_endian = "...")` to guard a
+//! format shared with other targets. The backing array is zero-based (see [`crate::index`]); node
+//! `i` is at byte offset `i * size_of::<T>()` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::bytes::{as_bytes, as_bytes_mut, from_bytes_mut};
+//!
+//! let mut fw = [0u64; 8];
+//! update(&mut fw, 3, 5);
+//!
+//! // e.g. a region of shared memory, suitably aligned
+//! let mut shared = [0u64; 8];
+//! let region = as_bytes_mut(&mut shared);
+//! region.copy_from_slice(as_bytes(&fw));
+//!
+//! let view = from_bytes_mut::<u64>(region).unwrap();
+//! update(view, 6, 2);
+//! assert_eq!(prefix_sum(view, 7), 7);
+//! assert!(from_bytes_mut::<u64>(&mut region[1..]).is_err());
+//! ```
+//!
+
+pub use bytemuck::{Pod, PodCastError};
+
+/// Views the Fenwick tree stored in a borrowed slice as bytes, in native byte order.
+pub fn as_bytes<T: Pod>(fenwick: &[T]) -> &[u8] {
+    bytemuck::cast_slice(fenwick)
+}
+
+/// Views the Fenwick tree stored in a mutably borrowed slice as bytes, in native byte order.
+pub fn as_bytes_mut<T: Pod>(fenwick: &mut [T]) -> &mut [u8] {
+    bytemuck::cast_slice_mut(fenwick)
+}
+
+/// Views bytes in native byte order as the backing array of a Fenwick tree, or returns an error
+/// if `bytes` is not aligned to `align_of::<T>()` or its length is not a multiple of
+/// `size_of::<T>()` .
+pub fn from_bytes<T: Pod>(bytes: &[u8]) -> Result<&[T], PodCastError> {
+    bytemuck::try_cast_slice(bytes)
+}
+
+/// Mutable version of [`from_bytes`] .
+pub fn from_bytes_mut<T: Pod>(bytes: &mut [u8]) -> Result<&mut [T], PodCastError> {
+    bytemuck::try_cast_slice_mut(bytes)
+}
+
+/// Views bytes in native byte order as the backing array of a Fenwick tree, without checking.
+///
+/// # Safety
+///
+/// `bytes` must be aligned to `align_of::<T>()` , and its length must be a multiple of
+/// `size_of::<T>()` . `T` must not be zero-sized.
+///
+pub unsafe fn from_bytes_unchecked<T: Pod>(bytes: &[u8]) -> &[T] {
+    debug_assert!(from_bytes::<T>(bytes).is_ok());
+    // SAFETY: the caller guarantees alignment and size, and any bytes are a valid `T: Pod`
+    unsafe {
+        core::slice::from_raw_parts(
+            bytes.as_ptr().cast(),
+            bytes.len() / core::mem::size_of::<T>(),
+        )
+    }
+}
+
+/// Mutable version of [`from_bytes_unchecked`] .
+///
+/// # Safety
+///
+/// Same as [`from_bytes_unchecked`] .
+///
+pub unsafe fn from_bytes_unchecked_mut<T: Pod>(bytes: &mut [u8]) -> &mut [T] {
+    debug_assert!(from_bytes::<T>(bytes).is_ok());
+    // SAFETY: the caller guarantees alignment and size, and any bytes are a valid `T: Pod`
+    unsafe {
+        core::slice::from_raw_parts_mut(
+            bytes.as_mut_ptr().cast(),
+            bytes.len() / core::mem::size_of::<T>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    use crate::array::{build, prefix_sum};
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 0..64 {
+            let mut fw: std::vec::Vec<i64> =
+                (0..len).map(|_| rng.gen_range(-100..=100)).collect();
+            let data = fw.clone();
+            build(&mut fw);
+            let bytes = as_bytes(&fw).to_vec();
+            assert_eq!(bytes.len(), len * 8);
+            // a `Vec<u8>` is not necessarily aligned for `i64` , so copy into an aligned buffer
+            let mut aligned = std::vec![0i64; len];
+            as_bytes_mut(&mut aligned).copy_from_slice(&bytes);
+            let region = as_bytes_mut(&mut aligned);
+            let view = from_bytes::<i64>(region).unwrap();
+            assert_eq!(view, fw);
+            // SAFETY: `region` comes from a `[i64]`
+            let unchecked = unsafe { from_bytes_unchecked_mut::<i64>(region) };
+            for (i, node) in fw.iter().enumerate() {
+                assert_eq!(prefix_sum(unchecked, i), data[..=i].iter().sum::<i64>());
+                let node_bytes = bytes[i * 8..i * 8 + 8].try_into().unwrap();
+                assert_eq!(i64::from_ne_bytes(node_bytes), *node);
+            }
+            if len > 0 {
+                let slop = Err(PodCastError::OutputSliceWouldHaveSlop);
+                assert_eq!(from_bytes::<i64>(&region[..7]), slop);
+            }
+            if len > 1 {
+                assert_eq!(
+                    from_bytes::<i64>(&region[1..9]),
+                    Err(PodCastError::TargetAlignmentGreaterAndInputNotAligned)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Coordinate compression, mapping a set of sparse keys to dense indices `0..N` for use as indices
+//! of a Fenwick tree.
+//!
+//! Keys are sorted and deduplicated once, so the index of a key is its rank among the distinct
+//! keys. Keys that were not among the compressed ones can still be located between them with
+//! [`Compressor::rank_lower_bound`] and [`Compressor::rank_upper_bound`] , e.g. to turn a range of
+//! keys into a range of indices.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::compress::Compressor;
+//!
+//! let events = [(1_000_000, 3), (5, 1), (42, 2), (5, 4)];
+//! let c = Compressor::new(events.iter().map(|&(t, _)| t));
+//! assert_eq!(c.len(), 3);
+//! assert_eq!(c.index_of(&42), Some(1));
+//! assert_eq!(c.key_of(2), &1_000_000);
+//!
+//! let mut fw = vec![0; c.len()];
+//! for (t, w) in events {
+//!     update(&mut fw, c.index_of(&t).unwrap(), w);
+//! }
+//! // total weight of keys <= 100
+//! let end = c.rank_upper_bound(&100);
+//! assert_eq!(end, 2);
+//! assert_eq!(prefix_sum(&fw, end - 1), 7);
+//! ```
+//!
+
+use alloc::vec::Vec;
+
+/// Mapping between a sorted set of distinct keys and their indices.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Compressor<K> {
+    keys: Vec<K>,
+}
+
+impl<K: Ord> Compressor<K> {
+    /// Compresses `keys` , ignoring duplicates. Takes `O(N log(N))` .
+    pub fn new<I: IntoIterator<Item = K>>(keys: I) -> Self {
+        let mut keys: Vec<K> = keys.into_iter().collect();
+        keys.sort_unstable();
+        keys.dedup();
+        Self { keys }
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the distinct keys in increasing order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the index of `key` , or `None` if it is not one of the compressed keys. Takes
+    /// `O(log(N))` .
+    pub fn index_of(&self, key: &K) -> Option<usize> {
+        self.keys.binary_search(key).ok()
+    }
+
+    /// Returns the key at `index` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()` .
+    ///
+    pub fn key_of(&self, index: usize) -> &K {
+        &self.keys[index]
+    }
+
+    /// Returns the number of compressed keys less than `key` , i.e. the index of the first key no
+    /// less than `key` . Takes `O(log(N))` .
+    pub fn rank_lower_bound(&self, key: &K) -> usize {
+        self.keys.partition_point(|k| k < key)
+    }
+
+    /// Returns the number of compressed keys no greater than `key` , i.e. the index of the first
+    /// key greater than `key` . Takes `O(log(N))` .
+    pub fn rank_upper_bound(&self, key: &K) -> usize {
+        self.keys.partition_point(|k| k <= key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for n in 0..64 {
+            let keys = (0..n).map(|_| rng.gen_range(-50..50) * 3).collect_vec();
+            let c = Compressor::new(keys.iter().copied());
+            let distinct = keys.iter().copied().sorted().dedup().collect_vec();
+            assert_eq!(c.keys(), distinct);
+            for (i, k) in distinct.iter().enumerate() {
+                assert_eq!(c.index_of(k), Some(i));
+                assert_eq!(c.key_of(i), k);
+            }
+            for key in -160..160 {
+                assert_eq!(c.index_of(&key).is_some(), keys.contains(&key));
+                let below = distinct.iter().filter(|&&k| k < key).count();
+                let upto = distinct.iter().filter(|&&k| k <= key).count();
+                assert_eq!(c.rank_lower_bound(&key), below);
+                assert_eq!(c.rank_upper_bound(&key), upto);
+            }
+        }
+    }
+}
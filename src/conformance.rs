@@ -0,0 +1,519 @@
+//! Conformance suite shared by all Fenwick tree backends, in this crate or downstream.
+//!
+//! [`FenwickConformance`] captures the core semantics of a tree of `i64` elements: point updates,
+//! prefix sums, range sums and selection by prefix sum. A backend implements the required methods
+//! (and may override the provided ones with faster versions), then
+//! [`fenwick_conformance_tests!`](crate::fenwick_conformance_tests) generates `#[test]` functions
+//! that check it against a naive array over randomized operations, for each seed in [`SEEDS`] .
+//!
+//! Operations are generated from the seed alone, so the checks are replay-deterministic: a failure
+//! reports the backend, the seed and the failing query, and calling the same check (e.g.
+//! [`check_prefix_sums`]) with that seed performs exactly the same operations again.
+//!
+//! In this crate, the suite runs on the dense backing array (`Vec<i64>`), `sparse::SparseFenwick` ,
+//! `smart::SmartFenwick` , the persistent `cow::CowFenwick` , `deque::FenwickDeque` ,
+//! `sharded::ShardedFenwick` and the atomic `shared::SharedFenwick` . [`selftest`](crate::selftest)
+//! runs a subset of it at runtime.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::conformance::{check_all, FenwickConformance};
+//!
+//! /// Naive backend, e.g. a reference model for a new tree.
+//! struct Naive(Vec<i64>);
+//!
+//! impl FenwickConformance for Naive {
+//!     const NAME: &'static str = "naive";
+//!     fn new(len: usize) -> Self {
+//!         Naive(vec![0; len])
+//!     }
+//!     fn len(&self) -> usize {
+//!         self.0.len()
+//!     }
+//!     fn update(&mut self, i: usize, delta: i64) {
+//!         self.0[i] += delta;
+//!     }
+//!     fn prefix_sum(&self, i: usize) -> i64 {
+//!         self.0[..=i].iter().sum()
+//!     }
+//! }
+//!
+//! // in a test module, `fenwick::fenwick_conformance_tests!(Naive);` generates the tests
+//! check_all::<Naive>(42);
+//! ```
+//!
+
+use core::ops::Range;
+
+use crate::selftest::SplitMix64;
+
+/// Seeds used by the generated tests.
+pub const SEEDS: Range<u64> = 0..32;
+
+/// Maximum number of elements of the trees under test.
+pub const MAX_LEN: usize = 64;
+
+/// Core semantics of a Fenwick tree of `i64` elements.
+///
+/// See [module-level documentation](self).
+///
+pub trait FenwickConformance {
+    /// Name of the backend in failure messages.
+    const NAME: &'static str;
+
+    /// Creates a tree with `len` zero elements.
+    fn new(len: usize) -> Self;
+
+    /// Returns the number of elements.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no elements.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` .
+    fn update(&mut self, i: usize, delta: i64);
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the original array `a` .
+    fn prefix_sum(&self, i: usize) -> i64;
+
+    /// Conceptually calculates the sum of `a[range]` on the original array `a` .
+    fn range_sum(&self, range: Range<usize>) -> i64 {
+        if range.start >= range.end {
+            return 0;
+        }
+        let mut sum = self.prefix_sum(range.end - 1);
+        if range.start > 0 {
+            sum -= self.prefix_sum(range.start - 1);
+        }
+        sum
+    }
+
+    /// Returns the smallest `i` such that `a[0] + ... + a[i] >= target` , or `self.len()` if there
+    /// is none, assuming that all elements are non-negative (see
+    /// [`lower_bound`](crate::array::lower_bound)).
+    fn select(&self, target: i64) -> usize {
+        // binary search over prefix sums
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.prefix_sum(mid) >= target {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        lo
+    }
+}
+
+/// Returns a random length in `0..=MAX_LEN` .
+fn random_len(rng: &mut SplitMix64) -> usize {
+    rng.below(MAX_LEN + 1)
+}
+
+/// Returns a random delta in `-100..=100` , or `0..=100` if `non_negative` .
+fn random_delta(rng: &mut SplitMix64, non_negative: bool) -> i64 {
+    if non_negative {
+        rng.below(101) as i64
+    } else {
+        rng.below(201) as i64 - 100
+    }
+}
+
+/// Checks that the length is as created, and that prefix sums match a naive array after random
+/// updates, both at random points along the way and for all indices at the end.
+///
+/// # Panics
+///
+/// Panics with the seed and the failing query on a mismatch.
+///
+pub fn check_prefix_sums<B: FenwickConformance>(seed: u64) {
+    let mut rng = SplitMix64(seed);
+    for _ in 0..4 {
+        let len = random_len(&mut rng);
+        let mut tree = B::new(len);
+        assert_eq!(tree.len(), len, "{} (seed {}): len", B::NAME, seed);
+        assert_eq!(tree.is_empty(), len == 0, "{} (seed {}): is_empty", B::NAME, seed);
+        let mut naive = [0i64; MAX_LEN];
+        for step in 0..len * 2 {
+            let i = rng.below(len);
+            let delta = random_delta(&mut rng, false);
+            tree.update(i, delta);
+            naive[i] += delta;
+            let j = rng.below(len);
+            let expected: i64 = naive[..=j].iter().sum();
+            let actual = tree.prefix_sum(j);
+            assert_eq!(
+                actual, expected,
+                "{} (seed {}): prefix_sum({}) at step {}", B::NAME, seed, j, step
+            );
+        }
+        let mut expected = 0;
+        for (i, x) in naive[..len].iter().enumerate() {
+            expected += x;
+            let actual = tree.prefix_sum(i);
+            assert_eq!(
+                actual, expected,
+                "{} (seed {}): prefix_sum({}) with len {}", B::NAME, seed, i, len
+            );
+        }
+    }
+}
+
+/// Checks all range sums (including empty ranges) against a naive array after random updates.
+///
+/// # Panics
+///
+/// Panics with the seed and the failing query on a mismatch.
+///
+pub fn check_range_sums<B: FenwickConformance>(seed: u64) {
+    let mut rng = SplitMix64(seed);
+    let len = random_len(&mut rng);
+    let mut tree = B::new(len);
+    let mut naive = [0i64; MAX_LEN];
+    for _ in 0..len * 2 {
+        let i = rng.below(len);
+        let delta = random_delta(&mut rng, false);
+        tree.update(i, delta);
+        naive[i] += delta;
+    }
+    for start in 0..=len {
+        for end in start..=len {
+            let expected: i64 = naive[start..end].iter().sum();
+            let actual = tree.range_sum(start..end);
+            assert_eq!(
+                actual, expected,
+                "{} (seed {}): range_sum({}..{})", B::NAME, seed, start, end
+            );
+        }
+    }
+}
+
+/// Checks selection of every target from `0` to past the total against a naive array, after
+/// random non-negative updates.
+///
+/// # Panics
+///
+/// Panics with the seed and the failing query on a mismatch.
+///
+pub fn check_select<B: FenwickConformance>(seed: u64) {
+    let mut rng = SplitMix64(seed);
+    let len = random_len(&mut rng);
+    let mut tree = B::new(len);
+    let mut naive = [0i64; MAX_LEN];
+    for _ in 0..len {
+        let i = rng.below(len);
+        let delta = random_delta(&mut rng, true) / 10;
+        tree.update(i, delta);
+        naive[i] += delta;
+    }
+    let total: i64 = naive.iter().sum();
+    for target in 0..=total + 1 {
+        let mut sum = 0;
+        let expected = naive[..len].iter().position(|&x| {
+            sum += x;
+            sum >= target
+        });
+        let actual = tree.select(target);
+        assert_eq!(
+            actual,
+            expected.unwrap_or(len),
+            "{} (seed {}): select({})", B::NAME, seed, target
+        );
+    }
+}
+
+/// Checks that undoing random updates (by applying their negations in reverse order) brings every
+/// prefix sum back to zero.
+///
+/// # Panics
+///
+/// Panics with the seed and the failing query on a mismatch.
+///
+pub fn check_inverse<B: FenwickConformance>(seed: u64) {
+    let mut rng = SplitMix64(seed);
+    let len = 1 + rng.below(MAX_LEN);
+    let mut tree = B::new(len);
+    let mut updates = [(0, 0i64); MAX_LEN];
+    for update in updates.iter_mut() {
+        *update = (rng.below(len), random_delta(&mut rng, false));
+        tree.update(update.0, update.1);
+    }
+    for &(i, delta) in updates.iter().rev() {
+        tree.update(i, -delta);
+    }
+    for i in 0..len {
+        let actual = tree.prefix_sum(i);
+        assert_eq!(actual, 0, "{} (seed {}): prefix_sum({}) after undo", B::NAME, seed, i);
+    }
+}
+
+/// Runs every check of this module with `seed` .
+///
+/// # Panics
+///
+/// Panics with the seed and the failing query on a mismatch.
+///
+pub fn check_all<B: FenwickConformance>(seed: u64) {
+    check_prefix_sums::<B>(seed);
+    check_range_sums::<B>(seed);
+    check_select::<B>(seed);
+    check_inverse::<B>(seed);
+}
+
+/// Generates `#[test]` functions running the [`conformance`](crate::conformance) checks on a
+/// backend, for each seed in [`SEEDS`](crate::conformance::SEEDS) .
+///
+/// `fenwick_conformance_tests!(Backend)` generates the functions in the current module, and
+/// `fenwick_conformance_tests!(name: Backend)` wraps them in a module `name` (which sees the items
+/// of the current module), so that one test module can cover several backends.
+///
+/// # Examples
+///
+/// ```
+/// # use fenwick::conformance::FenwickConformance;
+/// # struct MyTree(Vec<i64>);
+/// # impl FenwickConformance for MyTree {
+/// #     const NAME: &'static str = "mine";
+/// #     fn new(len: usize) -> Self { MyTree(vec![0; len]) }
+/// #     fn len(&self) -> usize { self.0.len() }
+/// #     fn update(&mut self, i: usize, delta: i64) { self.0[i] += delta; }
+/// #     fn prefix_sum(&self, i: usize) -> i64 { self.0[..=i].iter().sum() }
+/// # }
+/// #[cfg(test)]
+/// mod tests {
+///     use super::*;
+///
+///     fenwick::fenwick_conformance_tests!(MyTree);
+/// }
+/// ```
+///
+#[macro_export]
+macro_rules! fenwick_conformance_tests {
+    ($name:ident : $backend:ty) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::fenwick_conformance_tests!($backend);
+        }
+    };
+    ($backend:ty) => {
+        #[test]
+        fn conformance_prefix_sums() {
+            for seed in $crate::conformance::SEEDS {
+                $crate::conformance::check_prefix_sums::<$backend>(seed);
+            }
+        }
+
+        #[test]
+        fn conformance_range_sums() {
+            for seed in $crate::conformance::SEEDS {
+                $crate::conformance::check_range_sums::<$backend>(seed);
+            }
+        }
+
+        #[test]
+        fn conformance_select() {
+            for seed in $crate::conformance::SEEDS {
+                $crate::conformance::check_select::<$backend>(seed);
+            }
+        }
+
+        #[test]
+        fn conformance_inverse() {
+            for seed in $crate::conformance::SEEDS {
+                $crate::conformance::check_inverse::<$backend>(seed);
+            }
+        }
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickConformance for alloc::vec::Vec<i64> {
+    const NAME: &'static str = "dense";
+    fn new(len: usize) -> Self {
+        alloc::vec![0; len]
+    }
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        crate::array::update(self, i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        crate::array::prefix_sum(self, i)
+    }
+    fn select(&self, target: i64) -> usize {
+        crate::array::lower_bound(self, target)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickConformance for crate::sparse::SparseFenwick<i64> {
+    const NAME: &'static str = "sparse";
+    fn new(len: usize) -> Self {
+        Self::new(len)
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        self.update(i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        self.prefix_sum(i)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickConformance for crate::smart::SmartFenwick<i64> {
+    const NAME: &'static str = "smart";
+    fn new(len: usize) -> Self {
+        Self::new(len)
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        self.update(i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        self.prefix_sum(i)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickConformance for crate::cow::CowFenwick<i64> {
+    const NAME: &'static str = "cow";
+    fn new(len: usize) -> Self {
+        Self::new(len)
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        self.update(i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        self.prefix_sum(i)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickConformance for crate::deque::FenwickDeque<i64> {
+    const NAME: &'static str = "deque";
+    fn new(len: usize) -> Self {
+        let mut deque = Self::new();
+        for _ in 0..len {
+            deque.push_back(0);
+        }
+        deque
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        self.update(i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        self.prefix_sum(i)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FenwickConformance for crate::sharded::ShardedFenwick<i64> {
+    const NAME: &'static str = "sharded";
+    fn new(len: usize) -> Self {
+        let mut sharded = Self::new(len);
+        sharded.register_shard();
+        sharded.register_shard();
+        sharded
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        // spread updates over the shards
+        crate::array::update(self.shard_mut(i % 2), i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        self.prefix_sum(i)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use crate::cow::CowFenwick;
+    use crate::deque::FenwickDeque;
+    use crate::sharded::ShardedFenwick;
+    #[cfg(target_has_atomic = "64")]
+    use crate::shared::SharedFenwick;
+    use crate::smart::SmartFenwick;
+    use crate::sparse::SparseFenwick;
+    #[cfg(target_has_atomic = "64")]
+    use core::sync::atomic::AtomicU64;
+    use std::vec::Vec;
+
+    /// Region of atomics for a [`SharedFenwick`] , which does not own its storage.
+    #[cfg(target_has_atomic = "64")]
+    struct Shared(Vec<AtomicU64>);
+
+    #[cfg(target_has_atomic = "64")]
+    impl FenwickConformance for Shared {
+        const NAME: &'static str = "shared";
+        fn new(len: usize) -> Self {
+            Shared((0..SharedFenwick::words(len)).map(|_| AtomicU64::new(0)).collect())
+        }
+        fn len(&self) -> usize {
+            SharedFenwick::new(&self.0).len()
+        }
+        fn update(&mut self, i: usize, delta: i64) {
+            // counts wrap around, so negative deltas are subtractions
+            SharedFenwick::new(&self.0).add(i, delta as u64);
+        }
+        fn prefix_sum(&self, i: usize) -> i64 {
+            SharedFenwick::new(&self.0).prefix_sum(i) as i64
+        }
+    }
+
+    crate::fenwick_conformance_tests!(dense: Vec<i64>);
+    crate::fenwick_conformance_tests!(sparse: SparseFenwick<i64>);
+    crate::fenwick_conformance_tests!(smart: SmartFenwick<i64>);
+    crate::fenwick_conformance_tests!(cow: CowFenwick<i64>);
+    crate::fenwick_conformance_tests!(deque: FenwickDeque<i64>);
+    crate::fenwick_conformance_tests!(sharded: ShardedFenwick<i64>);
+    #[cfg(target_has_atomic = "64")]
+    crate::fenwick_conformance_tests!(shared: Shared);
+
+    #[test]
+    #[should_panic(expected = "broken (seed 0)")]
+    fn reports_seed() {
+        /// Backend whose prefix sums skip the first element.
+        struct Broken(Vec<i64>);
+
+        impl FenwickConformance for Broken {
+            const NAME: &'static str = "broken";
+            fn new(len: usize) -> Self {
+                Broken(std::vec![0; len])
+            }
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+            fn update(&mut self, i: usize, delta: i64) {
+                self.0[i] += delta;
+            }
+            fn prefix_sum(&self, i: usize) -> i64 {
+                self.0[1..=i].iter().sum()
+            }
+        }
+
+        check_all::<Broken>(0);
+    }
+}
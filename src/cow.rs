@@ -0,0 +1,230 @@
+//! Copy-on-write Fenwick tree supporting cheap frozen snapshots.
+//!
+//! The backing array is split into fixed-size chunks, each behind an `Arc` , and the table of
+//! chunks is itself behind an `Arc` . Taking a snapshot with [`CowFenwick::freeze`] (or cloning)
+//! takes `O(1)` . The first update afterwards copies the chunk table (`O(N / C)` for chunks of `C`
+//! nodes), and each update copies the (at most `O(log(N))`) chunks it touches the first time it
+//! touches them. The snapshot can be sent to another thread and queried while the original keeps
+//! receiving updates.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::cow::CowFenwick;
+//!
+//! let mut live = CowFenwick::new(10_000);
+//! live.update(10, 5);
+//! let frozen = live.freeze();
+//! live.update(20, 7);
+//! let report = std::thread::spawn(move || frozen.prefix_sum(9_999));
+//! live.update(10, 1);
+//! assert_eq!(report.join().unwrap(), 5);
+//! assert_eq!(live.prefix_sum(9_999), 13);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::index::zero_based::{down, up};
+
+/// Number of nodes in each copy-on-write chunk.
+const CHUNK_LEN: usize = 1024;
+
+/// Copy-on-write Fenwick tree.
+///
+/// Equality compares the logical contents, regardless of which chunks are shared. A tree also
+/// compares equal to a snapshot with the same contents.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CowFenwick<T> {
+    len: usize,
+    chunks: Arc<Vec<Arc<Vec<T>>>>,
+}
+
+/// Immutable snapshot of a [`CowFenwick`] , sharing unchanged chunks with it.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FrozenFenwick<T> {
+    len: usize,
+    chunks: Arc<Vec<Arc<Vec<T>>>>,
+}
+
+impl<T> CowFenwick<T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a tree with `len` zero elements.
+    pub fn new(len: usize) -> Self {
+        let chunks = (0..len.div_ceil(CHUNK_LEN))
+            .map(|c| Arc::new(vec![T::default(); (len - c * CHUNK_LEN).min(CHUNK_LEN)]))
+            .collect();
+        Self {
+            len,
+            chunks: Arc::new(chunks),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` . Takes `O(log(N))` , plus
+    /// copying the chunk table and each touched chunk that are still shared with a snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, delta: T) {
+        let chunks = Arc::make_mut(&mut self.chunks);
+        for ii in up(i, self.len) {
+            Arc::make_mut(&mut chunks[ii / CHUNK_LEN])[ii % CHUNK_LEN] += delta.clone();
+        }
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the original array `a` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        assert!(i < self.len);
+        prefix_sum(&self.chunks, i)
+    }
+
+    /// Takes an immutable snapshot of the current state. Takes `O(1)` .
+    pub fn freeze(&self) -> FrozenFenwick<T> {
+        FrozenFenwick {
+            len: self.len,
+            chunks: self.chunks.clone(),
+        }
+    }
+}
+
+impl<T> FrozenFenwick<T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the original array `a` at the time of the
+    /// snapshot. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        assert!(i < self.len);
+        prefix_sum(&self.chunks, i)
+    }
+}
+
+impl<T: PartialEq> PartialEq<FrozenFenwick<T>> for CowFenwick<T> {
+    fn eq(&self, other: &FrozenFenwick<T>) -> bool {
+        self.len == other.len && self.chunks == other.chunks
+    }
+}
+
+impl<T: PartialEq> PartialEq<CowFenwick<T>> for FrozenFenwick<T> {
+    fn eq(&self, other: &CowFenwick<T>) -> bool {
+        other == self
+    }
+}
+
+fn prefix_sum<T>(chunks: &[Arc<Vec<T>>], i: usize) -> T
+where
+    T: AddAssign + Clone + Default
+{
+    let mut sum = T::default();
+    for ii in down(i) {
+        sum += chunks[ii / CHUNK_LEN][ii % CHUNK_LEN].clone();
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn logical_eq() {
+        let mut live = CowFenwick::new(3 * CHUNK_LEN);
+        live.update(CHUNK_LEN, 3);
+        let frozen = live.freeze();
+        let mut other = CowFenwick::new(3 * CHUNK_LEN);
+        other.update(CHUNK_LEN, 3);
+        // equal contents, regardless of chunk sharing
+        assert_eq!(live, other);
+        assert_eq!(live, frozen);
+        assert_eq!(frozen, other);
+        live.update(0, 1);
+        assert_ne!(live, frozen);
+        assert_ne!(frozen, live);
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in [1, 100, CHUNK_LEN, CHUNK_LEN + 1, 5 * CHUNK_LEN - 3] {
+            let mut live = CowFenwick::new(len);
+            let mut naive = std::vec![0i64; len];
+            let mut snapshots = std::vec![];
+            for round in 0..8 {
+                for _ in 0..32 {
+                    let i = rng.gen_range(0..len);
+                    let x = rng.gen_range(-100..=100);
+                    live.update(i, x);
+                    naive[i] += x;
+                }
+                let frozen = live.freeze();
+                // freezing shares the whole chunk table
+                assert!(Arc::ptr_eq(&live.chunks, &frozen.chunks));
+                snapshots.push((frozen, naive.clone()));
+                if round == 0 && len > 2 * CHUNK_LEN {
+                    // an update only copies the chunks it touches
+                    live.update(0, 1);
+                    naive[0] += 1;
+                    let frozen = &snapshots[0].0;
+                    let shared = live.chunks.iter().zip(frozen.chunks.iter())
+                        .filter(|(a, b)| Arc::ptr_eq(a, b))
+                        .count();
+                    assert!(shared >= live.chunks.len() - 3);
+                }
+            }
+            for (frozen, naive) in snapshots.iter().chain([(live.freeze(), naive)].iter()) {
+                let mut sum = 0;
+                for (i, x) in naive.iter().enumerate() {
+                    sum += x;
+                    assert_eq!(frozen.prefix_sum(i), sum);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Replicated counter vectors (CRDTs) with prefix sums, e.g. for distributed tallies.
+//!
+//! A [`GCounterVector`] is a vector of grow-only counters. Each replica only increments its own
+//! counts, and replicas converge by merging: the count of each replica at each index is the maximum
+//! seen, so merging is commutative, associative and idempotent, and states can be exchanged in any
+//! order and any number of times. The logical value at an index is the sum over all replicas.
+//! A [`PNCounterVector`] pairs two of them to also support decrements.
+//!
+//! The logical values are kept in a Fenwick tree, so prefix sums take `O(log(N))` . Incrementing
+//! takes `O(log(N))` , and merging takes `O(R N)` for `R` replicas, plus `O(log(N))` for each
+//! count that changed.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::crdt::{GCounterVector, PNCounterVector};
+//!
+//! let mut a = GCounterVector::new(1, 10);
+//! let mut b = GCounterVector::new(2, 10);
+//! a.increment(3, 5);
+//! b.increment(3, 2);
+//! b.increment(8, 1);
+//! a.merge(&b);
+//! b.merge(&a);
+//! a.merge(&b); // merging again changes nothing
+//! assert_eq!(a, b);
+//! assert_eq!(a.get(3), 7);
+//! assert_eq!(a.prefix_sum(5), 7);
+//! assert_eq!(a.prefix_sum(9), 8);
+//!
+//! let mut a = PNCounterVector::new(1, 4);
+//! let mut b = PNCounterVector::new(2, 4);
+//! a.increment(0, 3);
+//! b.decrement(0, 1);
+//! b.decrement(2, 4);
+//! a.merge(&b);
+//! assert_eq!(a.get(0), 2);
+//! assert_eq!(a.prefix_sum(3), -2);
+//! ```
+//!
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{get, prefix_sum};
+use crate::index::zero_based::up;
+
+/// Vector of grow-only counters, replicated across replicas identified by `u64` ids.
+///
+/// Two vectors are equal if they have the same counts from every replica, regardless of which
+/// replica they belong to.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug)]
+pub struct GCounterVector {
+    replica: u64,
+    /// Counts of each replica at each index.
+    counts: BTreeMap<u64, Vec<u64>>,
+    /// Fenwick tree over the sums of counts over all replicas.
+    fenwick: Vec<u64>,
+}
+
+impl GCounterVector {
+    /// Creates a vector of `len` zero counters, owned by replica `replica` .
+    pub fn new(replica: u64, len: usize) -> Self {
+        Self {
+            replica,
+            counts: BTreeMap::new(),
+            fenwick: vec![0; len],
+        }
+    }
+
+    /// Returns the id of the replica this vector belongs to.
+    pub fn replica(&self) -> u64 {
+        self.replica
+    }
+
+    /// Returns the number of counters.
+    pub fn len(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns `true` if there are no counters.
+    pub fn is_empty(&self) -> bool {
+        self.fenwick.is_empty()
+    }
+
+    /// Adds `n` to counter `i` , on behalf of this replica. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn increment(&mut self, i: usize, n: u64) {
+        let len = self.len();
+        assert!(i < len);
+        self.counts.entry(self.replica).or_insert_with(|| vec![0; len])[i] += n;
+        self.add(i, n);
+    }
+
+    fn add(&mut self, i: usize, n: u64) {
+        for ii in up(i, self.len()) {
+            self.fenwick[ii] += n;
+        }
+    }
+
+    /// Merges the counts of `other` into this vector, keeping the maximum count of each replica at
+    /// each index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.len() != other.len()` .
+    ///
+    pub fn merge(&mut self, other: &GCounterVector) {
+        let len = self.len();
+        assert_eq!(len, other.len());
+        for (&replica, theirs) in &other.counts {
+            let ours = self.counts.entry(replica).or_insert_with(|| vec![0; len]);
+            let mut deltas = Vec::new();
+            for (i, (x, &y)) in ours.iter_mut().zip(theirs).enumerate() {
+                if y > *x {
+                    deltas.push((i, y - *x));
+                    *x = y;
+                }
+            }
+            for (i, delta) in deltas {
+                self.add(i, delta);
+            }
+        }
+    }
+
+    /// Returns the value of counter `i` , summed over all replicas. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn get(&self, i: usize) -> u64 {
+        // `get` subtracts children from the node, which cannot underflow here
+        get(&self.fenwick, i)
+    }
+
+    /// Returns the sum of counters `0..=i` , over all replicas. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> u64 {
+        prefix_sum(&self.fenwick, i)
+    }
+
+    /// Returns the counts of replicas that have counted anything.
+    fn nonzero_counts(&self) -> impl Iterator<Item = (&u64, &Vec<u64>)> {
+        self.counts.iter().filter(|(_, counts)| counts.iter().any(|&x| x > 0))
+    }
+}
+
+impl PartialEq for GCounterVector {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.nonzero_counts().eq(other.nonzero_counts())
+    }
+}
+
+impl Eq for GCounterVector {}
+
+/// Vector of counters supporting increments and decrements, replicated across replicas identified
+/// by `u64` ids.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PNCounterVector {
+    increments: GCounterVector,
+    decrements: GCounterVector,
+}
+
+impl PNCounterVector {
+    /// Creates a vector of `len` zero counters, owned by replica `replica` .
+    pub fn new(replica: u64, len: usize) -> Self {
+        Self {
+            increments: GCounterVector::new(replica, len),
+            decrements: GCounterVector::new(replica, len),
+        }
+    }
+
+    /// Returns the id of the replica this vector belongs to.
+    pub fn replica(&self) -> u64 {
+        self.increments.replica()
+    }
+
+    /// Returns the number of counters.
+    pub fn len(&self) -> usize {
+        self.increments.len()
+    }
+
+    /// Returns `true` if there are no counters.
+    pub fn is_empty(&self) -> bool {
+        self.increments.is_empty()
+    }
+
+    /// Adds `n` to counter `i` , on behalf of this replica. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn increment(&mut self, i: usize, n: u64) {
+        self.increments.increment(i, n);
+    }
+
+    /// Subtracts `n` from counter `i` , on behalf of this replica. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn decrement(&mut self, i: usize, n: u64) {
+        self.decrements.increment(i, n);
+    }
+
+    /// Merges the increments and decrements of `other` into this vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.len() != other.len()` .
+    ///
+    pub fn merge(&mut self, other: &PNCounterVector) {
+        self.increments.merge(&other.increments);
+        self.decrements.merge(&other.decrements);
+    }
+
+    /// Returns the value of counter `i` , summed over all replicas. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn get(&self, i: usize) -> i64 {
+        self.increments.get(i) as i64 - self.decrements.get(i) as i64
+    }
+
+    /// Returns the sum of counters `0..=i` , over all replicas. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> i64 {
+        self.increments.prefix_sum(i) as i64 - self.decrements.prefix_sum(i) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..24 {
+            let mut replicas: std::vec::Vec<PNCounterVector> =
+                (0..4).map(|r| PNCounterVector::new(r, len)).collect();
+            let mut naive = std::vec![0i64; len];
+            for _ in 0..len * 8 {
+                let r = rng.gen_range(0..replicas.len());
+                let i = rng.gen_range(0..len);
+                let n = rng.gen_range(0..5);
+                match rng.gen_range(0..3) {
+                    0 => {
+                        replicas[r].increment(i, n);
+                        naive[i] += n as i64;
+                    }
+                    1 => {
+                        replicas[r].decrement(i, n);
+                        naive[i] -= n as i64;
+                    }
+                    _ => {
+                        // gossip: merge a random other replica's state
+                        let other = replicas[rng.gen_range(0..replicas.len())].clone();
+                        replicas[r].merge(&other);
+                    }
+                }
+            }
+            // merging everything in any order converges to the same state
+            let mut order: std::vec::Vec<usize> = (0..replicas.len()).collect();
+            let snapshot = replicas.clone();
+            for replica in &mut replicas {
+                order.shuffle(&mut rng);
+                for &o in &order {
+                    replica.merge(&snapshot[o]);
+                }
+            }
+            for replica in &replicas {
+                assert_eq!(replica, &replicas[0]);
+                let mut sum = 0;
+                for (i, x) in naive.iter().enumerate() {
+                    sum += x;
+                    assert_eq!(replica.get(i), *x);
+                    assert_eq!(replica.prefix_sum(i), sum);
+                }
+            }
+        }
+    }
+}
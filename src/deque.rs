@@ -0,0 +1,257 @@
+//! Double-ended Fenwick tree supporting `push_front` / `push_back` / `pop_front` / `pop_back` .
+//!
+//! Elements are addressed by their logical position counted from the current front, like
+//! `VecDeque` . Internally, elements are split between two Fenwick trees: one holding the front
+//! part in reverse order and one holding the back part. Pushing or popping at either end touches
+//! only the end of one tree and takes `O(log(N))` . When one end runs out while popping, the
+//! remaining elements are rebalanced between the two trees in `O(N)` , which is `O(1)` amortized.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::deque::FenwickDeque;
+//!
+//! // sliding log: new entries arrive at the front, old entries expire at the back
+//! let mut log = FenwickDeque::new();
+//! log.push_front(5);
+//! log.push_front(3);
+//! log.push_front(2); // logical array: [2, 3, 5]
+//! assert_eq!(log.prefix_sum(0), 2);
+//! assert_eq!(log.prefix_sum(1), 5);
+//! assert_eq!(log.prefix_sum(2), 10);
+//! assert_eq!(log.pop_back(), Some(5)); // logical array: [2, 3]
+//! log.push_front(7); // logical array: [7, 2, 3]
+//! log.update(2, 10); // logical array: [7, 2, 13]
+//! assert_eq!(log.prefix_sum(1), 9);
+//! assert_eq!(log.prefix_sum(2), 22);
+//! assert_eq!(log.len(), 3);
+//! ```
+//!
+
+use core::hash::{Hash, Hasher};
+use core::ops::{AddAssign, SubAssign};
+
+use alloc::vec::Vec;
+
+use crate::array::{build, pop, prefix_sum, push, unbuild, update};
+
+/// Double-ended Fenwick tree.
+///
+/// Equality and hashing are defined over the logical array, regardless of how elements are split
+/// between the front and back trees internally. Both take `O(N)` .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default)]
+pub struct FenwickDeque<T> {
+    /// Fenwick tree over the front part, in reverse logical order.
+    front: Vec<T>,
+    /// Fenwick tree over the back part, in logical order.
+    back: Vec<T>,
+}
+
+impl<T> FenwickDeque<T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Creates an empty deque.
+    pub fn new() -> Self {
+        Self {
+            front: Vec::new(),
+            back: Vec::new(),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts `value` before the first element. Takes `O(log(N))` .
+    pub fn push_front(&mut self, value: T) {
+        push(&mut self.front, value);
+    }
+
+    /// Inserts `value` after the last element. Takes `O(log(N))` .
+    pub fn push_back(&mut self, value: T) {
+        push(&mut self.back, value);
+    }
+
+    /// Removes and returns the first element, or `None` if empty. Takes `O(log(N))` amortized.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            self.rebalance(self.len().div_ceil(2));
+        }
+        pop(&mut self.front)
+    }
+
+    /// Removes and returns the last element, or `None` if empty. Takes `O(log(N))` amortized.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.back.is_empty() {
+            self.rebalance(self.len() / 2);
+        }
+        pop(&mut self.back)
+    }
+
+    /// Conceptually performs `a[i] += delta` on the logical array `a` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, delta: T) {
+        assert!(i < self.len());
+        let f = self.front.len();
+        if i < f {
+            update(&mut self.front, f - 1 - i, delta);
+        } else {
+            update(&mut self.back, i - f, delta);
+        }
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the logical array `a` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        assert!(i < self.len());
+        let f = self.front.len();
+        if i < f {
+            // logical `0..=i` is reversed position `f - 1 - i..f` in the front tree
+            let mut sum = prefix_sum(&self.front, f - 1);
+            if i + 1 < f {
+                sum -= prefix_sum(&self.front, f - 2 - i);
+            }
+            sum
+        } else {
+            let mut sum = if f > 0 { prefix_sum(&self.front, f - 1) } else { T::default() };
+            sum += prefix_sum(&self.back, i - f);
+            sum
+        }
+    }
+
+    /// Moves elements between the front and back trees such that the front tree holds the first
+    /// `front_len` elements. Takes `O(N)` .
+    fn rebalance(&mut self, front_len: usize) {
+        unbuild(&mut self.front);
+        unbuild(&mut self.back);
+        let mut values = core::mem::take(&mut self.front);
+        values.reverse();
+        values.append(&mut self.back);
+        self.back = values.split_off(front_len);
+        values.reverse();
+        self.front = values;
+        build(&mut self.front);
+        build(&mut self.back);
+    }
+
+    /// Returns the logical array. Takes `O(N)` .
+    fn values(&self) -> Vec<T> {
+        let mut values = self.front.clone();
+        unbuild(&mut values);
+        values.reverse();
+        let start = values.len();
+        values.extend_from_slice(&self.back);
+        unbuild(&mut values[start..]);
+        values
+    }
+}
+
+impl<T> PartialEq for FenwickDeque<T>
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.values() == other.values()
+    }
+}
+
+impl<T> Eq for FenwickDeque<T>
+where
+    T: AddAssign + SubAssign + Clone + Default + Eq
+{}
+
+impl<T> Hash for FenwickDeque<T>
+where
+    T: AddAssign + SubAssign + Clone + Default + Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn logical_eq() {
+        use std::hash::BuildHasher;
+        let hasher = std::collections::hash_map::RandomState::new();
+
+        // same logical array [1, 2, 3, 4] split differently between front and back
+        let mut a = FenwickDeque::new();
+        let mut b = FenwickDeque::new();
+        for x in [1, 2, 3, 4] {
+            a.push_back(x);
+        }
+        for x in [4, 3, 2, 1] {
+            b.push_front(x);
+        }
+        b.push_back(5);
+        b.pop_back();
+        assert_ne!(a.front.len(), b.front.len());
+        assert_eq!(a, b);
+        assert_eq!(hasher.hash_one(&a), hasher.hash_one(&b));
+        b.update(0, 1);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        let mut deque = FenwickDeque::new();
+        let mut naive = std::collections::VecDeque::new();
+        for _ in 0..4096 {
+            match rng.gen_range(0..5) {
+                0 => {
+                    let x = rng.gen_range(-100..=100);
+                    deque.push_front(x);
+                    naive.push_front(x);
+                }
+                1 => {
+                    let x = rng.gen_range(-100..=100);
+                    deque.push_back(x);
+                    naive.push_back(x);
+                }
+                2 => assert_eq!(deque.pop_front(), naive.pop_front()),
+                3 => assert_eq!(deque.pop_back(), naive.pop_back()),
+                _ => {
+                    if !naive.is_empty() {
+                        let i = rng.gen_range(0..naive.len());
+                        let x = rng.gen_range(-100..=100);
+                        deque.update(i, x);
+                        naive[i] += x;
+                    }
+                }
+            }
+            assert_eq!(deque.len(), naive.len());
+            assert_eq!(deque.values(), naive.iter().copied().collect::<std::vec::Vec<_>>());
+            let mut sum = 0i64;
+            for (i, x) in naive.iter().enumerate() {
+                sum += x;
+                assert_eq!(deque.prefix_sum(i), sum);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Deterministic element type for trees of floating point values.
+//!
+//! Floating point addition is not associative, so the node values of a Fenwick tree of `f64` (and
+//! therefore its prefix sums) depend on the order of updates, and may differ across platforms that
+//! evaluate floating point expressions differently. [`DetF64`] instead converts each value once to
+//! a 128-bit fixed-point integer, whose addition is associative and exact. The resulting prefix
+//! sums are bit-identical regardless of the order of updates or the platform.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::detfloat::DetF64;
+//!
+//! let deltas = [0.1, 1e10, -1e10, 0.2, 0.3];
+//! let fw1 = &mut [DetF64::default(); 4];
+//! let fw2 = &mut [DetF64::default(); 4];
+//! for &x in &deltas {
+//!     update(fw1, 1, DetF64::from_f64(x));
+//! }
+//! for &x in deltas.iter().rev() {
+//!     update(fw2, 1, DetF64::from_f64(x));
+//! }
+//! assert_eq!(prefix_sum(fw1, 3), prefix_sum(fw2, 3));
+//! assert_eq!(prefix_sum(fw1, 3).to_f64().to_bits(), prefix_sum(fw2, 3).to_f64().to_bits());
+//! ```
+//!
+
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// `2^64` , i.e. one in the fixed-point representation (64 fractional bits).
+const SCALE: f64 = (1u128 << 64) as f64;
+
+/// Fixed-point number with 64 integer bits (including sign) and 64 fractional bits, converted
+/// deterministically from and to `f64` .
+///
+/// Values of magnitude at least `2^63` saturate on conversion, NaN converts to zero, and bits below
+/// `2^-64` are truncated towards zero. Addition and subtraction are exact, and panic on overflow in
+/// debug builds.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DetF64(i128);
+
+impl DetF64 {
+    /// Converts from `f64` . See [`DetF64`] for rounding and saturation.
+    pub fn from_f64(x: f64) -> Self {
+        // scaling by a power of two is exact (unless it overflows to infinity, which saturates)
+        Self((x * SCALE) as i128)
+    }
+
+    /// Converts to the nearest `f64` .
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE
+    }
+
+    /// Creates a value from its raw fixed-point representation, i.e. `raw * 2^-64` .
+    pub const fn from_bits(raw: i128) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw fixed-point representation, i.e. `self * 2^64` .
+    pub const fn to_bits(self) -> i128 {
+        self.0
+    }
+}
+
+impl From<f64> for DetF64 {
+    fn from(x: f64) -> Self {
+        Self::from_f64(x)
+    }
+}
+
+impl From<DetF64> for f64 {
+    fn from(x: DetF64) -> Self {
+        x.to_f64()
+    }
+}
+
+impl AddAssign for DetF64 {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for DetF64 {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Add for DetF64 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for DetF64 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for DetF64 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    use crate::array::{prefix_sum, update};
+
+    #[test]
+    fn conversions() {
+        for x in [0.0, 1.0, -1.0, 0.5, 2f64.powi(-64), -123456.789, 2f64.powi(62)] {
+            assert_eq!(DetF64::from_f64(x).to_f64(), x);
+        }
+        assert!((DetF64::from_f64(1e-10).to_f64() - 1e-10).abs() < 1e-19);
+        assert_eq!(DetF64::from_f64(2f64.powi(-65)).to_bits(), 0);
+        assert_eq!(DetF64::from_f64(1.0).to_bits(), 1 << 64);
+        assert_eq!(DetF64::from_f64(f64::NAN), DetF64::default());
+        assert_eq!(DetF64::from_f64(f64::INFINITY).to_bits(), i128::MAX);
+        assert_eq!(DetF64::from_f64(-1e300).to_bits(), i128::MIN);
+    }
+
+    #[test]
+    fn order_independent() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut ops = (0..len * 4).map(|_| {
+                let x: f64 = rng.gen_range(-1.0..1.0) * 10f64.powi(rng.gen_range(-8..8));
+                (rng.gen_range(0..len), DetF64::from_f64(x))
+            }).collect_vec();
+            let mut fw1 = std::vec![DetF64::default(); len];
+            for &(i, x) in &ops {
+                update(&mut fw1, i, x);
+            }
+            ops.shuffle(&mut rng);
+            let mut fw2 = std::vec![DetF64::default(); len];
+            for &(i, x) in &ops {
+                update(&mut fw2, i, x);
+            }
+            for i in 0..len {
+                let (a, b) = (prefix_sum(&fw1, i), prefix_sum(&fw2, i));
+                assert_eq!(a.to_bits(), b.to_bits());
+                assert_eq!(a.to_f64().to_bits(), b.to_f64().to_bits());
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Human-readable text dumps of the values of a tree, as CSV or JSON lines, e.g. to inspect and
+//! patch counters with standard text tools.
+//!
+//! [`export_values`] writes one `(index, value)` pair per non-zero element of the original array,
+//! in increasing order of index, and [`import_values`] reads them back into the backing array of a
+//! tree of a given length. Both formats use the `Display` and `FromStr` implementations of the
+//! element type, which do not depend on the locale (e.g. `1234.5` , never `1.234,5`):
+//!
+//! - [`Format::Csv`] : a header line `index,value` , then lines such as `3,-5` ;
+//! - [`Format::JsonLines`] : one object per line, such as `{"index":3,"value":-5}` .
+//!
+//! Both functions stream: export writes line by line to any `core::fmt::Write` (e.g. a `String` ,
+//! or an adapter over a file), taking `O(log(N))` per element without allocating, and import
+//! consumes any iterator of lines (e.g. `str::lines` or `BufRead::lines`). Import is strict: it
+//! stops at the first line that does not parse, whose index is out of bound, or whose index is not
+//! greater than the previous one (which also rejects duplicates), and reports its line number.
+//!
+//! Owned trees convert from and to backing arrays, e.g. with `SparseFenwick::from_slice` and
+//! `SparseFenwick::to_vec` .
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::dump::{export_values, import_values, Format};
+//!
+//! let mut fw = vec![0i64; 1000];
+//! update(&mut fw, 3, 5);
+//! update(&mut fw, 700, -2);
+//!
+//! let mut csv = String::new();
+//! export_values(&fw, &mut csv, Format::Csv).unwrap();
+//! assert_eq!(csv, "index,value\n3,5\n700,-2\n");
+//!
+//! // patch a counter with a text editor, then restore
+//! let patched = csv.replace("700,-2", "700,4");
+//! let restored: Vec<i64> = import_values(patched.lines(), 1000, Format::Csv).unwrap();
+//! assert_eq!(prefix_sum(&restored, 999), 9);
+//!
+//! let mut json = String::new();
+//! export_values(&fw, &mut json, Format::JsonLines).unwrap();
+//! assert_eq!(json, "{\"index\":3,\"value\":5}\n{\"index\":700,\"value\":-2}\n");
+//!
+//! let err = import_values::<i64, _>(json.lines(), 500, Format::JsonLines).unwrap_err();
+//! assert_eq!(err.line, 2);
+//! # }
+//! ```
+//!
+
+use core::fmt;
+use core::ops::{AddAssign, SubAssign};
+#[cfg(feature = "alloc")]
+use core::str::FromStr;
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::array;
+
+/// Text format of a dump.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// Comma-separated values with a header line `index,value` .
+    Csv,
+    /// One JSON object `{"index":i,"value":x}` per line.
+    JsonLines,
+}
+
+/// Header line of [`Format::Csv`] .
+const CSV_HEADER: &str = "index,value";
+
+/// Writes the non-zero elements of the original array of `fenwick` to `writer` in `format` .
+/// Takes `O(N log(N))` and does not allocate.
+///
+/// Values are written with their `Display` implementation, which for [`Format::JsonLines`] must
+/// produce a JSON number (true for integers and finite floats).
+///
+/// # Errors
+///
+/// Returns the first error of `writer` .
+///
+pub fn export_values<T, W>(fenwick: &[T], writer: &mut W, format: Format) -> fmt::Result
+where
+    T: AddAssign + SubAssign + Clone + Default + PartialEq + fmt::Display,
+    W: fmt::Write + ?Sized
+{
+    span!("export_values", len = fenwick.len());
+    if format == Format::Csv {
+        writeln!(writer, "{}", CSV_HEADER)?;
+    }
+    let zero = T::default();
+    for i in 0..fenwick.len() {
+        let value = array::get(fenwick, i);
+        if value == zero {
+            continue;
+        }
+        match format {
+            Format::Csv => writeln!(writer, "{},{}", i, value)?,
+            Format::JsonLines => writeln!(writer, "{{\"index\":{},\"value\":{}}}", i, value)?,
+        }
+    }
+    Ok(())
+}
+
+/// Line of a dump rejected by [`import_values`] .
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImportError {
+    /// One-based line number (counting the CSV header).
+    pub line: usize,
+    /// Reason for rejecting the line.
+    pub kind: ImportErrorKind,
+}
+
+/// Reason for rejecting a line of a dump.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImportErrorKind {
+    /// The CSV header is missing or different from `index,value` .
+    Header,
+    /// The line does not have the structure of the format.
+    Syntax,
+    /// The index is not a valid `usize` .
+    Index,
+    /// The value could not be parsed as an element.
+    Value,
+    /// The index is out of bound for the length of the tree.
+    OutOfBound(usize),
+    /// The index is not greater than the index on the previous line.
+    OutOfOrder(usize),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            ImportErrorKind::Header => write!(f, "expected header `{}`", CSV_HEADER),
+            ImportErrorKind::Syntax => write!(f, "malformed line"),
+            ImportErrorKind::Index => write!(f, "invalid index"),
+            ImportErrorKind::Value => write!(f, "invalid value"),
+            ImportErrorKind::OutOfBound(i) => write!(f, "index {} out of bound", i),
+            ImportErrorKind::OutOfOrder(i) => write!(f, "index {} not in increasing order", i),
+        }
+    }
+}
+
+impl core::error::Error for ImportError {}
+
+/// Splits a line of [`Format::JsonLines`] into its index and value tokens.
+#[cfg(feature = "alloc")]
+fn split_json(line: &str) -> Option<(&str, &str)> {
+    let body = line.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let (first, second) = body.split_once(',')?;
+    let (mut index, mut value) = (None, None);
+    for field in [first, second] {
+        let (key, token) = field.split_once(':')?;
+        let slot = match key.trim() {
+            "\"index\"" => &mut index,
+            "\"value\"" => &mut value,
+            _ => return None,
+        };
+        if slot.replace(token.trim()).is_some() {
+            return None;
+        }
+    }
+    Some((index?, value?))
+}
+
+/// Reads a dump written by [`export_values`] into the backing array of a tree with `len` elements.
+/// Elements missing from the dump are zero. Takes `O(N)` plus `O(1)` per line.
+///
+/// # Errors
+///
+/// Returns an error for the first line that is rejected (see [module-level
+/// documentation](self)).
+///
+#[cfg(feature = "alloc")]
+pub fn import_values<T, L>(
+    lines: impl IntoIterator<Item = L>,
+    len: usize,
+    format: Format,
+) -> Result<Vec<T>, ImportError>
+where
+    T: AddAssign + Clone + Default + FromStr,
+    L: AsRef<str>
+{
+    span!("import_values", len);
+    let mut fenwick = vec![T::default(); len];
+    let mut lines = lines.into_iter().zip(1..);
+    if format == Format::Csv {
+        match lines.next() {
+            Some((header, _)) if header.as_ref() == CSV_HEADER => {}
+            _ => return Err(ImportError { line: 1, kind: ImportErrorKind::Header }),
+        }
+    }
+    let mut last = None;
+    for (line, number) in lines {
+        let error = |kind| ImportError { line: number, kind };
+        let line = line.as_ref();
+        let (index, value) = match format {
+            Format::Csv => line.split_once(','),
+            Format::JsonLines => split_json(line),
+        }
+        .ok_or(error(ImportErrorKind::Syntax))?;
+        let index: usize = index.parse().map_err(|_| error(ImportErrorKind::Index))?;
+        if value.is_empty() || value.starts_with('"') {
+            return Err(error(ImportErrorKind::Value));
+        }
+        let value: T = value.parse().map_err(|_| error(ImportErrorKind::Value))?;
+        if index >= len {
+            return Err(error(ImportErrorKind::OutOfBound(index)));
+        }
+        if last.is_some_and(|last| index <= last) {
+            return Err(error(ImportErrorKind::OutOfOrder(index)));
+        }
+        last = Some(index);
+        fenwick[index] = value;
+    }
+    array::build(&mut fenwick);
+    Ok(fenwick)
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+    use std::string::String;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in [0, 1, 2, 10, 300] {
+            for format in [Format::Csv, Format::JsonLines] {
+                let mut values = std::vec![0i64; len];
+                for value in values.iter_mut() {
+                    if rng.gen_bool(0.3) {
+                        *value = rng.gen_range(-1000..=1000);
+                    }
+                }
+                let mut fw = values.clone();
+                array::build(&mut fw);
+                let mut text = String::new();
+                export_values(&fw, &mut text, format).unwrap();
+                let header = (format == Format::Csv) as usize;
+                let nonzero = values.iter().filter(|&&x| x != 0).count();
+                assert_eq!(text.lines().count(), header + nonzero);
+                assert_eq!(import_values::<i64, _>(text.lines(), len, format), Ok(fw));
+            }
+        }
+        let fw = [0.5f64, 0.0, -1e-300];
+        let mut text = String::new();
+        export_values(&fw, &mut text, Format::JsonLines).unwrap();
+        assert_eq!(import_values::<f64, _>(text.lines(), 3, Format::JsonLines), Ok(fw.to_vec()));
+    }
+
+    #[test]
+    fn rejects() {
+        let reject = |text: &str, format| import_values::<i32, _>(text.lines(), 10, format);
+        let error = |line, kind| Err(ImportError { line, kind });
+        let csv = Format::Csv;
+        assert_eq!(reject("", csv), error(1, ImportErrorKind::Header));
+        assert_eq!(reject("value,index\n", csv), error(1, ImportErrorKind::Header));
+        assert_eq!(reject("index,value\n1,2\n3", csv), error(3, ImportErrorKind::Syntax));
+        assert_eq!(reject("index,value\n1,2\n\n", csv), error(3, ImportErrorKind::Syntax));
+        assert_eq!(reject("index,value\n-1,2", csv), error(2, ImportErrorKind::Index));
+        assert_eq!(reject("index,value\n1, 2", csv), error(2, ImportErrorKind::Value));
+        assert_eq!(reject("index,value\n1,2,3", csv), error(2, ImportErrorKind::Value));
+        assert_eq!(reject("index,value\n1,", csv), error(2, ImportErrorKind::Value));
+        assert_eq!(reject("index,value\n10,1", csv), error(2, ImportErrorKind::OutOfBound(10)));
+        assert_eq!(reject("index,value\n5,1\n5,2", csv), error(3, ImportErrorKind::OutOfOrder(5)));
+        assert_eq!(reject("index,value\n5,1\n4,2", csv), error(3, ImportErrorKind::OutOfOrder(4)));
+
+        let json = Format::JsonLines;
+        assert!(reject("{ \"value\" : 2 , \"index\" : 1 }", json).is_ok());
+        assert_eq!(reject("{\"index\":1}", json), error(1, ImportErrorKind::Syntax));
+        assert_eq!(reject("{\"index\":1,\"index\":2}", json), error(1, ImportErrorKind::Syntax));
+        assert_eq!(reject("{\"index\":1,\"count\":2}", json), error(1, ImportErrorKind::Syntax));
+        assert_eq!(reject("[1,2]", json), error(1, ImportErrorKind::Syntax));
+        assert_eq!(reject("{\"index\":1,\"value\":\"2\"}", json), error(1, ImportErrorKind::Value));
+        assert_eq!(reject("{\"index\":1.5,\"value\":2}", json), error(1, ImportErrorKind::Index));
+    }
+}
@@ -0,0 +1,258 @@
+//! Energy accumulator for turn scheduling, e.g. in roguelikes and fixed-timestep simulations.
+//!
+//! Each entity accumulates energy (credit) until it reaches a fixed threshold, at which point it is
+//! ready to act; acting spends energy. Energy is kept per entity, and a Fenwick tree counts the
+//! ready entities, so that the next ready entity is the first one at which the prefix count of
+//! ready entities reaches `1` . Accumulating, spending and selecting the next ready entity all take
+//! `O(log(N))` , and advancing every entity by one timestep takes `O(N)` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::energy::EnergyAccumulator;
+//!
+//! let speeds = [10, 25, 50];
+//! let mut acc = EnergyAccumulator::new(speeds.len(), 100);
+//! let mut turns = Vec::new();
+//! for _ in 0..4 {
+//!     acc.tick(&speeds);
+//!     while let Some(entity) = acc.next_ready() {
+//!         turns.push(entity);
+//!         acc.spend(entity, 100);
+//!     }
+//! }
+//! assert_eq!(turns, [2, 1, 2]);
+//! assert_eq!(acc.energy(0), 40);
+//! ```
+//!
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{build, lower_bound, prefix_sum, update};
+use crate::index::zero_based::up;
+
+/// Energy accumulator over a fixed number of entities.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EnergyAccumulator {
+    threshold: u64,
+    energy: Vec<u64>,
+    /// Fenwick tree over `1` for each ready entity and `0` otherwise.
+    ready: Vec<usize>,
+}
+
+impl EnergyAccumulator {
+    /// Creates an accumulator over `entities` entities with no energy, each ready to act once its
+    /// energy reaches `threshold` .
+    pub fn new(entities: usize, threshold: u64) -> Self {
+        let mut ready = vec![0; entities];
+        if threshold == 0 {
+            ready.fill(1);
+            build(&mut ready);
+        }
+        Self {
+            threshold,
+            energy: vec![0; entities],
+            ready,
+        }
+    }
+
+    /// Returns the number of entities.
+    pub fn len(&self) -> usize {
+        self.energy.len()
+    }
+
+    /// Returns `true` if there are no entities.
+    pub fn is_empty(&self) -> bool {
+        self.energy.is_empty()
+    }
+
+    /// Returns the energy an entity needs to be ready.
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    /// Returns the energy of `entity` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity >= self.len()` .
+    ///
+    pub fn energy(&self, entity: usize) -> u64 {
+        self.energy[entity]
+    }
+
+    /// Returns `true` if `entity` has reached the threshold.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity >= self.len()` .
+    ///
+    pub fn is_ready(&self, entity: usize) -> bool {
+        self.energy[entity] >= self.threshold
+    }
+
+    /// Returns the number of ready entities. Takes `O(log(N))` .
+    pub fn ready_count(&self) -> usize {
+        match self.ready.len() {
+            0 => 0,
+            len => prefix_sum(&self.ready, len - 1),
+        }
+    }
+
+    /// Adds `amount` to the energy of `entity` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity >= self.len()` .
+    ///
+    pub fn accumulate(&mut self, entity: usize, amount: u64) {
+        let was_ready = self.is_ready(entity);
+        self.energy[entity] += amount;
+        if !was_ready && self.is_ready(entity) {
+            update(&mut self.ready, entity, 1);
+        }
+    }
+
+    /// Advances one timestep, adding `gains[i]` to the energy of each entity `i` . Takes `O(N)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gains.len() != self.len()` .
+    ///
+    pub fn tick(&mut self, gains: &[u64]) {
+        assert_eq!(gains.len(), self.len());
+        for (energy, gain) in self.energy.iter_mut().zip(gains) {
+            *energy += gain;
+        }
+        for (ready, &energy) in self.ready.iter_mut().zip(&self.energy) {
+            *ready = (energy >= self.threshold) as usize;
+        }
+        build(&mut self.ready);
+    }
+
+    /// Subtracts `amount` (e.g. the cost of an action) from the energy of `entity` . Takes
+    /// `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity >= self.len()` , or if `amount` exceeds the energy of `entity` .
+    ///
+    pub fn spend(&mut self, entity: usize, amount: u64) {
+        assert!(amount <= self.energy[entity], "not enough energy");
+        let was_ready = self.is_ready(entity);
+        self.energy[entity] -= amount;
+        if was_ready && !self.is_ready(entity) {
+            self.unready(entity);
+        }
+    }
+
+    /// Resets the energy of `entity` to zero, returning its previous energy. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity >= self.len()` .
+    ///
+    pub fn reset(&mut self, entity: usize) -> u64 {
+        let energy = self.energy[entity];
+        self.spend(entity, energy);
+        energy
+    }
+
+    fn unready(&mut self, entity: usize) {
+        for ii in up(entity, self.ready.len()) {
+            self.ready[ii] -= 1;
+        }
+    }
+
+    /// Returns the ready entity with the smallest index, or `None` if no entity is ready. Takes
+    /// `O(log(N))` .
+    pub fn next_ready(&self) -> Option<usize> {
+        self.nth_ready(0)
+    }
+
+    /// Returns the first ready entity at or after `entity` , wrapping around to the start, or
+    /// `None` if no entity is ready. Takes `O(log(N))` .
+    ///
+    /// This serves ready entities in round-robin order, by passing one past the entity that acted
+    /// last.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity > self.len()` .
+    ///
+    pub fn next_ready_from(&self, entity: usize) -> Option<usize> {
+        assert!(entity <= self.len());
+        let before = if entity == 0 { 0 } else { prefix_sum(&self.ready, entity - 1) };
+        self.nth_ready(before).or_else(|| self.next_ready())
+    }
+
+    /// Returns the `k`-th (zero-based) ready entity in order of index, if any.
+    fn nth_ready(&self, k: usize) -> Option<usize> {
+        let entity = lower_bound(&self.ready, k + 1);
+        (entity < self.len()).then_some(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 0..24 {
+            let threshold = rng.gen_range(0..20);
+            let mut acc = EnergyAccumulator::new(len, threshold);
+            let mut naive = std::vec![0u64; len];
+            for _ in 0..len * 8 {
+                match rng.gen_range(0..4) {
+                    0 => {
+                        let gains: std::vec::Vec<u64> =
+                            (0..len).map(|_| rng.gen_range(0..5)).collect();
+                        acc.tick(&gains);
+                        for (x, gain) in naive.iter_mut().zip(&gains) {
+                            *x += gain;
+                        }
+                    }
+                    1 if len > 0 => {
+                        let i = rng.gen_range(0..len);
+                        let amount = rng.gen_range(0..10);
+                        acc.accumulate(i, amount);
+                        naive[i] += amount;
+                    }
+                    2 if len > 0 => {
+                        let i = rng.gen_range(0..len);
+                        let amount = rng.gen_range(0..=naive[i]);
+                        acc.spend(i, amount);
+                        naive[i] -= amount;
+                    }
+                    3 if len > 0 => {
+                        let i = rng.gen_range(0..len);
+                        assert_eq!(acc.reset(i), naive[i]);
+                        naive[i] = 0;
+                    }
+                    _ => {}
+                }
+                let ready: std::vec::Vec<usize> =
+                    (0..len).filter(|&i| naive[i] >= threshold).collect();
+                assert_eq!(acc.ready_count(), ready.len());
+                assert_eq!(acc.next_ready(), ready.first().copied());
+                for i in 0..=len {
+                    let expected = ready.iter().find(|&&j| j >= i).or(ready.first()).copied();
+                    assert_eq!(acc.next_ready_from(i), expected);
+                }
+                for (i, &x) in naive.iter().enumerate() {
+                    assert_eq!(acc.energy(i), x);
+                    assert_eq!(acc.is_ready(i), x >= threshold);
+                }
+            }
+        }
+    }
+}
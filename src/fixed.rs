@@ -0,0 +1,194 @@
+//! Decimal fixed-point element type, e.g. for currency.
+//!
+//! [`Fixed<SCALE>`](Fixed) is an `i64` count of units of `10^-SCALE` , so sums of amounts with at
+//! most `SCALE` decimal places are exact, without the rounding error of floating point or the
+//! overhead of big integers. It can be used directly as the element type of any additive tree.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::fixed::Fixed;
+//!
+//! type Cents = Fixed<2>;
+//!
+//! let balances = &mut [Cents::default(); 4];
+//! update(balances, 0, Cents::from_raw(10)); // 0.10
+//! update(balances, 1, Cents::from_raw(20)); // 0.20
+//! update(balances, 3, Cents::from_int(-1));
+//! assert_eq!(prefix_sum(balances, 1), Cents::from_raw(30));
+//! assert_eq!(prefix_sum(balances, 3).to_string(), "-0.70");
+//! assert_eq!(Cents::from_raw(i64::MAX).checked_add(Cents::from_raw(1)), None);
+//! ```
+//!
+
+use core::fmt;
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// Decimal fixed-point number with `SCALE` decimal places, stored as an `i64` count of units of
+/// `10^-SCALE` .
+///
+/// Arithmetic operators panic on overflow in debug builds, same as `i64` ; use the `checked_*`
+/// methods to detect overflow instead. `SCALE` must be at most 18.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<const SCALE: u32>(i64);
+
+impl<const SCALE: u32> Fixed<SCALE> {
+    /// Number of units in one, i.e. `10^SCALE` .
+    pub const ONE: i64 = 10i64.pow(SCALE);
+
+    /// Creates a number from a count of units of `10^-SCALE` .
+    pub const fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the count of units of `10^-SCALE` .
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    /// Creates a number from an integer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result overflows (in debug builds).
+    ///
+    pub const fn from_int(x: i64) -> Self {
+        Self(x * Self::ONE)
+    }
+
+    /// Creates a number from an integer, or returns `None` on overflow.
+    pub const fn checked_from_int(x: i64) -> Option<Self> {
+        match x.checked_mul(Self::ONE) {
+            Some(raw) => Some(Self(raw)),
+            None => None,
+        }
+    }
+
+    /// Returns `self + rhs` , or `None` on overflow.
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(raw) => Some(Self(raw)),
+            None => None,
+        }
+    }
+
+    /// Returns `self - rhs` , or `None` on overflow.
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(raw) => Some(Self(raw)),
+            None => None,
+        }
+    }
+
+    /// Returns `-self` , or `None` on overflow.
+    pub const fn checked_neg(self) -> Option<Self> {
+        match self.0.checked_neg() {
+            Some(raw) => Some(Self(raw)),
+            None => None,
+        }
+    }
+}
+
+impl<const SCALE: u32> fmt::Display for Fixed<SCALE> {
+    /// Formats the number in decimal with exactly `SCALE` decimal places.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let one = Self::ONE as u64;
+        if SCALE == 0 {
+            write!(f, "{}{}", sign, abs)
+        } else {
+            write!(f, "{}{}.{:0width$}", sign, abs / one, abs % one, width = SCALE as usize)
+        }
+    }
+}
+
+impl<const SCALE: u32> AddAssign for Fixed<SCALE> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl<const SCALE: u32> SubAssign for Fixed<SCALE> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl<const SCALE: u32> Add for Fixed<SCALE> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl<const SCALE: u32> Sub for Fixed<SCALE> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl<const SCALE: u32> Neg for Fixed<SCALE> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use std::string::ToString;
+
+    use rand::prelude::*;
+
+    use crate::array::{checked_prefix_sum, get, prefix_sum, update};
+
+    #[test]
+    fn display() {
+        assert_eq!(Fixed::<2>::from_raw(12345).to_string(), "123.45");
+        assert_eq!(Fixed::<2>::from_raw(-5).to_string(), "-0.05");
+        assert_eq!(Fixed::<3>::from_int(7).to_string(), "7.000");
+        assert_eq!(Fixed::<0>::from_raw(-42).to_string(), "-42");
+        assert_eq!(Fixed::<4>::from_raw(i64::MIN).to_string(), "-922337203685477.5808");
+    }
+
+    #[test]
+    fn checked() {
+        type F = Fixed<2>;
+        assert_eq!(F::checked_from_int(i64::MAX / 100), Some(F::from_raw(i64::MAX / 100 * 100)));
+        assert_eq!(F::checked_from_int(i64::MAX / 100 + 1), None);
+        assert_eq!(F::from_raw(i64::MIN).checked_sub(F::from_raw(1)), None);
+        assert_eq!(F::from_raw(i64::MIN).checked_neg(), None);
+        assert_eq!(F::from_raw(1).checked_add(F::from_raw(2)), Some(F::from_raw(3)));
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut fw = std::vec![Fixed::<2>::default(); len];
+            let mut naive = std::vec![0i64; len];
+            for _ in 0..len * 2 {
+                let i = rng.gen_range(0..len);
+                let x = rng.gen_range(-100_000..=100_000);
+                update(&mut fw, i, Fixed::from_raw(x));
+                naive[i] += x;
+            }
+            let mut sum = 0;
+            for (i, x) in naive.iter().enumerate() {
+                sum += x;
+                assert_eq!(get(&fw, i), Fixed::from_raw(*x));
+                assert_eq!(prefix_sum(&fw, i), Fixed::from_raw(sum));
+                assert_eq!(checked_prefix_sum(&fw, i), Some(Fixed::from_raw(sum)));
+            }
+        }
+    }
+}
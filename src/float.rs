@@ -0,0 +1,204 @@
+//! Query variants for floating-point trees that detect non-finite (NaN or infinite) nodes.
+//!
+//! A single NaN or infinity written into a float tree (e.g. from corrupted input) spreads into
+//! every node above it, and from there into the prefix sums of all later elements. The queries in
+//! this module check every node they visit, and report the first non-finite one as a
+//! [`NonFiniteError`] instead of returning a poisoned sum. The error identifies the offending node,
+//! the range of elements it covers, and the element that poisoned it, along with the partial sum
+//! of the nodes visited before it.
+//!
+//! Only non-finite nodes are reported: a sum of finite nodes that overflows to infinity is
+//! returned as is.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::update;
+//! use fenwick::float::try_prefix_sum;
+//!
+//! let fw = &mut [0.0f64; 8];
+//! update(fw, 1, 2.0);
+//! update(fw, 6, 3.0);
+//! assert_eq!(try_prefix_sum(fw, 7), Ok(5.0));
+//! update(fw, 2, f64::NAN);
+//! assert_eq!(try_prefix_sum(fw, 1), Ok(2.0));
+//! let err = try_prefix_sum(fw, 6).unwrap_err();
+//! assert_eq!(err.node, 3);
+//! assert_eq!(err.elements, 0..4);
+//! assert_eq!(err.element, 2);
+//! assert_eq!(err.partial, 3.0); // nodes 6 and 5
+//! ```
+//!
+
+use core::fmt;
+use core::ops::{AddAssign, Range};
+
+use crate::index::zero_based::{down, node_range};
+
+/// Element types that may hold non-finite values.
+pub trait CheckFinite: AddAssign + Clone + Default {
+    /// Returns `true` if the value is neither NaN nor infinite.
+    fn is_finite(&self) -> bool;
+}
+
+impl CheckFinite for f32 {
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+
+impl CheckFinite for f64 {
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+
+/// Non-finite node found by a query.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NonFiniteError<T> {
+    /// Index of the first non-finite node visited.
+    pub node: usize,
+    /// Range of elements covered by the node.
+    pub elements: Range<usize>,
+    /// Element that poisoned the node: the node itself is non-finite but all of its children are
+    /// finite.
+    pub element: usize,
+    /// Sum of the nodes visited before the non-finite one.
+    pub partial: T,
+}
+
+impl<T> fmt::Display for NonFiniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "non-finite node {} covering elements {:?} (first poisoned at element {})",
+            self.node, self.elements, self.element
+        )
+    }
+}
+
+impl<T: fmt::Debug> core::error::Error for NonFiniteError<T> {}
+
+/// Finds the element that poisoned non-finite node `i` , by descending into non-finite children
+/// until reaching a node whose children are all finite. Takes `O(log(N)^2)` .
+fn poisoned_element<T: CheckFinite>(fenwick: &[T], mut i: usize) -> usize {
+    'descend: loop {
+        let start = node_range(i).start;
+        // children of node `i` are `i - 1` , then each node just below the previous child's range
+        let mut child = i;
+        while child > start {
+            child -= 1;
+            if !fenwick[child].is_finite() {
+                i = child;
+                continue 'descend;
+            }
+            child = node_range(child).start;
+        }
+        return i;
+    }
+}
+
+/// Checked version of [`prefix_sum`](crate::array::prefix_sum) : returns an error describing the
+/// first non-finite node visited, if any.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// See [module-level documentation](self).
+///
+pub fn try_prefix_sum<T: CheckFinite>(fenwick: &[T], i: usize) -> Result<T, NonFiniteError<T>> {
+    assert!(i < fenwick.len());
+    let mut sum = T::default();
+    for ii in down(i) {
+        let x = &fenwick[ii];
+        if !x.is_finite() {
+            return Err(NonFiniteError {
+                node: ii,
+                elements: node_range(ii),
+                element: poisoned_element(fenwick, ii),
+                partial: sum,
+            });
+        }
+        sum += x.clone();
+    }
+    Ok(sum)
+}
+
+/// Returns the first non-finite node of the whole tree (with the same details as
+/// [`try_prefix_sum`] , and `partial` set to zero), or `None` if all nodes are finite. Takes
+/// `O(N)` .
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::array::update;
+/// use fenwick::float::find_non_finite;
+///
+/// let fw = &mut [0.0f32; 8];
+/// assert_eq!(find_non_finite(fw), None);
+/// update(fw, 5, f32::INFINITY);
+/// let err = find_non_finite(fw).unwrap();
+/// assert_eq!((err.node, err.element), (5, 5));
+/// ```
+///
+pub fn find_non_finite<T: CheckFinite>(fenwick: &[T]) -> Option<NonFiniteError<T>> {
+    let node = fenwick.iter().position(|x| !x.is_finite())?;
+    Some(NonFiniteError {
+        node,
+        elements: node_range(node),
+        element: poisoned_element(fenwick, node),
+        partial: T::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    use crate::array::{get, prefix_sum, update};
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut fw = std::vec![0.0f64; len];
+            for _ in 0..len {
+                update(&mut fw, rng.gen_range(0..len), rng.gen_range(-8..8) as f64);
+            }
+            assert_eq!(find_non_finite(&fw), None);
+            for i in 0..len {
+                assert_eq!(try_prefix_sum(&fw, i), Ok(prefix_sum(&fw, i)));
+            }
+            let bad = rng.gen_range(0..len);
+            let poison = if rng.gen_bool(0.5) { f64::NAN } else { f64::NEG_INFINITY };
+            update(&mut fw, bad, poison);
+            let err = find_non_finite(&fw).unwrap();
+            assert_eq!((err.node, err.element), (bad, bad));
+            for i in 0..len {
+                match try_prefix_sum(&fw, i) {
+                    Ok(sum) => {
+                        assert!(i < bad);
+                        assert_eq!(sum, prefix_sum(&fw, i));
+                    }
+                    Err(err) => {
+                        assert!(i >= bad);
+                        assert!(err.elements.contains(&bad));
+                        assert_eq!(err.element, bad);
+                        // the partial sum covers the elements after the offending node
+                        let total: f64 = (err.elements.end..=i).map(|j| get(&fw, j)).sum();
+                        assert_eq!(err.partial, total);
+                    }
+                }
+            }
+        }
+    }
+}
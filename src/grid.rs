@@ -0,0 +1,274 @@
+//! Weighted 2D grid (e.g. screen pixels or map tiles) for heatmaps, backed by a 2D Fenwick tree.
+//!
+//! Adding weight to a point and summing over a rectangle both take `O(log(W) log(H))` for a grid
+//! of `W` by `H` cells.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::grid::Grid2D;
+//!
+//! let mut heat = Grid2D::new(8, 6);
+//! heat.add_point(1, 1, 3);
+//! heat.add_point(2, 1, 4);
+//! heat.add_point(6, 4, 5);
+//! heat.add_point(7, 5, 1);
+//! assert_eq!(heat.rect_sum(0, 0, 2, 2), 7);
+//! assert_eq!(heat.rect_sum(2, 0, 7, 5), 10);
+//! assert_eq!(heat.rect_sum(3, 0, 5, 5), 0);
+//! // the 2x2 window with the largest total weight starts at (1, 0) (tied with (1, 1))
+//! assert_eq!(heat.hotspot_max_rect(2, 2), Some(((1, 0), 7)));
+//! assert_eq!(heat.hotspot_max_rect(1, 1), Some(((6, 4), 5)));
+//! ```
+//!
+
+use core::ops::{AddAssign, SubAssign};
+
+use alloc::vec::Vec;
+
+use crate::array::prefix_sum;
+use crate::nd::NdFenwick;
+
+/// Weighted 2D grid of `width` by `height` cells.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid2D<T> {
+    fenwick: NdFenwick<T, 2>,
+}
+
+impl<T> Grid2D<T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Creates a grid of `width` by `height` cells with zero weight.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            fenwick: NdFenwick::new([width, height]),
+        }
+    }
+
+    /// Returns the number of cells along the x axis.
+    pub fn width(&self) -> usize {
+        self.fenwick.shape()[0]
+    }
+
+    /// Returns the number of cells along the y axis.
+    pub fn height(&self) -> usize {
+        self.fenwick.shape()[1]
+    }
+
+    /// Adds weight `w` to cell `(x, y)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `(x, y)` is out of bound.
+    ///
+    pub fn add_point(&mut self, x: usize, y: usize, w: T) {
+        self.fenwick.update([x, y], w);
+    }
+
+    /// Returns the total weight of cells `(x, y)` with `x0 <= x <= x1` and `y0 <= y <= y1` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x0 > x1` , `y0 > y1` , or `(x1, y1)` is out of bound.
+    ///
+    pub fn rect_sum(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> T {
+        assert!(x0 <= x1 && y0 <= y1);
+        let mut sum = self.corner_sum(x1 + 1, y1 + 1);
+        sum -= self.corner_sum(x0, y1 + 1);
+        sum -= self.corner_sum(x1 + 1, y0);
+        sum += self.corner_sum(x0, y0);
+        sum
+    }
+
+    /// Returns the total weight of cells `(x, y)` with `x < x_end` and `y < y_end` .
+    fn corner_sum(&self, x_end: usize, y_end: usize) -> T {
+        if x_end == 0 || y_end == 0 {
+            T::default()
+        } else {
+            self.fenwick.prefix_sum([x_end - 1, y_end - 1])
+        }
+    }
+
+    /// Finds the smallest `x` such that the total weight of cells `(x', y)` with `x' <= x` and
+    /// `y0 <= y <= y1` is no less than `target` , or `self.width()` if there is no such `x` .
+    ///
+    /// Descends the tree along the x axis, taking `O(log(W) log(H))` instead of the
+    /// `O(log(W)^2 log(H))` of a binary search over [`Grid2D::rect_sum`] . This is useful e.g. for
+    /// weighted sampling of a column within a band of rows.
+    ///
+    /// The result is only meaningful if all weights are non-negative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y0 > y1` or `y1` is out of bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::grid::Grid2D;
+    ///
+    /// let mut heat = Grid2D::new(8, 4);
+    /// heat.add_point(1, 0, 2);
+    /// heat.add_point(3, 2, 5);
+    /// heat.add_point(6, 1, 1);
+    /// assert_eq!(heat.lower_bound_x(0, 3, 1), 1);
+    /// assert_eq!(heat.lower_bound_x(0, 3, 3), 3);
+    /// assert_eq!(heat.lower_bound_x(1, 3, 1), 3);
+    /// assert_eq!(heat.lower_bound_x(1, 3, 6), 6);
+    /// assert_eq!(heat.lower_bound_x(1, 3, 7), 8);
+    /// ```
+    ///
+    pub fn lower_bound_x(&self, y0: usize, y1: usize, target: T) -> usize
+    where
+        T: PartialOrd
+    {
+        let height = self.height();
+        assert!(y0 <= y1 && y1 < height);
+        let nodes = self.fenwick.as_slice();
+        // sum of node `x` of the x axis over `y0..=y1`
+        let band_sum = |x: usize| {
+            let row = &nodes[x * height..(x + 1) * height];
+            let mut sum = prefix_sum(row, y1);
+            if y0 > 0 {
+                sum -= prefix_sum(row, y0 - 1);
+            }
+            sum
+        };
+        let width = self.width();
+        // `pos` is the number of columns known to have prefix weight less than `target`
+        let mut pos = 0usize;
+        let mut sum = T::default();
+        let mut step = if width == 0 {
+            0
+        } else {
+            1usize << (usize::BITS - 1 - width.leading_zeros())
+        };
+        while step > 0 {
+            if pos + step <= width {
+                let mut next = sum.clone();
+                next += band_sum(pos + step - 1);
+                if next < target {
+                    sum = next;
+                    pos += step;
+                }
+            }
+            step >>= 1;
+        }
+        pos
+    }
+
+    /// Finds the `w` by `h` window with the largest total weight, returning its top-left cell and
+    /// total weight, or `None` if the window does not fit in the grid (or is empty).
+    ///
+    /// Ties are broken towards the smallest `x` , then the smallest `y` . Takes
+    /// `O(W H log(W) log(H))` time to tabulate all prefix sums once, and `O(W H)` extra space.
+    ///
+    pub fn hotspot_max_rect(&self, w: usize, h: usize) -> Option<((usize, usize), T)>
+    where
+        T: PartialOrd
+    {
+        let (width, height) = (self.width(), self.height());
+        if w == 0 || h == 0 || w > width || h > height {
+            return None;
+        }
+        // `table[x * (height + 1) + y] == self.corner_sum(x, y)`
+        let stride = height + 1;
+        let mut table = Vec::with_capacity((width + 1) * stride);
+        for x in 0..=width {
+            for y in 0..=height {
+                table.push(self.corner_sum(x, y));
+            }
+        }
+        let mut best: Option<((usize, usize), T)> = None;
+        for x in 0..=width - w {
+            for y in 0..=height - h {
+                let mut sum = table[(x + w) * stride + y + h].clone();
+                sum -= table[x * stride + y + h].clone();
+                sum -= table[(x + w) * stride + y].clone();
+                sum += table[x * stride + y].clone();
+                if best.as_ref().map_or(true, |(_, max)| sum > *max) {
+                    best = Some(((x, y), sum));
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for (width, height) in [(1, 1), (7, 3), (4, 9), (16, 16)] {
+            let mut grid = Grid2D::new(width, height);
+            let mut naive = std::vec![std::vec![0i64; height]; width];
+            for _ in 0..32 {
+                let (x, y) = (rng.gen_range(0..width), rng.gen_range(0..height));
+                let w = rng.gen_range(-10..=100);
+                grid.add_point(x, y, w);
+                naive[x][y] += w;
+            }
+            let naive_sum = |x0: usize, y0: usize, x1: usize, y1: usize| -> i64 {
+                (x0..=x1).map(|x| naive[x][y0..=y1].iter().sum::<i64>()).sum()
+            };
+            for _ in 0..32 {
+                let (xa, xb) = (rng.gen_range(0..width), rng.gen_range(0..width));
+                let (ya, yb) = (rng.gen_range(0..height), rng.gen_range(0..height));
+                let (x0, x1, y0, y1) = (xa.min(xb), xa.max(xb), ya.min(yb), ya.max(yb));
+                assert_eq!(grid.rect_sum(x0, y0, x1, y1), naive_sum(x0, y0, x1, y1));
+            }
+            for w in 1..=width {
+                for h in 1..=height {
+                    let ((x, y), max) = grid.hotspot_max_rect(w, h).unwrap();
+                    assert_eq!(naive_sum(x, y, x + w - 1, y + h - 1), max);
+                    for x in 0..=width - w {
+                        for y in 0..=height - h {
+                            assert!(naive_sum(x, y, x + w - 1, y + h - 1) <= max);
+                        }
+                    }
+                }
+            }
+            assert_eq!(grid.hotspot_max_rect(width + 1, 1), None);
+        }
+    }
+
+    #[test]
+    fn lower_bound_x_randoms() {
+        let mut rng = thread_rng();
+        for (width, height) in [(1, 1), (7, 3), (4, 9), (16, 16), (33, 5)] {
+            let mut grid = Grid2D::new(width, height);
+            let mut naive = std::vec![std::vec![0u32; height]; width];
+            for _ in 0..32 {
+                let (x, y) = (rng.gen_range(0..width), rng.gen_range(0..height));
+                let w = rng.gen_range(0..=5);
+                grid.add_point(x, y, w);
+                naive[x][y] += w;
+            }
+            for _ in 0..32 {
+                let (ya, yb) = (rng.gen_range(0..height), rng.gen_range(0..height));
+                let (y0, y1) = (ya.min(yb), ya.max(yb));
+                let total: u32 = naive.iter().map(|col| col[y0..=y1].iter().sum::<u32>()).sum();
+                for target in 0..=total + 1 {
+                    let expected = naive.iter()
+                        .scan(0, |s, col| {
+                            *s += col[y0..=y1].iter().sum::<u32>();
+                            Some(*s)
+                        })
+                        .position(|s| s >= target)
+                        .unwrap_or(width);
+                    assert_eq!(grid.lower_bound_x(y0, y1, target), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Group of Fenwick trees over the same indices, updated together.
+//!
+//! A [`TreeGroup<T, K>`](TreeGroup) stores `K` trees interleaved in one backing array, so one
+//! logical update (e.g. count, sum and sum of squares of a sample) walks the nodes once and updates
+//! all trees together, and they can never get out of sync.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::group::TreeGroup;
+//!
+//! // count, sum and sum of squares of samples recorded at each index
+//! let mut g = TreeGroup::<f64, 3>::new(10);
+//! g.record(2, 1.0);
+//! g.record(3, 3.0);
+//! g.record(8, 5.0);
+//! g.record(9, 7.0);
+//! assert_eq!(g.prefix_sum(3), [2.0, 4.0, 10.0]);
+//! assert_eq!(g.mean_upto(3), Some(2.0));
+//! assert_eq!(g.variance_upto(9), Some(5.0));
+//! assert_eq!(g.mean_upto(1), None);
+//!
+//! let mut g = TreeGroup::<i32, 2>::new(4);
+//! g.update(1, [1, 10]);
+//! g.update(3, [1, -4]);
+//! assert_eq!(g.prefix_sum(3), [2, 6]);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::index::zero_based::{down, up};
+
+/// Group of `K` Fenwick trees over the same indices.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TreeGroup<T, const K: usize> {
+    len: usize,
+    /// Node `i` of tree `k` is `nodes[i * K + k]` .
+    nodes: Vec<T>,
+}
+
+impl<T, const K: usize> TreeGroup<T, K>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a group of `K` trees, each with `len` zero elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total number of nodes overflows `usize` .
+    ///
+    pub fn new(len: usize) -> Self {
+        let total = len.checked_mul(K).expect("too many nodes");
+        Self {
+            len,
+            nodes: vec![T::default(); total],
+        }
+    }
+
+    /// Returns the number of elements in each tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Conceptually performs `a_k[i] += deltas[k]` on the original array `a_k` of every tree `k` .
+    /// Takes `O(K log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, deltas: [T; K]) {
+        for ii in up(i, self.len) {
+            for (node, delta) in self.nodes[ii * K..(ii + 1) * K].iter_mut().zip(&deltas) {
+                *node += delta.clone();
+            }
+        }
+    }
+
+    /// Conceptually calculates `a_k[0] + ... + a_k[i]` on the original array `a_k` of every tree
+    /// `k` . Takes `O(K log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> [T; K] {
+        assert!(i < self.len);
+        let mut sums: [T; K] = core::array::from_fn(|_| T::default());
+        for ii in down(i) {
+            for (sum, node) in sums.iter_mut().zip(&self.nodes[ii * K..(ii + 1) * K]) {
+                *sum += node.clone();
+            }
+        }
+        sums
+    }
+}
+
+impl TreeGroup<f64, 3> {
+    /// Records sample `x` at index `i` , adding `1` , `x` and `x * x` to the three trees (count,
+    /// sum and sum of squares).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn record(&mut self, i: usize, x: f64) {
+        self.update(i, [1.0, x, x * x]);
+    }
+
+    /// Returns the mean of samples recorded at indices up to and including `i` , or `None` if
+    /// there are none.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn mean_upto(&self, i: usize) -> Option<f64> {
+        let [count, sum, _] = self.prefix_sum(i);
+        (count > 0.0).then(|| sum / count)
+    }
+
+    /// Returns the population variance of samples recorded at indices up to and including `i` , or
+    /// `None` if there are none.
+    ///
+    /// This is computed from the sum of squares, which loses precision if the mean is large
+    /// compared to the standard deviation; see [`StatsFenwick`](crate::stats::StatsFenwick) for a
+    /// more robust alternative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn variance_upto(&self, i: usize) -> Option<f64> {
+        let [count, sum, sum_sq] = self.prefix_sum(i);
+        if count > 0.0 {
+            let mean = sum / count;
+            Some((sum_sq / count - mean * mean).max(0.0))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut g = TreeGroup::<i64, 3>::new(len);
+            let mut naive = std::vec![[0i64; 3]; len];
+            for _ in 0..len * 2 {
+                let i = rng.gen_range(0..len);
+                let deltas = [(); 3].map(|_| rng.gen_range(-100..=100));
+                g.update(i, deltas);
+                for (x, d) in naive[i].iter_mut().zip(deltas) {
+                    *x += d;
+                }
+            }
+            let mut sums = [0i64; 3];
+            for (i, row) in naive.iter().enumerate() {
+                for (s, x) in sums.iter_mut().zip(row) {
+                    *s += x;
+                }
+                assert_eq!(g.prefix_sum(i), sums);
+            }
+        }
+    }
+
+    #[test]
+    fn mean_variance() {
+        let mut rng = thread_rng();
+        let mut g = TreeGroup::<f64, 3>::new(32);
+        let mut samples = std::vec![];
+        for _ in 0..200 {
+            let i = rng.gen_range(0..32);
+            let x = rng.gen_range(-10.0..10.0);
+            g.record(i, x);
+            samples.push((i, x));
+        }
+        for i in 0..32 {
+            let xs: std::vec::Vec<f64> = samples.iter().filter(|s| s.0 <= i).map(|s| s.1).collect();
+            if xs.is_empty() {
+                assert_eq!(g.mean_upto(i), None);
+                continue;
+            }
+            let n = xs.len() as f64;
+            let mean = xs.iter().sum::<f64>() / n;
+            let var = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+            assert!((g.mean_upto(i).unwrap() - mean).abs() < 1e-9);
+            assert!((g.variance_upto(i).unwrap() - var).abs() < 1e-9);
+        }
+    }
+}
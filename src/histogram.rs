@@ -0,0 +1,715 @@
+//! Frequency histogram over a fixed number of buckets, backed by a Fenwick tree of counts.
+//!
+//! Recording a sample, counting samples below a bucket and finding the bucket of a percentile all
+//! take `O(log(N))` where `N` is the number of buckets.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::histogram::Histogram;
+//!
+//! // latency histogram with 1ms buckets from 0ms to 99ms
+//! let mut h = Histogram::new(100);
+//! for ms in [3, 5, 5, 7, 12, 12, 12, 40, 41, 95] {
+//!     h.record(ms);
+//! }
+//! assert_eq!(h.total(), 10);
+//! assert_eq!(h.count_below(5), 1);
+//! assert_eq!(h.count_below(12), 4);
+//! assert_eq!(h.percentile(50.0), Some(12));
+//! assert_eq!(h.percentile(90.0), Some(41));
+//! assert_eq!(h.percentile(100.0), Some(95));
+//!
+//! let mut other = Histogram::new(100);
+//! other.record(1);
+//! h.merge(&other);
+//! assert_eq!(h.total(), 11);
+//! assert_eq!(h.percentile(0.0), Some(1));
+//! ```
+//!
+
+use core::ops::Range;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{
+    build, get, heavy_ranges, lower_bound, merge_add, prefix_sum, top_k, unbuild, update,
+};
+use crate::index::zero_based::up;
+
+/// Frequency histogram over a fixed number of buckets.
+///
+/// Two histograms are equal if and only if they have the same number of buckets and the same count
+/// in each bucket.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Histogram {
+    fenwick: Vec<u64>,
+    total: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram with `buckets` buckets.
+    pub fn new(buckets: usize) -> Self {
+        Self {
+            fenwick: vec![0; buckets],
+            total: 0,
+        }
+    }
+
+    /// Returns the number of buckets.
+    pub fn buckets(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns the total number of recorded samples.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Records one sample in `bucket`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is out of bound.
+    ///
+    pub fn record(&mut self, bucket: usize) {
+        self.record_n(bucket, 1);
+    }
+
+    /// Records `n` samples in `bucket`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is out of bound.
+    ///
+    pub fn record_n(&mut self, bucket: usize, n: u64) {
+        update(&mut self.fenwick, bucket, n);
+        self.total += n;
+    }
+
+    /// Removes one previously recorded sample from `bucket`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is out of bound or has no samples.
+    ///
+    pub fn remove(&mut self, bucket: usize) {
+        self.remove_n(bucket, 1);
+    }
+
+    /// Removes `n` previously recorded samples from `bucket`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is out of bound or has less than `n` samples.
+    ///
+    pub fn remove_n(&mut self, bucket: usize, n: u64) {
+        assert!(self.count(bucket) >= n);
+        for ii in up(bucket, self.buckets()) {
+            self.fenwick[ii] -= n;
+        }
+        self.total -= n;
+    }
+
+    /// Returns the number of samples in `bucket`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is out of bound.
+    ///
+    pub fn count(&self, bucket: usize) -> u64 {
+        get(&self.fenwick, bucket)
+    }
+
+    /// Returns the number of samples in buckets strictly below `bucket`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket > self.buckets()` .
+    ///
+    pub fn count_below(&self, bucket: usize) -> u64 {
+        assert!(bucket <= self.buckets());
+        if bucket == 0 {
+            0
+        } else {
+            prefix_sum(&self.fenwick, bucket - 1)
+        }
+    }
+
+    /// Returns the bucket containing the `p`-th percentile sample (nearest-rank method), or `None`
+    /// if the histogram is empty.
+    ///
+    /// `percentile(0.0)` is the lowest non-empty bucket and `percentile(100.0)` is the highest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not within `0.0..=100.0` .
+    ///
+    pub fn percentile(&self, p: f64) -> Option<usize> {
+        assert!((0.0..=100.0).contains(&p));
+        if self.total == 0 {
+            return None;
+        }
+        let exact = p * self.total as f64 / 100.0;
+        let mut rank = exact as u64;
+        if (rank as f64) < exact {
+            rank += 1;
+        }
+        let rank = rank.clamp(1, self.total);
+        Some(lower_bound(&self.fenwick, rank))
+    }
+
+    /// Returns the fraction of samples in buckets up to and including `bucket` , or `None` if the
+    /// histogram is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is out of bound.
+    ///
+    pub fn cdf(&self, bucket: usize) -> Option<f64> {
+        assert!(bucket < self.buckets());
+        if self.total == 0 {
+            return None;
+        }
+        Some(self.count_below(bucket + 1) as f64 / self.total as f64)
+    }
+
+    /// Returns the smallest bucket whose [`cdf`](Self::cdf) is no less than `p` , or `None` if the
+    /// histogram is empty.
+    ///
+    /// The result is always a non-empty bucket: empty buckets never increase the cdf, so ties are
+    /// broken towards the non-empty bucket where the cdf reaches `p` . In particular,
+    /// `inverse_cdf(0.0)` is the lowest non-empty bucket. For any non-empty bucket `i` ,
+    /// `inverse_cdf(cdf(i))` is exactly `i` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not within `0.0..=1.0` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::histogram::Histogram;
+    ///
+    /// let mut h = Histogram::new(10);
+    /// assert_eq!(h.cdf(0), None);
+    /// assert_eq!(h.inverse_cdf(0.5), None);
+    /// h.record_n(2, 1);
+    /// h.record_n(7, 3);
+    /// assert_eq!(h.cdf(1), Some(0.0));
+    /// assert_eq!(h.cdf(2), Some(0.25));
+    /// assert_eq!(h.cdf(6), Some(0.25));
+    /// assert_eq!(h.cdf(9), Some(1.0));
+    /// assert_eq!(h.inverse_cdf(0.0), Some(2));
+    /// assert_eq!(h.inverse_cdf(0.25), Some(2));
+    /// assert_eq!(h.inverse_cdf(0.26), Some(7));
+    /// assert_eq!(h.inverse_cdf(1.0), Some(7));
+    /// ```
+    ///
+    pub fn inverse_cdf(&self, p: f64) -> Option<usize> {
+        assert!((0.0..=1.0).contains(&p));
+        if self.total == 0 {
+            return None;
+        }
+        // find the smallest rank such that `rank / total >= p` , evaluated exactly as in `cdf`
+        let total = self.total as f64;
+        let mut rank = (p * total) as u64;
+        while rank > 0 && (rank - 1) as f64 / total >= p {
+            rank -= 1;
+        }
+        while (rank as f64) / total < p {
+            rank += 1;
+        }
+        let rank = rank.clamp(1, self.total);
+        Some(lower_bound(&self.fenwick, rank))
+    }
+
+    /// Returns the `k` buckets with the most samples, in decreasing order of count (ties broken by
+    /// smaller bucket). Takes `O(N + k log(k))` . See [`top_k`] .
+    pub fn top_k(&self, k: usize) -> Vec<usize> {
+        top_k(&self.fenwick, k)
+    }
+
+    /// Returns the maximal ranges of consecutive buckets that each hold more than fraction `theta`
+    /// of all samples, in increasing order, e.g. hot spans of a latency timeline.
+    ///
+    /// Takes `O(log(N)^2 / theta)` rather than scanning all buckets. See [`heavy_ranges`] .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::histogram::Histogram;
+    ///
+    /// let mut h = Histogram::new(10);
+    /// h.record_n(2, 30);
+    /// h.record_n(3, 25);
+    /// h.record_n(7, 40);
+    /// h.record_n(8, 5);
+    /// assert_eq!(h.heavy_ranges(0.1), [2..4, 7..8]);
+    /// assert_eq!(h.heavy_ranges(0.5), []);
+    /// ```
+    ///
+    pub fn heavy_ranges(&self, theta: f64) -> Vec<Range<usize>> {
+        heavy_ranges(&self.fenwick, (theta * self.total as f64) as u64)
+    }
+
+    /// Adds all samples recorded in `other` into `self`.
+    ///
+    /// Takes `O(N)` . See [`merge_add`] .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` has a different number of buckets.
+    ///
+    pub fn merge(&mut self, other: &Histogram) {
+        merge_add(&mut self.fenwick, &other.fenwick);
+        self.total += other.total;
+    }
+
+    /// Adds all samples recorded in `other` into `self` , where both histograms divide the same
+    /// range of values evenly into possibly different numbers of buckets.
+    ///
+    /// Bucket `b` of `other` (out of `M`) covers the same values as fraction `b / M` to
+    /// `(b + 1) / M` of the buckets of `self` (out of `N`), and its samples are counted in the
+    /// bucket of `self` containing the start of that range, i.e. bucket `b * N / M` . This is exact
+    /// when `M` is a multiple of `N` (downsampling into coarser buckets).
+    ///
+    /// Takes `O(N + M)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` has no buckets but `other` has samples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::histogram::Histogram;
+    ///
+    /// let mut coarse = Histogram::new(10); // 10ms buckets from 0ms to 99ms
+    /// let mut fine = Histogram::new(100); // 1ms buckets from 0ms to 99ms
+    /// fine.record(3);
+    /// fine.record(15);
+    /// fine.record(19);
+    /// coarse.merge_rescaled(&fine);
+    /// assert_eq!(coarse.count(0), 1);
+    /// assert_eq!(coarse.count(1), 2);
+    /// ```
+    ///
+    pub fn merge_rescaled(&mut self, other: &Histogram) {
+        let (n, m) = (self.buckets(), other.buckets());
+        if m == 0 || other.total == 0 {
+            return;
+        }
+        assert!(n > 0);
+        span!("merge_rescaled", buckets = n, other_buckets = m);
+        let mut counts = other.fenwick.clone();
+        unbuild(&mut counts);
+        let mut rescaled = vec![0u64; n];
+        for (b, c) in counts.into_iter().enumerate() {
+            rescaled[(b as u128 * n as u128 / m as u128) as usize] += c;
+        }
+        build(&mut rescaled);
+        merge_add(&mut self.fenwick, &rescaled);
+        self.total += other.total;
+    }
+
+    /// Exports an approximate summary of the distribution as a polyline of the cumulative count,
+    /// with at most `max_points` points `(bucket, number of samples in buckets 0..=bucket)` .
+    ///
+    /// The points are taken at the first non-empty bucket and at evenly spaced ranks up to the
+    /// total, so they are strictly increasing in both coordinates, and the last point counts all
+    /// samples. An empty histogram has an empty summary. Takes `O(max_points log(N))` .
+    ///
+    /// See [`Histogram::from_summary`] for the inverse.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_points < 2` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::histogram::Histogram;
+    ///
+    /// let mut h = Histogram::new(100);
+    /// for ms in [3, 5, 5, 7, 12, 12, 12, 40, 41, 95] {
+    ///     h.record(ms);
+    /// }
+    /// let summary = h.to_quantile_summary(3);
+    /// assert_eq!(summary, [(3, 1), (12, 7), (95, 10)]);
+    ///
+    /// let approx = Histogram::from_summary(100, &summary);
+    /// assert_eq!(approx.total(), 10);
+    /// assert_eq!(approx.count_below(13), 7);
+    /// assert_eq!(approx.percentile(100.0), Some(95));
+    /// ```
+    ///
+    pub fn to_quantile_summary(&self, max_points: usize) -> Vec<(usize, u64)> {
+        assert!(max_points >= 2);
+        let mut points: Vec<(usize, u64)> = Vec::with_capacity(max_points);
+        if self.total == 0 {
+            return points;
+        }
+        let steps = (max_points - 1) as u128;
+        let ranks = (0..=steps).map(|j| if j == 0 {
+            1
+        } else {
+            (j * self.total as u128).div_ceil(steps) as u64
+        });
+        for rank in ranks {
+            let bucket = lower_bound(&self.fenwick, rank);
+            if points.last().is_some_and(|&(last, _)| last == bucket) {
+                continue;
+            }
+            points.push((bucket, prefix_sum(&self.fenwick, bucket)));
+        }
+        points
+    }
+
+    /// Creates a histogram with `buckets` buckets from a summary exported by
+    /// [`Histogram::to_quantile_summary`] (or any polyline of the cumulative count in the same
+    /// form).
+    ///
+    /// The samples of the first point are put in its bucket. The samples between two consecutive
+    /// points are spread evenly over the buckets after the former up to and including the latter,
+    /// so the cumulative count at each point is exact. Takes `O(N)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if the points are not strictly increasing in bucket and non-decreasing in
+    /// cumulative count, or if any bucket is out of bound.
+    ///
+    /// # Examples
+    ///
+    /// See [`Histogram::to_quantile_summary`] .
+    ///
+    pub fn from_summary(buckets: usize, summary: &[(usize, u64)]) -> Self {
+        let mut counts = vec![0u64; buckets];
+        let mut prev: Option<(usize, u64)> = None;
+        for &(bucket, cumulative) in summary {
+            assert!(bucket < buckets);
+            match prev {
+                None => counts[bucket] = cumulative,
+                Some((prev_bucket, prev_cumulative)) => {
+                    assert!(prev_bucket < bucket && prev_cumulative <= cumulative);
+                    let n = (cumulative - prev_cumulative) as u128;
+                    let width = (bucket - prev_bucket) as u128;
+                    // remainder goes to the last buckets; `n * width` may overflow `u64`
+                    for (k, count) in counts[prev_bucket + 1..=bucket].iter_mut().enumerate() {
+                        let k = k as u128;
+                        *count = (n * (k + 1) / width - n * k / width) as u64;
+                    }
+                }
+            }
+            prev = Some((bucket, cumulative));
+        }
+        build(&mut counts);
+        Self {
+            fenwick: counts,
+            total: prev.map_or(0, |(_, cumulative)| cumulative),
+        }
+    }
+}
+
+/// Percentile thresholds on a [`Histogram`] , e.g. "p99 above 250ms", reporting which thresholds
+/// were crossed (in either direction) since the last check.
+///
+/// Each threshold is checked with [`Histogram::percentile`] , so checking after a batch of
+/// recorded samples takes `O(W log(N))` for `W` thresholds, without computing any other
+/// percentile.
+///
+/// # Examples
+///
+/// ```
+/// use fenwick::histogram::{Crossing, Histogram, ThresholdWatcher};
+///
+/// // 1ms buckets from 0ms to 999ms
+/// let mut h = Histogram::new(1000);
+/// let mut watcher = ThresholdWatcher::new();
+/// let p99 = watcher.watch(99.0, 250);
+/// let p50 = watcher.watch(50.0, 100);
+///
+/// for ms in 0..100 {
+///     h.record(ms);
+/// }
+/// assert_eq!(watcher.check(&h), []);
+///
+/// h.record_n(300, 2);
+/// assert_eq!(watcher.check(&h), [Crossing { id: p99, above: true }]);
+/// assert_eq!(watcher.check(&h), []);
+/// assert!(watcher.is_above(p99) && !watcher.is_above(p50));
+///
+/// h.record_n(10, 200);
+/// assert_eq!(watcher.check(&h), [Crossing { id: p99, above: false }]);
+/// ```
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThresholdWatcher {
+    /// `(p, bucket)` of each threshold.
+    thresholds: Vec<(f64, usize)>,
+    /// Whether each threshold was exceeded at the last check.
+    above: Vec<bool>,
+}
+
+/// A threshold of a [`ThresholdWatcher`] that was crossed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Crossing {
+    /// Threshold returned by [`ThresholdWatcher::watch`] .
+    pub id: usize,
+    /// `true` if the percentile rose above the threshold, or `false` if it fell back to or below.
+    pub above: bool,
+}
+
+impl ThresholdWatcher {
+    /// Creates a watcher with no thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of thresholds.
+    pub fn len(&self) -> usize {
+        self.thresholds.len()
+    }
+
+    /// Returns `true` if there are no thresholds.
+    pub fn is_empty(&self) -> bool {
+        self.thresholds.is_empty()
+    }
+
+    /// Adds the threshold "the `p`-th percentile is above `bucket` ", i.e.
+    /// `histogram.percentile(p) > Some(bucket)` , and returns its id. The threshold is initially
+    /// not exceeded, so the next check reports it if it is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not within `0.0..=100.0` .
+    ///
+    pub fn watch(&mut self, p: f64, bucket: usize) -> usize {
+        assert!((0.0..=100.0).contains(&p));
+        self.thresholds.push((p, bucket));
+        self.above.push(false);
+        self.thresholds.len() - 1
+    }
+
+    /// Returns `true` if threshold `id` was exceeded at the last check.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id >= self.len()` .
+    ///
+    pub fn is_above(&self, id: usize) -> bool {
+        self.above[id]
+    }
+
+    /// Checks all thresholds against `histogram` , and returns those crossed since the last
+    /// check, in order of id. An empty histogram exceeds no threshold. Takes `O(W log(N))` .
+    pub fn check(&mut self, histogram: &Histogram) -> Vec<Crossing> {
+        let mut crossings = Vec::new();
+        for (id, (&(p, bucket), above)) in self.thresholds.iter().zip(&mut self.above).enumerate() {
+            let now = histogram.percentile(p).is_some_and(|b| b > bucket);
+            if now != *above {
+                *above = now;
+                crossings.push(Crossing { id, above: now });
+            }
+        }
+        crossings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for buckets in 1..64 {
+            let mut h = Histogram::new(buckets);
+            let mut naive = std::vec![0u64; buckets];
+            let dist = rand::distributions::Uniform::new(0, buckets);
+            for bucket in (&mut rng).sample_iter(dist).take(buckets * 4) {
+                h.record(bucket);
+                naive[bucket] += 1;
+            }
+            let sorted = naive.iter().enumerate()
+                .flat_map(|(i, &n)| std::iter::repeat(i).take(n as usize))
+                .collect_vec();
+            for bucket in 0..=buckets {
+                assert_eq!(h.count_below(bucket), naive[..bucket].iter().sum::<u64>());
+            }
+            for p in 0..=100 {
+                let rank = (p * sorted.len()).div_ceil(100).max(1);
+                assert_eq!(h.percentile(p as f64), Some(sorted[rank - 1]));
+            }
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = Histogram::new(4);
+        let mut b = Histogram::new(4);
+        a.record_n(0, 2);
+        b.record_n(3, 6);
+        a.merge(&b);
+        assert_eq!(a.total(), 8);
+        assert_eq!(a.top_k(4), [3, 0, 1, 2]);
+        assert_eq!(a.count_below(3), 2);
+        assert_eq!(a.percentile(25.0), Some(0));
+        assert_eq!(a.percentile(26.0), Some(3));
+        assert_eq!(Histogram::new(4).percentile(50.0), None);
+    }
+
+    #[test]
+    fn merge_rescaled_randoms() {
+        let mut rng = thread_rng();
+        for _ in 0..64 {
+            let n = rng.gen_range(1..32);
+            let m = rng.gen_range(1..64);
+            let mut a = Histogram::new(n);
+            let mut b = Histogram::new(m);
+            let mut naive = std::vec![0u64; n];
+            for _ in 0..rng.gen_range(0..100) {
+                let bucket = rng.gen_range(0..n);
+                a.record(bucket);
+                naive[bucket] += 1;
+            }
+            for _ in 0..rng.gen_range(0..100) {
+                let bucket = rng.gen_range(0..m);
+                b.record(bucket);
+                naive[bucket * n / m] += 1;
+            }
+            a.merge_rescaled(&b);
+            assert_eq!(a.total(), naive.iter().sum::<u64>());
+            for (bucket, count) in naive.iter().enumerate() {
+                assert_eq!(a.count(bucket), *count);
+            }
+        }
+    }
+
+    #[test]
+    fn summary_randoms() {
+        let mut rng = thread_rng();
+        for buckets in 1..64 {
+            let mut h = Histogram::new(buckets);
+            for _ in 0..rng.gen_range(0..200) {
+                h.record(rng.gen_range(0..buckets));
+            }
+            for max_points in [2, 3, 5, 100] {
+                let summary = h.to_quantile_summary(max_points);
+                assert!(summary.len() <= max_points);
+                assert!(summary.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+                for &(bucket, cumulative) in &summary {
+                    assert_eq!(h.count_below(bucket) + h.count(bucket), cumulative);
+                }
+                let approx = Histogram::from_summary(buckets, &summary);
+                assert_eq!(approx.total(), h.total());
+                assert_eq!(approx.to_quantile_summary(max_points).last(), summary.last());
+                for &(bucket, cumulative) in &summary {
+                    assert_eq!(approx.count_below(bucket) + approx.count(bucket), cumulative);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn summary_large_counts() {
+        // `n * width` overflows `u64`
+        let n = u64::MAX / 2;
+        let approx = Histogram::from_summary(8, &[(0, 0), (7, n)]);
+        assert_eq!(approx.total(), n);
+        assert_eq!(approx.count_below(7) + approx.count(7), n);
+        let counts: std::vec::Vec<u64> = (1..8).map(|bucket| approx.count(bucket)).collect();
+        assert!(counts.iter().all(|&count| count == n / 7 || count == n / 7 + 1));
+        assert_eq!(counts.iter().sum::<u64>(), n);
+    }
+
+    #[test]
+    fn cdf_randoms() {
+        let mut rng = thread_rng();
+        for buckets in 1..64 {
+            let mut h = Histogram::new(buckets);
+            let dist = rand::distributions::Uniform::new(0, buckets);
+            let samples = rng.gen_range(1..200);
+            for bucket in (&mut rng).sample_iter(dist).take(samples) {
+                h.record(bucket);
+            }
+            let mut prev = 0.0;
+            for bucket in 0..buckets {
+                let cdf = h.cdf(bucket).unwrap();
+                assert!(prev <= cdf);
+                if h.count(bucket) > 0 {
+                    assert_eq!(h.inverse_cdf(cdf), Some(bucket));
+                }
+                prev = cdf;
+            }
+            assert_eq!(prev, 1.0);
+            for _ in 0..100 {
+                let p = rng.gen_range(0.0..=1.0);
+                let i = h.inverse_cdf(p).unwrap();
+                assert!(h.cdf(i).unwrap() >= p);
+                assert!(h.count(i) > 0);
+                assert!(i == 0 || h.cdf(i - 1).unwrap() < p || p == 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn remove() {
+        let mut h = Histogram::new(8);
+        h.record_n(2, 3);
+        h.record_n(5, 1);
+        h.remove(2);
+        h.remove_n(5, 1);
+        assert_eq!(h.total(), 2);
+        assert_eq!(h.count(2), 2);
+        assert_eq!(h.count(5), 0);
+        assert_eq!(h.count_below(8), 2);
+        assert_eq!(h.percentile(100.0), Some(2));
+    }
+
+    #[test]
+    fn watcher_randoms() {
+        let mut rng = thread_rng();
+        for buckets in 1..32 {
+            let mut h = Histogram::new(buckets);
+            let mut watcher = ThresholdWatcher::new();
+            let thresholds = (0..8)
+                .map(|_| (rng.gen_range(0.0..=100.0), rng.gen_range(0..buckets)))
+                .collect_vec();
+            for &(p, bucket) in &thresholds {
+                watcher.watch(p, bucket);
+            }
+            let mut above = std::vec![false; thresholds.len()];
+            for _ in 0..32 {
+                for _ in 0..rng.gen_range(0..8) {
+                    let bucket = rng.gen_range(0..buckets);
+                    if rng.gen_bool(0.3) && h.count(bucket) > 0 {
+                        h.remove(bucket);
+                    } else {
+                        h.record(bucket);
+                    }
+                }
+                let mut expected = std::vec::Vec::new();
+                for (id, &(p, bucket)) in thresholds.iter().enumerate() {
+                    let now = h.percentile(p).is_some_and(|b| b > bucket);
+                    if now != above[id] {
+                        expected.push(Crossing { id, above: now });
+                        above[id] = now;
+                    }
+                }
+                assert_eq!(watcher.check(&h), expected);
+                for (id, &a) in above.iter().enumerate() {
+                    assert_eq!(watcher.is_above(id), a);
+                }
+            }
+        }
+    }
+}
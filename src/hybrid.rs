@@ -0,0 +1,204 @@
+//! 2D array that is a Fenwick tree along one axis and dense along the other.
+//!
+//! When only one axis needs prefix sums with fast updates (e.g. time), and the other is small and
+//! fixed (e.g. a handful of categories), each node of a 1D Fenwick tree along the first axis can
+//! hold a dense row over the second. Compared to a full 2D Fenwick tree ([`crate::nd`]), this
+//! drops one log factor: updates take `O(log(R))` for `R` rows, and a prefix sum over `W` columns
+//! takes `O(W log(R))` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::hybrid::HybridFenwick2D;
+//!
+//! // 1000 time steps by 4 categories
+//! let mut fw = HybridFenwick2D::new(1000, 4);
+//! fw.update(10, 2, 5);
+//! fw.update(20, 2, 1);
+//! fw.update(20, 3, 7);
+//! assert_eq!(fw.prefix_sum(15, 2), 5);
+//! assert_eq!(fw.prefix_sum(999, 2), 6);
+//! assert_eq!(fw.prefix_sum_cols(999, 2..4), 13);
+//! assert_eq!(fw.prefix_sum_cols(999, ..2), 0);
+//! ```
+//!
+//! Rows and columns may be indexed by distinct [`IndexLike`] types (see
+//! [`HybridFenwick2D::new_indexed`]), so that passing them in the wrong order does not compile.
+//!
+
+use core::marker::PhantomData;
+use core::ops::{AddAssign, RangeBounds};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::index::zero_based::{down, up};
+use crate::index::{to_range, IndexLike};
+
+/// 2D array that is a Fenwick tree along rows and dense along columns, indexed by `R` and `C` .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HybridFenwick2D<T, R = usize, C = usize> {
+    rows: usize,
+    cols: usize,
+    /// Node `i` of the Fenwick tree along rows is `nodes[i * cols..(i + 1) * cols]` .
+    nodes: Vec<T>,
+    index: PhantomData<fn(R, C)>,
+}
+
+impl<T> HybridFenwick2D<T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates an array of `rows` by `cols` zeros, indexed by `usize` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total number of elements overflows `usize` .
+    ///
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self::new_indexed(rows, cols)
+    }
+}
+
+impl<T, R, C> HybridFenwick2D<T, R, C>
+where
+    T: AddAssign + Clone + Default,
+    R: IndexLike,
+    C: IndexLike
+{
+    /// Creates an array of `rows` by `cols` zeros, with rows indexed by `R` and columns indexed by
+    /// `C` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total number of elements overflows `usize` .
+    ///
+    pub fn new_indexed(rows: usize, cols: usize) -> Self {
+        let len = rows.checked_mul(cols).expect("shape too large");
+        Self {
+            rows,
+            cols,
+            nodes: vec![T::default(); len],
+            index: PhantomData,
+        }
+    }
+
+    /// Returns the number of rows (the Fenwick tree axis).
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns (the dense axis).
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Conceptually performs `a[row][col] += delta` on the original array `a` . Takes
+    /// `O(log(R))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` or `col` is out of bound.
+    ///
+    pub fn update(&mut self, row: R, col: C, delta: T) {
+        let (row, col) = (row.index(), col.index());
+        assert!(col < self.cols);
+        for ii in up(row, self.rows) {
+            self.nodes[ii * self.cols + col] += delta.clone();
+        }
+    }
+
+    /// Conceptually calculates `a[0][col] + ... + a[row][col]` on the original array `a` . Takes
+    /// `O(log(R))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` or `col` is out of bound.
+    ///
+    pub fn prefix_sum(&self, row: R, col: C) -> T {
+        self.prefix_sum_cols(row, col..C::from_index(col.index() + 1))
+    }
+
+    /// Conceptually calculates the sum of `a[i][j]` over all `i <= row` and `j` in `cols` on the
+    /// original array `a` . Takes `O(W log(R))` for `W` columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` or `cols` is out of bound.
+    ///
+    pub fn prefix_sum_cols(&self, row: R, cols: impl RangeBounds<C>) -> T {
+        let row = row.index();
+        assert!(row < self.rows);
+        let bounds = (
+            cols.start_bound().map(|&c| c.index()),
+            cols.end_bound().map(|&c| c.index()),
+        );
+        let cols = to_range(&bounds, self.cols);
+        let mut sum = T::default();
+        for ii in down(row) {
+            let node = &self.nodes[ii * self.cols..(ii + 1) * self.cols];
+            for x in &node[cols.clone()] {
+                sum += x.clone();
+            }
+        }
+        sum
+    }
+
+    /// Conceptually calculates `a[0][j] + ... + a[row][j]` for every column `j` on the original
+    /// array `a` , adding them to `out[j]` . Takes `O(C log(R))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is out of bound or `out.len() != self.cols()` .
+    ///
+    pub fn prefix_sum_row(&self, row: R, out: &mut [T]) {
+        let row = row.index();
+        assert!(row < self.rows);
+        assert_eq!(out.len(), self.cols);
+        for ii in down(row) {
+            let node = &self.nodes[ii * self.cols..(ii + 1) * self.cols];
+            for (o, x) in out.iter_mut().zip(node) {
+                *o += x.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for (rows, cols) in [(1, 1), (17, 3), (64, 16), (5, 1)] {
+            let mut fw = HybridFenwick2D::new(rows, cols);
+            let mut naive = std::vec![std::vec![0i64; cols]; rows];
+            for _ in 0..64 {
+                let (i, j) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+                let x = rng.gen_range(-100..=100);
+                fw.update(i, j, x);
+                naive[i][j] += x;
+            }
+            let mut expected = std::vec![0i64; cols];
+            for (i, row) in naive.iter().enumerate() {
+                for (e, x) in expected.iter_mut().zip(row) {
+                    *e += x;
+                }
+                let mut out = std::vec![0i64; cols];
+                fw.prefix_sum_row(i, &mut out);
+                assert_eq!(out, expected);
+                for j in 0..cols {
+                    assert_eq!(fw.prefix_sum(i, j), expected[j]);
+                    assert_eq!(fw.prefix_sum_cols(i, j..cols), expected[j..].iter().sum());
+                }
+            }
+        }
+    }
+}
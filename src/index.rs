@@ -10,6 +10,16 @@
 //!
 //! This module implements both [zero-based](zero_based) and [one-based](one_based) index sequences.
 //!
+//! The iterators are plain index arithmetic and never allocate, so nested walks over
+//! multidimensional trees (as below) are allocation-free as well. This holds by construction: this
+//! module (like [`array`](crate::array)) does not depend on `alloc` .
+//!
+//! The underlying index math (`next_down` , `next_up` , `node_range` , ...) is exposed as
+//! `const fn` for building lookup tables in `const` items.
+//!
+//! Some owned trees (e.g. `hybrid::HybridFenwick2D`) take any [`IndexLike`] index type instead of
+//! `usize` , e.g. newtypes that keep the axes of a 2D tree apart.
+//!
 //! # Examples
 //!
 //! An ad-hoc 3D Fenwick tree over a 3D array may be implemented as follows:
@@ -46,7 +56,11 @@
 //! ```
 //!
 
+use core::ops::{Bound, Range, RangeBounds};
+
 pub mod one_based {
+    use core::ops::Range;
+
     /// Creates an iterator that yields indices of nodes that make up the prefix sum up to `init`
     /// in a one-based Fenwick tree.
     ///
@@ -72,8 +86,9 @@ pub mod one_based {
         })
     }
 
+    /// Returns the index following `i` in [`down`] , or zero if there is none.
     #[inline]
-    fn next_down(i: usize) -> usize {
+    pub const fn next_down(i: usize) -> usize {
         i & i.wrapping_sub(1)
     }
 
@@ -88,7 +103,8 @@ pub mod one_based {
     /// `1 <= init && init <= limit_inclusive && limit_inclusive <= (usize::max_value() >> 1)` .
     ///
     /// Note that the upper bound on `limit_inclusive` is irrelevant in practice since it is the
-    /// length of the backing array of the Fenwick tree and therefore limited by memory.
+    /// length of the backing array of the Fenwick tree and therefore limited by memory. Use
+    /// [`up_checked`] if indices may come from the full range of `usize` .
     ///
     /// # Examples
     ///
@@ -108,13 +124,89 @@ pub mod one_based {
         })
     }
 
+    /// Returns the index following `i` in [`up`] (regardless of the limit).
+    ///
+    /// # Panics
+    ///
+    /// Panics on overflow (in debug builds) if `i > (usize::max_value() >> 1)` .
+    ///
     #[inline]
-    fn next_up(i: usize) -> usize {
+    pub const fn next_up(i: usize) -> usize {
         (i | i.wrapping_sub(1)) + 1
     }
+
+    /// Returns the lowest set bit of `i` , i.e. the number of elements covered by node `i` in a
+    /// one-based Fenwick tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::index::one_based::lowbit;
+    ///
+    /// const LOWBIT: usize = lowbit(12);
+    /// assert_eq!(LOWBIT, 4);
+    /// assert_eq!(lowbit(7), 1);
+    /// assert_eq!(lowbit(0), 0);
+    /// ```
+    ///
+    #[inline]
+    pub const fn lowbit(i: usize) -> usize {
+        i & i.wrapping_neg()
+    }
+
+    /// Returns the range of elements of the original array covered by node `i` in a one-based
+    /// Fenwick tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics on overflow (in debug builds) if `i == usize::max_value()` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::index::one_based::node_range;
+    ///
+    /// const RANGE: core::ops::Range<usize> = node_range(12);
+    /// assert_eq!(RANGE, 9..13);
+    /// assert_eq!(node_range(7), 7..8);
+    /// ```
+    ///
+    #[inline]
+    pub const fn node_range(i: usize) -> Range<usize> {
+        i - lowbit(i) + 1..i + 1
+    }
+
+    /// Same as [`up`] , but accepts the full range of `usize` and never panics.
+    ///
+    /// The next index is calculated with checked arithmetic, and the iterator terminates when it
+    /// would overflow instead of requiring `limit_inclusive <= (usize::max_value() >> 1)` . Invalid
+    /// inputs (`init == 0` or `init > limit_inclusive`) yield an empty iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::index::one_based::up_checked;
+    ///
+    /// let top = usize::MAX - 2; // 0b111...1101
+    /// let seq: Vec<_> = up_checked(top, usize::MAX).collect();
+    /// assert_eq!(seq, [top, usize::MAX - 1]);
+    /// assert_eq!(up_checked(0, 10).count(), 0);
+    /// assert_eq!(up_checked(11, 10).count(), 0);
+    /// ```
+    ///
+    pub fn up_checked(init: usize, limit_inclusive: usize) -> impl Iterator<Item = usize> {
+        let first = if 1 <= init && init <= limit_inclusive { Some(init) } else { None };
+        core::iter::successors(first, move |&i| {
+            (i | (i - 1))
+                .checked_add(1)
+                .filter(|&next| next <= limit_inclusive)
+        })
+    }
 }
 
 pub mod zero_based {
+    use core::ops::Range;
+
     /// Creates an iterator that yields indices of nodes that make up the prefix sum up to `init`
     /// in a zero-based Fenwick tree.
     ///
@@ -139,8 +231,9 @@ pub mod zero_based {
         })
     }
 
+    /// Returns the index following `i` in [`down`] , or `usize::max_value()` if there is none.
     #[inline]
-    fn next_down(i: usize) -> usize {
+    pub const fn next_down(i: usize) -> usize {
         (i & i.wrapping_add(1)).wrapping_sub(1)
     }
 
@@ -166,10 +259,257 @@ pub mod zero_based {
         })
     }
 
+    /// Returns the index following `i` in [`up`] (regardless of the limit).
     #[inline]
-    fn next_up(i: usize) -> usize {
+    pub const fn next_up(i: usize) -> usize {
         i | i.wrapping_add(1)
     }
+
+    /// Returns the range of elements of the original array covered by node `i` in a zero-based
+    /// Fenwick tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics on overflow (in debug builds) if `i == usize::max_value()` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::index::zero_based::node_range;
+    ///
+    /// const RANGE: core::ops::Range<usize> = node_range(11);
+    /// assert_eq!(RANGE, 8..12);
+    /// assert_eq!(node_range(6), 6..7);
+    /// ```
+    ///
+    #[inline]
+    pub const fn node_range(i: usize) -> Range<usize> {
+        (i & i.wrapping_add(1))..i + 1
+    }
+
+    /// Returns the maximum number of indices yielded by [`down`] or [`up`] in a zero-based
+    /// Fenwick tree with `len` elements, i.e. the depth `D` required by [`WalkTable`] .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::index::zero_based::depth;
+    ///
+    /// assert_eq!(depth(0), 0);
+    /// assert_eq!(depth(1), 1);
+    /// assert_eq!(depth(31), 5);
+    /// assert_eq!(depth(32), 6);
+    /// ```
+    ///
+    pub const fn depth(len: usize) -> usize {
+        (usize::BITS - len.leading_zeros()) as usize
+    }
+
+    /// Index sequences of [`down`] and [`up`] for every element of a zero-based Fenwick tree with
+    /// `N` elements, precomputed in a `const` context.
+    ///
+    /// For small fixed-size trees, storing the table in a `const` item turns each traversal into a
+    /// loop over a constant slice, which the compiler can fully unroll. `D` must be at least
+    /// [`depth(N)`](depth) ; the table takes `O(N * D)` space.
+    ///
+    /// # Panics
+    ///
+    /// [`WalkTable::new`] panics (at compile time when evaluated in a `const` context) if `D` is
+    /// less than `depth(N)` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::index::zero_based::{depth, WalkTable};
+    ///
+    /// const LEN: usize = 32;
+    /// const WALK: WalkTable<LEN, { depth(LEN) }> = WalkTable::new();
+    ///
+    /// fn update(fw: &mut [i32; LEN], i: usize, delta: i32) {
+    ///     for &ii in WALK.up(i) {
+    ///         fw[ii] += delta;
+    ///     }
+    /// }
+    ///
+    /// fn prefix_sum(fw: &[i32; LEN], i: usize) -> i32 {
+    ///     let mut sum = 0;
+    ///     for &ii in WALK.down(i) {
+    ///         sum += fw[ii];
+    ///     }
+    ///     sum
+    /// }
+    ///
+    /// let mut fw = [0; LEN];
+    /// update(&mut fw, 3, 5);
+    /// update(&mut fw, 20, 7);
+    /// assert_eq!(prefix_sum(&fw, 2), 0);
+    /// assert_eq!(prefix_sum(&fw, 19), 5);
+    /// assert_eq!(prefix_sum(&fw, 31), 12);
+    /// ```
+    ///
+    #[derive(Clone, Copy, Debug)]
+    pub struct WalkTable<const N: usize, const D: usize> {
+        down: [[usize; D]; N],
+        down_len: [usize; N],
+        up: [[usize; D]; N],
+        up_len: [usize; N],
+    }
+
+    impl<const N: usize, const D: usize> WalkTable<N, D> {
+        /// Precomputes the index sequences. Takes `O(N * D)` .
+        pub const fn new() -> Self {
+            assert!(D >= depth(N));
+            let mut table = Self {
+                down: [[0; D]; N],
+                down_len: [0; N],
+                up: [[0; D]; N],
+                up_len: [0; N],
+            };
+            let mut init = 0;
+            while init < N {
+                let mut i = init;
+                let mut len = 0;
+                while i != !0 {
+                    table.down[init][len] = i;
+                    len += 1;
+                    i = next_down(i);
+                }
+                table.down_len[init] = len;
+
+                let mut i = init;
+                let mut len = 0;
+                while i < N {
+                    table.up[init][len] = i;
+                    len += 1;
+                    i = next_up(i);
+                }
+                table.up_len[init] = len;
+
+                init += 1;
+            }
+            table
+        }
+
+        /// Returns the indices yielded by [`down(init)`](down) .
+        ///
+        /// # Panics
+        ///
+        /// Panics if `init >= N` .
+        ///
+        #[inline]
+        pub fn down(&self, init: usize) -> &[usize] {
+            &self.down[init][..self.down_len[init]]
+        }
+
+        /// Returns the indices yielded by [`up(init, N)`](up) .
+        ///
+        /// # Panics
+        ///
+        /// Panics if `init >= N` .
+        ///
+        #[inline]
+        pub fn up(&self, init: usize) -> &[usize] {
+            &self.up[init][..self.up_len[init]]
+        }
+    }
+
+    impl<const N: usize, const D: usize> Default for WalkTable<N, D> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+/// Index type that converts to and from a zero-based `usize` index, so that owned trees can take
+/// newtype indices (e.g. `struct Row(usize)`) and the compiler catches swapped axes.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use fenwick::hybrid::HybridFenwick2D;
+/// use fenwick::index::IndexLike;
+///
+/// #[derive(Clone, Copy)]
+/// struct Row(usize);
+/// #[derive(Clone, Copy)]
+/// struct Col(usize);
+///
+/// impl IndexLike for Row {
+///     fn index(self) -> usize { self.0 }
+///     fn from_index(i: usize) -> Self { Row(i) }
+/// }
+/// impl IndexLike for Col {
+///     fn index(self) -> usize { self.0 }
+///     fn from_index(i: usize) -> Self { Col(i) }
+/// }
+///
+/// let mut fw = HybridFenwick2D::<i32, Row, Col>::new_indexed(10, 3);
+/// fw.update(Row(4), Col(1), 5);
+/// assert_eq!(fw.prefix_sum(Row(9), Col(1)), 5);
+/// # }
+/// ```
+///
+/// Swapped axes do not compile:
+///
+/// ```compile_fail
+/// # use fenwick::hybrid::HybridFenwick2D;
+/// # use fenwick::index::IndexLike;
+/// # #[derive(Clone, Copy)]
+/// # struct Row(usize);
+/// # #[derive(Clone, Copy)]
+/// # struct Col(usize);
+/// # impl IndexLike for Row {
+/// #     fn index(self) -> usize { self.0 }
+/// #     fn from_index(i: usize) -> Self { Row(i) }
+/// # }
+/// # impl IndexLike for Col {
+/// #     fn index(self) -> usize { self.0 }
+/// #     fn from_index(i: usize) -> Self { Col(i) }
+/// # }
+/// let mut fw = HybridFenwick2D::<i32, Row, Col>::new_indexed(10, 3);
+/// fw.update(Col(1), Row(4), 5);
+/// ```
+///
+pub trait IndexLike: Copy {
+    /// Returns the zero-based index.
+    fn index(self) -> usize;
+    /// Creates an index from a zero-based index.
+    fn from_index(i: usize) -> Self;
+}
+
+impl IndexLike for usize {
+    fn index(self) -> usize {
+        self
+    }
+
+    fn from_index(i: usize) -> Self {
+        i
+    }
+}
+
+/// Converts `range` to a half-open range within `0..len` , with the same semantics as slice
+/// indexing: e.g. `..` is `0..len` and `..=i` is `0..i + 1` . Every API of this crate that takes
+/// a range of indices normalizes it with this.
+///
+/// # Panics
+///
+/// Panics if the start is after the end, or the end is after `len` .
+///
+pub(crate) fn to_range<R: RangeBounds<usize> + ?Sized>(range: &R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1).expect("range start overflows usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1).expect("range end overflows usize"),
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range start {start} is after end {end}");
+    assert!(end <= len, "range end {end} is out of bound {len}");
+    start..end
 }
 
 #[cfg(test)]
@@ -178,6 +518,22 @@ mod tests {
     extern crate std;
     use itertools::Itertools;
 
+    #[test]
+    fn to_range_bounds() {
+        assert_eq!(to_range(&.., 8), 0..8);
+        assert_eq!(to_range(&(2..), 8), 2..8);
+        assert_eq!(to_range(&(..3), 8), 0..3);
+        assert_eq!(to_range(&(..=3), 8), 0..4);
+        assert_eq!(to_range(&(2..=7), 8), 2..8);
+        assert_eq!(to_range(&(8..8), 8), 8..8);
+        assert_eq!(to_range(&(Bound::Included(3), Bound::Included(2)), 8), 3..3);
+        assert_eq!(to_range(&(Bound::Excluded(2), Bound::Unbounded), 8), 3..8);
+        let reversed = (Bound::Included(3), Bound::Excluded(2));
+        assert!(std::panic::catch_unwind(|| to_range(&reversed, 8)).is_err());
+        assert!(std::panic::catch_unwind(|| to_range(&(..=8), 8)).is_err());
+        assert!(std::panic::catch_unwind(|| to_range(&(..=usize::MAX), 8)).is_err());
+    }
+
     #[test]
     fn down_example() {
         let init_one =
@@ -234,4 +590,82 @@ mod tests {
             ans_one
         );
     }
+
+    #[test]
+    fn up_checked_near_top() {
+        use rand::prelude::*;
+
+        // reference: repeatedly add the lowest set bit, stopping on overflow
+        fn naive(init: usize, limit: usize) -> std::vec::Vec<usize> {
+            let mut seq = std::vec![];
+            let mut i = Some(init);
+            while let Some(x) = i.filter(|&x| x <= limit) {
+                seq.push(x);
+                i = x.checked_add(x & x.wrapping_neg());
+            }
+            seq
+        }
+
+        let mut rng = thread_rng();
+        for _ in 0..10000 {
+            let limit = usize::MAX - rng.gen_range(0..=1024);
+            let init = limit - rng.gen_range(0..=1024);
+            let init = init.max(1);
+            assert_eq!(one_based::up_checked(init, limit).collect_vec(), naive(init, limit));
+        }
+        for _ in 0..10000 {
+            let limit = rng.gen_range(1..=(usize::MAX >> 1));
+            let init = rng.gen_range(1..=limit);
+            assert_eq!(
+                one_based::up_checked(init, limit).collect_vec(),
+                one_based::up(init, limit).collect_vec()
+            );
+        }
+        assert_eq!(one_based::up_checked(usize::MAX, usize::MAX).collect_vec(), [usize::MAX]);
+        assert_eq!(one_based::up_checked(1 << (usize::BITS - 1), usize::MAX).count(), 1);
+    }
+
+    #[test]
+    fn node_ranges() {
+        // nodes yielded by `down(i)` partition the prefix up to `i`
+        for i in 1..200usize {
+            let mut end = i + 1;
+            for ii in one_based::down(i) {
+                let range = one_based::node_range(ii);
+                assert_eq!(range.end, end);
+                assert_eq!(range.len(), one_based::lowbit(ii));
+                end = range.start;
+            }
+            assert_eq!(end, 1);
+        }
+        for i in 0..200usize {
+            let mut end = i + 1;
+            for ii in zero_based::down(i) {
+                let range = zero_based::node_range(ii);
+                assert_eq!(range.end, end);
+                end = range.start;
+            }
+            assert_eq!(end, 0);
+        }
+    }
+
+    #[test]
+    fn walk_table() {
+        fn check<const N: usize, const D: usize>(table: &zero_based::WalkTable<N, D>) {
+            for i in 0..N {
+                assert_eq!(table.down(i), zero_based::down(i).collect_vec());
+                assert_eq!(table.up(i), zero_based::up(i, N).collect_vec());
+            }
+        }
+        use zero_based::{depth, WalkTable};
+        const T1: WalkTable<1, { depth(1) }> = WalkTable::new();
+        const T32: WalkTable<32, { depth(32) }> = WalkTable::new();
+        const T45: WalkTable<45, { depth(45) }> = WalkTable::new();
+        const T64: WalkTable<64, { depth(64) }> = WalkTable::new();
+        check(&T1);
+        check(&T32);
+        check(&T45);
+        check(&T64);
+        check(&WalkTable::<10, 8>::new());
+    }
 }
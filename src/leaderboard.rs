@@ -0,0 +1,214 @@
+//! Leaderboard tracking the rank of each player by score.
+//!
+//! Scores are mapped to a fixed number of buckets by a [`ScoreToBucket`] implementation, and the
+//! number of players in each bucket is kept in a [`Histogram`] . Changing a player's score moves
+//! them from the old bucket to the new one, and ranks are counts of players in higher buckets, so
+//! both take `O(log(N) + log(P))` for `N` buckets and `P` players.
+//!
+//! Players whose scores fall in the same bucket are tied.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::leaderboard::{Leaderboard, UnitBuckets};
+//!
+//! // integer scores 0..=1000, one bucket each
+//! let mut board = Leaderboard::new(UnitBuckets(1001));
+//! board.set_score("alice", 300);
+//! board.set_score("bob", 700);
+//! board.set_score("carol", 300);
+//! assert_eq!(board.rank_of(&"bob"), Some(1));
+//! assert_eq!(board.rank_of(&"alice"), Some(2));
+//! assert_eq!(board.rank_of(&"carol"), Some(2)); // tied with alice
+//! assert_eq!(board.players_in_score_range(0..500), 2);
+//!
+//! board.set_score("alice", 800);
+//! assert_eq!(board.rank_of(&"alice"), Some(1));
+//! assert_eq!(board.rank_of(&"bob"), Some(2));
+//! assert_eq!(board.rank_of(&"carol"), Some(3));
+//! assert_eq!(board.players_in_score_range(0..500), 1);
+//! assert_eq!(board.players_in_score_range(700..=1000), 2);
+//! assert_eq!(board.rank_of(&"dave"), None);
+//! ```
+//!
+
+use core::ops::{Bound, RangeBounds};
+
+use alloc::collections::BTreeMap;
+
+use crate::histogram::Histogram;
+
+/// Mapping from scores to a fixed number of buckets.
+///
+/// The mapping must be non-decreasing: a higher score never maps to a lower bucket.
+pub trait ScoreToBucket {
+    type Score;
+
+    /// Returns the number of buckets.
+    fn buckets(&self) -> usize;
+
+    /// Returns the bucket of `score` , which must be less than `self.buckets()` .
+    fn bucket(&self, score: &Self::Score) -> usize;
+}
+
+/// Maps integer scores `0..self.0` to one bucket each.
+///
+/// Panics when mapping a score that is out of bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnitBuckets(pub usize);
+
+impl ScoreToBucket for UnitBuckets {
+    type Score = usize;
+
+    fn buckets(&self) -> usize {
+        self.0
+    }
+
+    fn bucket(&self, score: &usize) -> usize {
+        assert!(*score < self.0);
+        *score
+    }
+}
+
+/// Leaderboard tracking the rank of each player by score.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug)]
+pub struct Leaderboard<S: ScoreToBucket, P = u64> {
+    mapping: S,
+    histogram: Histogram,
+    scores: BTreeMap<P, S::Score>,
+}
+
+impl<S: ScoreToBucket, P: Ord> Leaderboard<S, P> {
+    /// Creates an empty leaderboard with the given score mapping.
+    pub fn new(mapping: S) -> Self {
+        Self {
+            histogram: Histogram::new(mapping.buckets()),
+            mapping,
+            scores: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of players.
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Returns `true` if there are no players.
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Returns the score mapping.
+    pub fn mapping(&self) -> &S {
+        &self.mapping
+    }
+
+    /// Returns the score of `player` , or `None` if not on the leaderboard.
+    pub fn score_of(&self, player: &P) -> Option<&S::Score> {
+        self.scores.get(player)
+    }
+
+    /// Sets the score of `player` , adding them to the leaderboard if needed. Returns the previous
+    /// score, if any.
+    pub fn set_score(&mut self, player: P, score: S::Score) -> Option<S::Score> {
+        self.histogram.record(self.mapping.bucket(&score));
+        let old = self.scores.insert(player, score);
+        if let Some(old) = &old {
+            self.histogram.remove(self.mapping.bucket(old));
+        }
+        old
+    }
+
+    /// Removes `player` from the leaderboard and returns their score, if any.
+    pub fn remove_player(&mut self, player: &P) -> Option<S::Score> {
+        let old = self.scores.remove(player)?;
+        self.histogram.remove(self.mapping.bucket(&old));
+        Some(old)
+    }
+
+    /// Returns the one-based rank of `player` , or `None` if not on the leaderboard.
+    ///
+    /// The rank is one plus the number of players in strictly higher buckets, so tied players
+    /// share the same rank.
+    pub fn rank_of(&self, player: &P) -> Option<u64> {
+        let bucket = self.mapping.bucket(self.scores.get(player)?);
+        Some(self.histogram.total() - self.histogram.count_below(bucket + 1) + 1)
+    }
+
+    /// Returns the number of players whose score falls in `range` , at bucket granularity: players
+    /// are counted if their bucket is within the buckets of the bounds of `range` , e.g. `a..b`
+    /// counts buckets from the bucket of `a` up to, but excluding, the bucket of `b` , and `a..`
+    /// counts every bucket from the bucket of `a` up to the top one.
+    pub fn players_in_score_range(&self, range: impl RangeBounds<S::Score>) -> u64 {
+        let lo = match range.start_bound() {
+            Bound::Included(score) => self.mapping.bucket(score),
+            Bound::Excluded(score) => self.mapping.bucket(score) + 1,
+            Bound::Unbounded => 0,
+        };
+        let hi = match range.end_bound() {
+            Bound::Included(score) => self.mapping.bucket(score) + 1,
+            Bound::Excluded(score) => self.mapping.bucket(score),
+            Bound::Unbounded => self.mapping.buckets(),
+        };
+        if lo >= hi {
+            0
+        } else {
+            self.histogram.count_below(hi) - self.histogram.count_below(lo)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        let mut board = Leaderboard::<_, u32>::new(UnitBuckets(100));
+        let mut naive = std::collections::BTreeMap::new();
+        for _ in 0..1000 {
+            let player = rng.gen_range(0..50);
+            if rng.gen_ratio(1, 5) {
+                assert_eq!(board.remove_player(&player), naive.remove(&player));
+            } else {
+                let score = rng.gen_range(0..100);
+                assert_eq!(board.set_score(player, score), naive.insert(player, score));
+            }
+            assert_eq!(board.len(), naive.len());
+            for player in 0..50 {
+                let expected = naive.get(&player)
+                    .map(|s| naive.values().filter(|&t| t > s).count() as u64 + 1);
+                assert_eq!(board.rank_of(&player), expected);
+            }
+            let lo = rng.gen_range(0..100);
+            let hi = rng.gen_range(0..100);
+            let expected = naive.values().filter(|&s| (lo..hi).contains(s)).count() as u64;
+            assert_eq!(board.players_in_score_range(lo..hi), expected);
+            let expected = naive.values().filter(|&s| (lo..=hi).contains(s)).count() as u64;
+            assert_eq!(board.players_in_score_range(lo..=hi), expected);
+            let expected = naive.values().filter(|&&s| s > lo).count() as u64;
+            let above = (Bound::Excluded(lo), Bound::Unbounded);
+            assert_eq!(board.players_in_score_range(above), expected);
+        }
+    }
+
+    #[test]
+    fn top_bucket() {
+        let mut board = Leaderboard::<_, u32>::new(UnitBuckets(10));
+        board.set_score(1, 9);
+        board.set_score(2, 0);
+        board.set_score(3, 5);
+        assert_eq!(board.players_in_score_range(5..=9), 2);
+        assert_eq!(board.players_in_score_range(9..), 1);
+        assert_eq!(board.players_in_score_range(..), 3);
+        assert_eq!(board.players_in_score_range(..=9), 3);
+        assert_eq!(board.players_in_score_range(0..9), 2);
+    }
+}
@@ -1,5 +1,95 @@
 #![doc = include_str!("../README.md")]
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+/// Enters a `tracing` span (at debug level) until the end of the enclosing block, if the `tracing`
+/// feature is enabled. Takes the same arguments as `tracing::debug_span!` .
+macro_rules! span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($args)*).entered();
+    };
+}
+
+pub mod advisor;
 pub mod array;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "alloc")]
+pub mod assign;
+#[cfg(feature = "alloc")]
+pub mod bitmap;
+#[cfg(feature = "alloc")]
+pub mod bitset;
+#[cfg(feature = "alloc")]
+pub mod booking;
+#[cfg(feature = "alloc")]
+pub mod bucket;
+#[cfg(feature = "bytemuck")]
+pub mod bytes;
+#[cfg(feature = "alloc")]
+pub mod compress;
+pub mod conformance;
+#[cfg(feature = "alloc")]
+pub mod cow;
+#[cfg(feature = "alloc")]
+pub mod crdt;
+#[cfg(feature = "alloc")]
+pub mod deque;
+pub mod detfloat;
+pub mod dump;
+#[cfg(feature = "alloc")]
+pub mod energy;
+pub mod fixed;
+pub mod float;
+#[cfg(feature = "alloc")]
+pub mod grid;
+#[cfg(feature = "alloc")]
+pub mod group;
+#[cfg(feature = "alloc")]
+pub mod histogram;
+#[cfg(feature = "alloc")]
+pub mod hybrid;
 pub mod index;
+#[cfg(feature = "alloc")]
+pub mod leaderboard;
+#[cfg(feature = "alloc")]
+pub mod nd;
+#[cfg(feature = "alloc")]
+pub mod observe;
+#[cfg(feature = "alloc")]
+pub mod offline;
+pub mod oplog;
+#[cfg(feature = "alloc")]
+pub mod partition;
+#[cfg(feature = "alloc")]
+pub mod plan;
+#[cfg(feature = "alloc")]
+pub mod privacy;
+#[cfg(all(feature = "alloc", feature = "rand"))]
+pub mod resample;
+#[cfg(feature = "alloc")]
+pub mod sampler;
+pub mod selftest;
+#[cfg(feature = "alloc")]
+pub mod sharded;
+#[cfg(target_has_atomic = "64")]
+pub mod shared;
+#[cfg(feature = "alloc")]
+pub mod smart;
+#[cfg(feature = "alloc")]
+pub mod sparse;
+#[cfg(feature = "alloc")]
+pub mod stabbing;
+#[cfg(feature = "alloc")]
+pub mod stats;
+pub mod suffix;
+#[cfg(feature = "alloc")]
+pub mod text;
+pub mod version;
+#[cfg(feature = "alloc")]
+pub mod wavelet;
+#[cfg(feature = "alloc")]
+pub mod window;
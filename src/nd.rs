@@ -0,0 +1,304 @@
+//! Multidimensional Fenwick tree with the number of dimensions fixed at compile time.
+//!
+//! Coordinates are arrays `[usize; D]` , so passing the wrong number of coordinates is a type
+//! error. The shape (length along each axis) is chosen at construction. Both operations take
+//! `O(log(N_0) * ... * log(N_{D-1}))` and never allocate.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::nd::NdFenwick;
+//!
+//! let mut fw = NdFenwick::<i32, 3>::new([4, 5, 6]);
+//! fw.update([1, 2, 3], 5);
+//! fw.update([3, 0, 0], 2);
+//! assert_eq!(fw.prefix_sum([0, 4, 5]), 0);
+//! assert_eq!(fw.prefix_sum([1, 2, 3]), 5);
+//! assert_eq!(fw.prefix_sum([3, 4, 5]), 7);
+//! assert_eq!(fw.prefix_sum([3, 1, 2]), 2);
+//! ```
+//!
+//! Mismatched arity does not compile:
+//!
+//! ```compile_fail
+//! use fenwick::nd::NdFenwick;
+//!
+//! let mut fw = NdFenwick::<i32, 3>::new([4, 5, 6]);
+//! fw.update([1, 2], 5);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::index::zero_based::{down, up};
+
+/// Multidimensional Fenwick tree with `D` dimensions.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NdFenwick<T, const D: usize> {
+    shape: [usize; D],
+    /// Row-major strides: `strides[D - 1] == 1` .
+    strides: [usize; D],
+    fenwick: Vec<T>,
+}
+
+impl<T, const D: usize> NdFenwick<T, D>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a tree of zeros with `shape[d]` elements along axis `d` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total number of elements overflows `usize` .
+    ///
+    pub fn new(shape: [usize; D]) -> Self {
+        let mut strides = [1; D];
+        let mut len = 1usize;
+        for d in (0..D).rev() {
+            strides[d] = len;
+            len = len.checked_mul(shape[d]).expect("shape too large");
+        }
+        Self {
+            shape,
+            strides,
+            fenwick: vec![T::default(); len],
+        }
+    }
+
+    /// Returns the number of elements along each axis.
+    pub fn shape(&self) -> [usize; D] {
+        self.shape
+    }
+
+    /// Returns the backing array of the tree, in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.fenwick
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i[d] >= self.shape()[d]` for any axis `d` .
+    ///
+    pub fn update(&mut self, i: [usize; D], delta: T) {
+        self.update_axis(0, 0, &i, &delta);
+    }
+
+    fn update_axis(&mut self, d: usize, offset: usize, i: &[usize; D], delta: &T) {
+        if d == D {
+            self.fenwick[offset] += delta.clone();
+            return;
+        }
+        for ii in up(i[d], self.shape[d]) {
+            self.update_axis(d + 1, offset + ii * self.strides[d], i, delta);
+        }
+    }
+
+    /// Conceptually calculates the sum of `a[j]` over all `j` such that `j[d] <= i[d]` for every
+    /// axis `d` , on the original array `a` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i[d] >= self.shape()[d]` for any axis `d` .
+    ///
+    pub fn prefix_sum(&self, i: [usize; D]) -> T {
+        assert!(i.iter().zip(&self.shape).all(|(i, n)| i < n));
+        let mut sum = T::default();
+        self.prefix_sum_axis(0, 0, &i, &mut sum);
+        sum
+    }
+
+    fn prefix_sum_axis(&self, d: usize, offset: usize, i: &[usize; D], sum: &mut T) {
+        if d == D {
+            *sum += self.fenwick[offset].clone();
+            return;
+        }
+        for ii in down(i[d]) {
+            self.prefix_sum_axis(d + 1, offset + ii * self.strides[d], i, sum);
+        }
+    }
+
+    /// Converts the original array in place into the backing array, by converting each line along
+    /// each axis (the transform is separable). Takes `O(D * N)` for `N` elements in total.
+    #[cfg(feature = "ndarray")]
+    fn build(&mut self) {
+        for d in 0..D {
+            let (n, stride) = (self.shape[d], self.strides[d]);
+            for flat in 0..self.fenwick.len() {
+                let i = flat / stride % n;
+                let parent = i | (i + 1);
+                if parent < n {
+                    let x = self.fenwick[flat].clone();
+                    self.fenwick[flat + (parent - i) * stride] += x;
+                }
+            }
+        }
+    }
+
+    /// Calculates the prefix sums at every index at once, in row-major order. Takes `O(D * N)` for
+    /// `N` elements in total.
+    #[cfg(feature = "ndarray")]
+    fn prefix_sums(&self) -> Vec<T> {
+        let mut sums = self.fenwick.clone();
+        for d in 0..D {
+            let (n, stride) = (self.shape[d], self.strides[d]);
+            for flat in 0..sums.len() {
+                // prefix sum at `i` is node `i` plus prefix sum at `(i & (i + 1)) - 1`
+                let i = flat / stride % n;
+                let start = i & (i + 1);
+                if start > 0 {
+                    let x = sums[flat - (i - start + 1) * stride].clone();
+                    sums[flat] += x;
+                }
+            }
+        }
+        sums
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T> NdFenwick<T, 1>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a tree from the original array `a` . Takes `O(N)` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::nd::NdFenwick;
+    /// use ndarray::array;
+    ///
+    /// let fw = NdFenwick::from_array1(array![3, 1, 4, 1, 5].view());
+    /// assert_eq!(fw.prefix_sum([2]), 8);
+    /// assert_eq!(fw.prefix_sums_array1(), array![3, 4, 8, 9, 14]);
+    /// ```
+    ///
+    pub fn from_array1(a: ndarray::ArrayView1<T>) -> Self {
+        let mut fw = Self {
+            shape: [a.len()],
+            strides: [1],
+            fenwick: a.iter().cloned().collect(),
+        };
+        fw.build();
+        fw
+    }
+
+    /// Returns the prefix sums `a[0] + ... + a[i]` at every index `i` . Takes `O(N)` .
+    pub fn prefix_sums_array1(&self) -> ndarray::Array1<T> {
+        ndarray::Array1::from(self.prefix_sums())
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T> NdFenwick<T, 2>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a tree with the same shape as the original array `a` , in any memory layout. Takes
+    /// `O(N)` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::nd::NdFenwick;
+    /// use ndarray::array;
+    ///
+    /// let a = array![[1, 2, 3], [4, 5, 6]];
+    /// let fw = NdFenwick::from_array2(a.t()); // transposed view, 3 by 2
+    /// assert_eq!(fw.shape(), [3, 2]);
+    /// assert_eq!(fw.prefix_sum([1, 1]), 12);
+    /// assert_eq!(fw.prefix_sums_array2(), array![[1, 5], [3, 12], [6, 21]]);
+    /// ```
+    ///
+    pub fn from_array2(a: ndarray::ArrayView2<T>) -> Self {
+        let (rows, cols) = a.dim();
+        let mut fw = Self {
+            shape: [rows, cols],
+            strides: [cols, 1],
+            fenwick: a.iter().cloned().collect(),
+        };
+        fw.build();
+        fw
+    }
+
+    /// Returns the prefix sums at every index, with the same shape as the tree. Takes `O(N)` .
+    pub fn prefix_sums_array2(&self) -> ndarray::Array2<T> {
+        ndarray::Array2::from_shape_vec((self.shape[0], self.shape[1]), self.prefix_sums())
+            .expect("shape matches")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    fn random_one<const D: usize>(rng: &mut ThreadRng, shape: [usize; D]) {
+        let mut fw = NdFenwick::<i64, D>::new(shape);
+        let mut naive = std::vec![];
+        for _ in 0..64 {
+            let i = shape.map(|n| rng.gen_range(0..n));
+            let x = rng.gen_range(-100..=100);
+            fw.update(i, x);
+            naive.push((i, x));
+        }
+        for i in shape.iter().map(|&n| 0..n).multi_cartesian_product() {
+            let i: [usize; D] = i.try_into().unwrap();
+            let expected: i64 = naive.iter()
+                .filter(|(j, _)| (0..D).all(|d| j[d] <= i[d]))
+                .map(|(_, x)| x)
+                .sum();
+            assert_eq!(fw.prefix_sum(i), expected);
+        }
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        random_one(&mut rng, [17]);
+        random_one(&mut rng, [5, 9]);
+        random_one(&mut rng, [1, 8]);
+        random_one(&mut rng, [4, 3, 6]);
+        random_one(&mut rng, [2, 3, 2, 3]);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_randoms() {
+        let mut rng = thread_rng();
+        for (rows, cols) in [(1, 1), (1, 7), (6, 1), (5, 9), (8, 8)] {
+            let a = ndarray::Array2::from_shape_fn((rows, cols), |_| rng.gen_range(-100..=100i64));
+            for view in [a.view(), a.t()] {
+                let fw = NdFenwick::from_array2(view);
+                let mut expected = NdFenwick::new([view.nrows(), view.ncols()]);
+                for ((r, c), &x) in view.indexed_iter() {
+                    expected.update([r, c], x);
+                }
+                assert_eq!(fw, expected);
+                let sums = fw.prefix_sums_array2();
+                for ((r, c), &x) in sums.indexed_iter() {
+                    assert_eq!(fw.prefix_sum([r, c]), x);
+                }
+            }
+            let row = a.row(0);
+            let fw = NdFenwick::from_array1(row);
+            let mut sum = 0;
+            for (i, (&x, &s)) in row.iter().zip(&fw.prefix_sums_array1()).enumerate() {
+                sum += x;
+                assert_eq!(fw.prefix_sum([i]), sum);
+                assert_eq!(s, sum);
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Fenwick tree that notifies an observer of every update, e.g. to keep caches, mirrors or
+//! replication streams in sync.
+//!
+//! The observer is a type parameter of [`ObservedFenwick`] , so notifying it is a static call that
+//! can be inlined. The default observer `()` does nothing and compiles away entirely.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::prefix_sum;
+//! use fenwick::observe::{FnObserver, ObservedFenwick};
+//! use fenwick::oplog::{replay, Op};
+//!
+//! // replication stream: a `Vec<Op<T>>` observer collects every update
+//! let mut fw = ObservedFenwick::new(8).with_observer(Vec::new());
+//! fw.update(3, 5);
+//! fw.update(6, -2);
+//! let mut replica = [0; 8];
+//! replay(&mut replica, fw.observer());
+//! assert_eq!(replica, fw.as_slice());
+//!
+//! // ad-hoc listener
+//! let mut total = 0;
+//! let mut fw = ObservedFenwick::new(8).with_observer(FnObserver(|_, delta: &i32| total += delta));
+//! fw.update(1, 4);
+//! fw.update(2, 3);
+//! assert_eq!(prefix_sum(fw.as_slice(), 7), 7);
+//! drop(fw);
+//! assert_eq!(total, 7);
+//!
+//! // no observer
+//! let mut fw = ObservedFenwick::<i32>::new(8);
+//! fw.update(0, 1);
+//! assert_eq!(fw.prefix_sum(7), 1);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, split_view, update, SplitView};
+use crate::oplog::Op;
+
+/// Listener notified of every update of an [`ObservedFenwick`] .
+pub trait Observer<T> {
+    /// Called after conceptually performing `a[i] += delta` on the original array `a` .
+    fn on_update(&mut self, i: usize, delta: &T);
+}
+
+/// Does nothing.
+impl<T> Observer<T> for () {
+    #[inline(always)]
+    fn on_update(&mut self, _i: usize, _delta: &T) {}
+}
+
+/// Appends every update as an [`Op`] .
+impl<T: Clone> Observer<T> for Vec<Op<T>> {
+    fn on_update(&mut self, i: usize, delta: &T) {
+        self.push(Op { index: i, delta: delta.clone() });
+    }
+}
+
+/// Notifies both observers in order.
+impl<T, A: Observer<T>, B: Observer<T>> Observer<T> for (A, B) {
+    fn on_update(&mut self, i: usize, delta: &T) {
+        self.0.on_update(i, delta);
+        self.1.on_update(i, delta);
+    }
+}
+
+impl<T, O: Observer<T> + ?Sized> Observer<T> for &mut O {
+    fn on_update(&mut self, i: usize, delta: &T) {
+        (**self).on_update(i, delta);
+    }
+}
+
+/// Observer calling a closure with `(i, delta)` .
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FnObserver<F>(pub F);
+
+impl<T, F: FnMut(usize, &T)> Observer<T> for FnObserver<F> {
+    fn on_update(&mut self, i: usize, delta: &T) {
+        (self.0)(i, delta);
+    }
+}
+
+/// Fenwick tree notifying observer `O` of every update.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ObservedFenwick<T, O = ()> {
+    fenwick: Vec<T>,
+    observer: O,
+}
+
+impl<T> ObservedFenwick<T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a tree of `len` zeros, with no observer.
+    pub fn new(len: usize) -> Self {
+        Self {
+            fenwick: vec![T::default(); len],
+            observer: (),
+        }
+    }
+}
+
+impl<T, O> ObservedFenwick<T, O>
+where
+    T: AddAssign + Clone + Default,
+    O: Observer<T>
+{
+    /// Replaces the observer, which will be notified of all further updates.
+    pub fn with_observer<P: Observer<T>>(self, observer: P) -> ObservedFenwick<T, P> {
+        ObservedFenwick {
+            fenwick: self.fenwick,
+            observer,
+        }
+    }
+
+    /// Returns the observer.
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Returns the observer for mutation (e.g. to drain collected events).
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Returns the backing array of the tree (see [`crate::array`]).
+    pub fn as_slice(&self) -> &[T] {
+        &self.fenwick
+    }
+
+    /// Returns a read-only view of the tree that can be split into parts for parallel traversal.
+    /// See [`split_view`] .
+    pub fn split_view(&self) -> SplitView<'_, T> {
+        split_view(&self.fenwick)
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.fenwick.is_empty()
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` , then notifies the
+    /// observer. Takes `O(log(N))` plus the observer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, delta: T) {
+        update(&mut self.fenwick, i, delta.clone());
+        self.observer.on_update(i, &delta);
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the original array `a` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        prefix_sum(&self.fenwick, i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    use crate::oplog::replay;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..32 {
+            let mut mirror = std::vec![0i64; len];
+            let mut count = 0;
+            let counter = FnObserver(|_, _: &i64| count += 1);
+            let mut fw = ObservedFenwick::new(len).with_observer((Vec::new(), counter));
+            for _ in 0..len * 2 {
+                fw.update(rng.gen_range(0..len), rng.gen_range(-9..=9));
+                // mirror stays in sync by draining the stream
+                replay(&mut mirror, fw.observer().0.iter());
+                fw.observer_mut().0.clear();
+            }
+            assert_eq!(mirror, fw.as_slice());
+            drop(fw);
+            assert_eq!(count, len * 2);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Offline algorithms answering batches of queries over a static array with Fenwick trees.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::offline::{range_frequency, range_kth, range_majority};
+//!
+//! let values = [5, 1, 4, 2, 3, 9, 7];
+//! // (range of positions, k) : k-th smallest (zero-based) value within the range
+//! let answers = range_kth(&values, &[(0..=6, 0), (0..=6, 6), (2..=4, 1), (3..=5, 2)]);
+//! assert_eq!(answers, [&1, &9, &3, &9]);
+//!
+//! let values = [2, 7, 7, 1, 7, 2, 2, 2];
+//! let answers = range_majority(&values, &[1..=4, 0..=7, 4..=7, 0..=1]);
+//! assert_eq!(answers, [Some(&7), None, Some(&2), None]);
+//! // (range of positions, value) : number of occurrences of the value within the range
+//! let answers = range_frequency(&values, &[(0..=7, &2), (1..=5, &7), (0..=7, &4)]);
+//! assert_eq!(answers, [4, 3, 0]);
+//! ```
+//!
+
+use core::ops::{Range, RangeBounds};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, update};
+use crate::index::to_range;
+use crate::index::zero_based::up;
+
+/// Finds the `k`-th smallest (zero-based) value within `values[range]` for each `(range, k)` in
+/// `queries` .
+///
+/// Uses parallel binary search over the sorted order of `values` : in each of the `O(log(N))`
+/// rounds, positions are inserted into a Fenwick tree over positions in increasing order of value,
+/// and each query checks how many of the inserted positions fall in its range. Takes
+/// `O((N + Q) log(N)^2)` for `N` values and `Q` queries, and `O(N + Q)` extra space.
+///
+/// Equal values are ordered by position, so the result is always one of the values in the range.
+///
+/// # Panics
+///
+/// Panics if any range is empty or out of bound, or if `k` is not less than the length of its
+/// range.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn range_kth<'a, T: Ord>(
+    values: &'a [T],
+    queries: &[(impl RangeBounds<usize>, usize)],
+) -> Vec<&'a T> {
+    let n = values.len();
+    let queries: Vec<(Range<usize>, usize)> = queries.iter().map(|(range, k)| {
+        let range = to_range(range, n);
+        assert!(*k < range.len());
+        (range, *k)
+    }).collect();
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| values[i].cmp(&values[j]));
+
+    // the answer of query `q` is `order[r]` for some `lo[q] <= r <= hi[q]`
+    let mut lo = vec![0usize; queries.len()];
+    let mut hi = vec![n.saturating_sub(1); queries.len()];
+    let mut fenwick = vec![0usize; n];
+    let mut pending: Vec<(usize, usize)> = Vec::with_capacity(queries.len());
+    loop {
+        pending.clear();
+        pending.extend(
+            (0..queries.len())
+                .filter(|&q| lo[q] < hi[q])
+                .map(|q| ((lo[q] + hi[q]) / 2, q))
+        );
+        if pending.is_empty() {
+            break;
+        }
+        pending.sort_unstable();
+        fenwick.fill(0);
+        let mut inserted = 0;
+        for &(mid, q) in &pending {
+            while inserted <= mid {
+                update(&mut fenwick, order[inserted], 1);
+                inserted += 1;
+            }
+            let (range, k) = &queries[q];
+            let mut count = prefix_sum(&fenwick, range.end - 1);
+            if range.start > 0 {
+                count -= prefix_sum(&fenwick, range.start - 1);
+            }
+            if count > *k {
+                hi[q] = mid;
+            } else {
+                lo[q] = mid + 1;
+            }
+        }
+    }
+    lo.into_iter().map(|r| &values[order[r]]).collect()
+}
+
+/// Counts the occurrences of `value` within `values[range]` for each `(range, value)` in
+/// `queries` .
+///
+/// Queries are answered in increasing order of value: the positions of each distinct value that is
+/// queried are inserted into a Fenwick tree over positions, and removed again after its queries.
+/// Takes `O(N log(N) + Q log(Q N))` for `N` values and `Q` queries, and `O(N + Q)` extra space.
+///
+/// # Panics
+///
+/// Panics if any range is out of bound.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn range_frequency<T: Ord>(
+    values: &[T],
+    queries: &[(impl RangeBounds<usize>, &T)],
+) -> Vec<usize> {
+    let n = values.len();
+    let ranges: Vec<Range<usize>> = queries.iter().map(|(range, _)| to_range(range, n)).collect();
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| values[i].cmp(&values[j]));
+    let mut by_value: Vec<usize> = (0..queries.len()).collect();
+    by_value.sort_by(|&p, &q| queries[p].1.cmp(queries[q].1));
+
+    let mut answers = vec![0; queries.len()];
+    let mut fenwick = vec![0usize; n];
+    // positions `order[group.clone()]` of the current value are inserted
+    let mut group = 0..0;
+    for q in by_value {
+        let (range, value) = (&ranges[q], queries[q].1);
+        if group.is_empty() || values[order[group.start]] != *value {
+            for &i in &order[group.clone()] {
+                for ii in up(i, n) {
+                    fenwick[ii] -= 1;
+                }
+            }
+            let start = group.end + order[group.end..].partition_point(|&i| values[i] < *value);
+            let len = order[start..].partition_point(|&i| values[i] == *value);
+            group = start..start + len;
+            for &i in &order[group.clone()] {
+                update(&mut fenwick, i, 1);
+            }
+        }
+        if !range.is_empty() {
+            answers[q] = prefix_sum(&fenwick, range.end - 1);
+            if range.start > 0 {
+                answers[q] -= prefix_sum(&fenwick, range.start - 1);
+            }
+        }
+    }
+    answers
+}
+
+/// Finds the majority value within `values[range]` for each range in `ranges` , i.e. the value
+/// occurring in strictly more than half of the positions, if any (so never for an empty range).
+///
+/// A majority value must be the median of the range, so this finds the median of each range with
+/// [`range_kth`] , then checks how often it occurs with [`range_frequency`] . Takes
+/// `O((N + Q) log(N)^2)` for `N` values and `Q` ranges.
+///
+/// # Panics
+///
+/// Panics if any range is out of bound.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn range_majority<'a, T: Ord>(
+    values: &'a [T],
+    ranges: &[impl RangeBounds<usize>],
+) -> Vec<Option<&'a T>> {
+    let ranges: Vec<Range<usize>> =
+        ranges.iter().map(|range| to_range(range, values.len())).collect();
+    // an empty range has no median, and no majority
+    let nonempty: Vec<Range<usize>> =
+        ranges.iter().filter(|range| !range.is_empty()).cloned().collect();
+    let queries: Vec<_> =
+        nonempty.iter().map(|range| (range.clone(), (range.len() - 1) / 2)).collect();
+    let medians = range_kth(values, &queries);
+    let queries: Vec<_> = nonempty.iter().cloned().zip(medians.iter().copied()).collect();
+    let counts = range_frequency(values, &queries);
+    let mut majorities = nonempty.iter().zip(medians).zip(counts).map(|((range, median), count)| {
+        (count * 2 > range.len()).then_some(median)
+    });
+    ranges.iter().map(|range| {
+        if range.is_empty() { None } else { majorities.next().unwrap() }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    #[test]
+    fn range_kth_randoms() {
+        let mut rng = thread_rng();
+        for n in 1..64 {
+            let values = (0..n).map(|_| rng.gen_range(0..n / 2 + 1)).collect_vec();
+            let queries = (0..n * 2).map(|_| {
+                let a = rng.gen_range(0..n);
+                let b = rng.gen_range(0..n);
+                let (l, r) = (a.min(b), a.max(b));
+                (l..=r, rng.gen_range(0..=r - l))
+            }).collect_vec();
+            let answers = range_kth(&values, &queries);
+            for ((range, k), answer) in queries.iter().zip(answers) {
+                let sorted = values[range.clone()].iter().sorted().collect_vec();
+                assert_eq!(answer, sorted[*k]);
+            }
+        }
+        let no_queries: [(Range<usize>, usize); 0] = [];
+        assert!(range_kth::<i32>(&[], &no_queries).is_empty());
+    }
+
+    #[test]
+    fn range_majority_empty() {
+        let values = [1, 1, 1, 2];
+        assert_eq!(range_majority(&values, &[2..2, 0..3, 4..4]), [None, Some(&1), None]);
+    }
+
+    #[test]
+    fn range_majority_randoms() {
+        let mut rng = thread_rng();
+        for n in 1..64 {
+            // few distinct values, so that majorities are common
+            let values = (0..n).map(|_| rng.gen_range(0..3)).collect_vec();
+            // including empty ranges
+            let ranges = (0..n * 2).map(|_| {
+                let a = rng.gen_range(0..=n);
+                let b = rng.gen_range(0..=n);
+                a.min(b)..a.max(b)
+            }).collect_vec();
+            let answers = range_majority(&values, &ranges);
+            for (range, answer) in ranges.iter().zip(answers) {
+                let counts = values[range.clone()].iter().counts();
+                let expected = counts.into_iter()
+                    .find(|&(_, count)| count * 2 > range.clone().count())
+                    .map(|(value, _)| value);
+                assert_eq!(answer, expected);
+            }
+            let queries = (0..n * 2).map(|_| {
+                let a = rng.gen_range(0..n);
+                let b = rng.gen_range(0..n);
+                (a.min(b)..=a.max(b), rng.gen_range(0..4))
+            }).collect_vec();
+            let borrowed = queries.iter().map(|(range, x)| (range.clone(), x)).collect_vec();
+            let answers = range_frequency(&values, &borrowed);
+            for ((range, value), answer) in queries.iter().zip(answers) {
+                assert_eq!(answer, values[range.clone()].iter().filter(|&x| x == value).count());
+            }
+        }
+    }
+}
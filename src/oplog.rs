@@ -0,0 +1,553 @@
+//! Operation logs for reconstructing Fenwick trees deterministically.
+//!
+//! Every change to a Fenwick tree stored in a slice is an [`Op`] . Applying the same sequence of
+//! operations to a zeroed slice of the same length always produces the same backing array, so a
+//! tree can be replicated or restored by shipping its operation log instead of its contents.
+//!
+//! With the `serde` feature enabled, [`Op`] implements `Serialize` and `Deserialize` .
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use fenwick::array::prefix_sum;
+//! use fenwick::oplog::{replay, Op, Recorder};
+//!
+//! let mut primary = [0i32; 8];
+//! let mut rec = Recorder::new(&mut primary);
+//! rec.update(3, 5);
+//! rec.update(6, -2);
+//! rec.apply(Op { index: 0, delta: 1 });
+//! assert_eq!(rec.prefix_sum(7), 4);
+//! let log = rec.into_log();
+//! assert_eq!(log.len(), 3);
+//!
+//! let mut replica = [0i32; 8];
+//! replay(&mut replica, &log);
+//! assert_eq!(replica, primary);
+//! assert_eq!(prefix_sum(&replica, 5), 6);
+//! # }
+//! ```
+//!
+//! Recorded operations can also be undone, e.g. to explore a speculative batch of updates with a
+//! [`Transaction`] :
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use fenwick::array::prefix_sum;
+//! use fenwick::oplog::Transaction;
+//!
+//! let mut fw = [0i32; 8];
+//! let mut tx = Transaction::begin(&mut fw);
+//! tx.update(2, 5);
+//! tx.update(4, 1);
+//! assert_eq!(tx.prefix_sum(7), 6);
+//! tx.rollback();
+//! assert_eq!(fw, [0; 8]);
+//!
+//! let mut tx = Transaction::begin(&mut fw);
+//! tx.update(2, 5);
+//! tx.commit();
+//! assert_eq!(prefix_sum(&fw, 7), 5);
+//!
+//! {
+//!     let mut tx = Transaction::begin(&mut fw);
+//!     tx.update(3, 2);
+//! } // dropped without committing: rolled back
+//! assert_eq!(prefix_sum(&fw, 7), 5);
+//! # }
+//! ```
+//!
+//! An [`UndoableFenwick`] keeps a bounded history of batches for undo and redo:
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use fenwick::oplog::{Op, UndoableFenwick};
+//!
+//! let mut fw = UndoableFenwick::new(8, 2);
+//! fw.update(1, 5);
+//! fw.apply([Op { index: 2, delta: 1 }, Op { index: 3, delta: 1 }]);
+//! fw.update(4, 7);
+//! assert_eq!(fw.prefix_sum(7), 14);
+//! assert!(fw.undo());
+//! assert_eq!(fw.prefix_sum(7), 7);
+//! assert!(fw.undo());
+//! assert_eq!(fw.prefix_sum(7), 5);
+//! assert!(!fw.undo()); // only the last 2 batches are kept
+//! assert!(fw.redo());
+//! assert_eq!(fw.prefix_sum(7), 7);
+//! # }
+//! ```
+//!
+
+use core::ops::{AddAssign, SubAssign};
+
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::array::update;
+#[cfg(feature = "alloc")]
+use crate::array::{prefix_sum, split_view, SplitView};
+use crate::index::zero_based::up;
+
+/// One operation on a Fenwick tree: conceptually `a[index] += delta` on the original array `a` .
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Op<T> {
+    pub index: usize,
+    pub delta: T,
+}
+
+/// Applies one operation to the Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// # Panics
+///
+/// Panics if `fenwick[op.index]` is out of bound.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn apply<T>(fenwick: &mut [T], op: Op<T>)
+where
+    T: AddAssign + Clone + Default
+{
+    update(fenwick, op.index, op.delta);
+}
+
+/// Applies a sequence of operations in order to the Fenwick tree stored in a borrowed slice
+/// (zero-based).
+///
+/// # Panics
+///
+/// Panics if any operation is out of bound. Operations before it have already been applied.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn replay<'a, T, I>(fenwick: &mut [T], ops: I)
+where
+    T: AddAssign + Clone + Default + 'a,
+    I: IntoIterator<Item = &'a Op<T>>,
+{
+    for op in ops {
+        apply(fenwick, op.clone());
+    }
+}
+
+/// Reverts one operation on the Fenwick tree stored in a borrowed slice (zero-based), i.e.
+/// conceptually performs `a[op.index] -= op.delta` on the original array `a` .
+///
+/// # Panics
+///
+/// Panics if `fenwick[op.index]` is out of bound.
+///
+pub fn unapply<T>(fenwick: &mut [T], op: Op<T>)
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    for ii in up(op.index, fenwick.len()) {
+        fenwick[ii] -= op.delta.clone();
+    }
+}
+
+/// Reverts a sequence of operations (previously applied in order) on the Fenwick tree stored in a
+/// borrowed slice (zero-based), by reverting them in reverse order.
+///
+/// # Panics
+///
+/// Panics if any operation is out of bound. Operations after it have already been reverted.
+///
+pub fn undo<'a, T, I>(fenwick: &mut [T], ops: I)
+where
+    T: AddAssign + SubAssign + Clone + Default + 'a,
+    I: IntoIterator<Item = &'a Op<T>>,
+    I::IntoIter: DoubleEndedIterator,
+{
+    for op in ops.into_iter().rev() {
+        unapply(fenwick, op.clone());
+    }
+}
+
+/// Fenwick tree stored in a borrowed slice that records every operation applied through it.
+///
+/// See [module-level documentation](self).
+///
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Recorder<'a, T> {
+    fenwick: &'a mut [T],
+    log: Vec<Op<T>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> Recorder<'a, T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Starts recording operations on `fenwick` with an empty log.
+    pub fn new(fenwick: &'a mut [T]) -> Self {
+        Self {
+            fenwick,
+            log: Vec::new(),
+        }
+    }
+
+    /// Applies and records one operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fenwick[op.index]` is out of bound. Nothing is recorded in that case.
+    ///
+    pub fn apply(&mut self, op: Op<T>) {
+        apply(self.fenwick, op.clone());
+        self.log.push(op);
+    }
+
+    /// Applies and records `a[i] += delta` . See [`update`] .
+    pub fn update(&mut self, i: usize, delta: T) {
+        self.apply(Op { index: i, delta });
+    }
+
+    /// Calculates a prefix sum. See [`prefix_sum`] .
+    pub fn prefix_sum(&self, i: usize) -> T {
+        prefix_sum(self.fenwick, i)
+    }
+
+    /// Returns the operations recorded so far.
+    pub fn log(&self) -> &[Op<T>] {
+        &self.log
+    }
+
+    /// Stops recording and returns the recorded operations.
+    pub fn into_log(self) -> Vec<Op<T>> {
+        self.log
+    }
+}
+
+/// Batch of operations on a Fenwick tree stored in a borrowed slice that can be rolled back.
+///
+/// Operations are applied immediately, so queries through the transaction see them. Rolling back
+/// reverts them in `O(K log(N))` for `K` operations. Only [`Transaction::commit`] keeps the
+/// operations: dropping a transaction without committing it (e.g. on an early return or a panic)
+/// rolls it back, same as [`Transaction::rollback`] .
+///
+/// See [module-level documentation](self).
+///
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+#[must_use = "a transaction is rolled back unless committed"]
+pub struct Transaction<'a, T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    recorder: Recorder<'a, T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> Transaction<'a, T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Begins a transaction on `fenwick` .
+    pub fn begin(fenwick: &'a mut [T]) -> Self {
+        Self {
+            recorder: Recorder::new(fenwick),
+        }
+    }
+
+    /// Applies one operation as part of the transaction. See [`Recorder::apply`] .
+    pub fn apply(&mut self, op: Op<T>) {
+        self.recorder.apply(op);
+    }
+
+    /// Applies `a[i] += delta` as part of the transaction. See [`update`] .
+    pub fn update(&mut self, i: usize, delta: T) {
+        self.recorder.update(i, delta);
+    }
+
+    /// Calculates a prefix sum, including operations of the transaction. See [`prefix_sum`] .
+    pub fn prefix_sum(&self, i: usize) -> T {
+        self.recorder.prefix_sum(i)
+    }
+
+    /// Returns the operations of the transaction so far.
+    pub fn log(&self) -> &[Op<T>] {
+        self.recorder.log()
+    }
+
+    /// Keeps the operations of the transaction and returns them.
+    pub fn commit(mut self) -> Vec<Op<T>> {
+        span!("commit", ops = self.log().len());
+        // leaves nothing for `drop` to revert
+        core::mem::take(&mut self.recorder.log)
+    }
+
+    /// Reverts all operations of the transaction.
+    pub fn rollback(self) {
+        // reverted by `drop`
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Drop for Transaction<'_, T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    fn drop(&mut self) {
+        let Recorder { fenwick, log } = &mut self.recorder;
+        if !log.is_empty() {
+            span!("rollback", ops = log.len());
+            undo(fenwick, log.iter());
+        }
+    }
+}
+
+/// Fenwick tree with a bounded history of applied batches of operations, supporting undo and redo.
+///
+/// Undoing or redoing a batch of `K` operations takes `O(K log(N))` . Only the last `max_history`
+/// batches can be undone; applying a new batch discards the batches that could be redone.
+///
+/// See [module-level documentation](self).
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct UndoableFenwick<T> {
+    fenwick: Vec<T>,
+    max_history: usize,
+    undo: VecDeque<Vec<Op<T>>>,
+    redo: Vec<Vec<Op<T>>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> UndoableFenwick<T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Creates a tree with `len` zero elements, keeping at most `max_history` batches for undo.
+    pub fn new(len: usize, max_history: usize) -> Self {
+        Self {
+            fenwick: vec![T::default(); len],
+            max_history,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Returns the backing array of the Fenwick tree.
+    pub fn as_slice(&self) -> &[T] {
+        &self.fenwick
+    }
+
+    /// Returns a read-only view of the tree that can be split into parts for parallel traversal.
+    /// See [`split_view`] .
+    pub fn split_view(&self) -> SplitView<'_, T> {
+        split_view(&self.fenwick)
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.fenwick.is_empty()
+    }
+
+    /// Applies a batch of operations in order, to be undone as a whole.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any operation is out of bound. The tree and its history are unchanged in that
+    /// case.
+    ///
+    pub fn apply<I>(&mut self, ops: I)
+    where
+        I: IntoIterator<Item = Op<T>>
+    {
+        let ops: Vec<Op<T>> = ops.into_iter().collect();
+        let len = self.len();
+        assert!(ops.iter().all(|op| op.index < len));
+        replay(&mut self.fenwick, &ops);
+        self.redo.clear();
+        if self.max_history == 0 {
+            return;
+        }
+        if self.undo.len() == self.max_history {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(ops);
+    }
+
+    /// Applies `a[i] += delta` as a batch of one operation. See [`update`] .
+    pub fn update(&mut self, i: usize, delta: T) {
+        self.apply([Op { index: i, delta }]);
+    }
+
+    /// Calculates a prefix sum. See [`prefix_sum`] .
+    pub fn prefix_sum(&self, i: usize) -> T {
+        prefix_sum(&self.fenwick, i)
+    }
+
+    /// Reverts the last applied batch, returning `false` if there is none in the history.
+    pub fn undo(&mut self) -> bool {
+        let Some(ops) = self.undo.pop_back() else { return false };
+        undo(&mut self.fenwick, &ops);
+        self.redo.push(ops);
+        true
+    }
+
+    /// Re-applies the last undone batch, returning `false` if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(ops) = self.redo.pop() else { return false };
+        replay(&mut self.fenwick, &ops);
+        self.undo.push_back(ops);
+        true
+    }
+}
+
+#[cfg(all(test, any(feature = "alloc", feature = "serde")))]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    #[cfg(feature = "alloc")]
+    use rand::prelude::*;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut primary = std::vec![0i64; len];
+            let mut rec = Recorder::new(&mut primary);
+            for _ in 0..len * 2 {
+                rec.update(rng.gen_range(0..len), rng.gen_range(-100..=100));
+            }
+            let log = rec.into_log();
+            let mut replica = std::vec![0i64; len];
+            replay(&mut replica, &log);
+            assert_eq!(replica, primary);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_wide_integers() {
+        fn assert_serde<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+        assert_serde::<Op<i128>>();
+        assert_serde::<Op<u128>>();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn transaction_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let mut fw = std::vec![0i64; len];
+            for _ in 0..len {
+                update(&mut fw, rng.gen_range(0..len), rng.gen_range(-100..=100));
+            }
+            let before = fw.clone();
+            let mut tx = Transaction::begin(&mut fw);
+            for _ in 0..len * 2 {
+                tx.update(rng.gen_range(0..len), rng.gen_range(-100..=100));
+            }
+            let log = tx.log().to_vec();
+            tx.rollback();
+            assert_eq!(fw, before);
+
+            let mut tx = Transaction::begin(&mut fw);
+            for op in &log {
+                tx.apply(*op);
+            }
+            assert_eq!(tx.commit(), log);
+            let mut expected = before.clone();
+            replay(&mut expected, &log);
+            assert_eq!(fw, expected);
+            undo(&mut fw, &log);
+            assert_eq!(fw, before);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn transaction_drop() {
+        let mut fw = [0i64; 8];
+        update(&mut fw, 1, 3);
+        let before = fw;
+        // early return through `?`
+        let speculate = |fw: &mut [i64], limit: i64| -> Result<std::vec::Vec<Op<i64>>, i64> {
+            let mut tx = Transaction::begin(fw);
+            tx.update(2, 4);
+            tx.update(5, -1);
+            let total = tx.prefix_sum(7);
+            if total > limit {
+                return Err(total);
+            }
+            Ok(tx.commit())
+        };
+        assert_eq!(speculate(&mut fw, 5), Err(6));
+        assert_eq!(fw, before);
+        let log = speculate(&mut fw, 6).unwrap();
+        undo(&mut fw, &log);
+        assert_eq!(fw, before);
+        // panic
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut tx = Transaction::begin(&mut fw);
+            tx.update(0, 7);
+            tx.update(8, 1);
+        }));
+        assert!(result.is_err());
+        assert_eq!(fw, before);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn undoable_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..32 {
+            let max_history = rng.gen_range(0..8);
+            let mut fw = UndoableFenwick::new(len, max_history);
+            // states after each batch still in the undo history, and after each undone batch
+            let mut states = std::vec![fw.as_slice().to_vec()];
+            let mut undone = std::vec![];
+            for _ in 0..64 {
+                match rng.gen_range(0..4) {
+                    0 => {
+                        let expected = states.len() > 1;
+                        assert_eq!(fw.undo(), expected);
+                        if expected {
+                            undone.push(states.pop().unwrap());
+                        }
+                    }
+                    1 => {
+                        let expected = undone.pop();
+                        assert_eq!(fw.redo(), expected.is_some());
+                        states.extend(expected);
+                    }
+                    _ => {
+                        let k = rng.gen_range(1..4);
+                        let ops = (0..k).map(|_| Op {
+                            index: rng.gen_range(0..len),
+                            delta: rng.gen_range(-100..=100i64),
+                        }).collect::<std::vec::Vec<_>>();
+                        fw.apply(ops);
+                        undone.clear();
+                        states.push(fw.as_slice().to_vec());
+                        if states.len() > max_history + 1 {
+                            states.remove(0);
+                        }
+                    }
+                }
+                assert_eq!(fw.as_slice(), states.last().unwrap().as_slice());
+            }
+        }
+    }
+}
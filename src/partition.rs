@@ -0,0 +1,268 @@
+//! Fenwick tree whose index space is divided into named partitions, with updates confined to one
+//! partition at a time, e.g. for multi-tenant counters sharing one tree.
+//!
+//! Each partition owns a contiguous range of indices. Updates go through a [`Partition`] handle,
+//! which rejects indices outside its range, while sums can be taken over any range of the whole
+//! tree. Adding a partition appends its range to the end of the tree in `O(K log(N))` for `K`
+//! indices.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::partition::PartitionedFenwick;
+//!
+//! let mut counters = PartitionedFenwick::new();
+//! let acme = counters.add_partition("acme", 4);
+//! let globex = counters.add_partition("globex", 3);
+//! assert_eq!(counters.range(globex), 4..7);
+//! assert_eq!(counters.find("acme"), Some(acme));
+//!
+//! let mut tenant = counters.partition_mut(acme);
+//! tenant.update(1, 5).unwrap();
+//! assert!(tenant.update(4, 1).is_err()); // belongs to "globex"
+//! counters.partition_mut(globex).update(6, 2).unwrap();
+//!
+//! assert_eq!(counters.partition_sum(acme), 5);
+//! assert_eq!(counters.range_sum(0..7), 7);
+//! assert_eq!(counters.range_sum(2..5), 0);
+//! ```
+//!
+
+use core::fmt;
+use core::ops::{AddAssign, Range, RangeBounds, SubAssign};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, push, update};
+use crate::index::to_range;
+
+/// Update rejected because its index is outside the partition.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OutOfPartition {
+    /// Rejected index.
+    pub index: usize,
+    /// Range of indices of the partition.
+    pub range: Range<usize>,
+}
+
+impl fmt::Display for OutOfPartition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "index {} is outside partition {:?}", self.index, self.range)
+    }
+}
+
+impl core::error::Error for OutOfPartition {}
+
+/// Fenwick tree divided into named partitions.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PartitionedFenwick<T> {
+    fenwick: Vec<T>,
+    /// Name and range of each partition, in order of indices.
+    partitions: Vec<(String, Range<usize>)>,
+}
+
+impl<T> PartitionedFenwick<T>
+where
+    T: AddAssign + SubAssign + Clone + Default
+{
+    /// Creates a tree with no partitions (and no indices).
+    pub fn new() -> Self {
+        Self {
+            fenwick: Vec::new(),
+            partitions: Vec::new(),
+        }
+    }
+
+    /// Returns the number of indices over all partitions.
+    pub fn len(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns `true` if there are no indices.
+    pub fn is_empty(&self) -> bool {
+        self.fenwick.is_empty()
+    }
+
+    /// Returns the number of partitions.
+    pub fn partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Adds a partition of `len` zero elements named `name` after all existing ones, and returns
+    /// its id. Takes `O(K log(N))` for `K == len` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if a partition named `name` already exists.
+    ///
+    pub fn add_partition(&mut self, name: &str, len: usize) -> usize {
+        assert!(self.find(name).is_none(), "duplicate partition name");
+        let start = self.len();
+        for _ in 0..len {
+            push(&mut self.fenwick, T::default());
+        }
+        self.partitions.push((name.to_string(), start..start + len));
+        self.partitions.len() - 1
+    }
+
+    /// Returns the id of the partition named `name` , if any.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.partitions.iter().position(|(n, _)| n == name)
+    }
+
+    /// Returns the name of partition `id` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id >= self.partitions()` .
+    ///
+    pub fn name(&self, id: usize) -> &str {
+        &self.partitions[id].0
+    }
+
+    /// Returns the range of indices of partition `id` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id >= self.partitions()` .
+    ///
+    pub fn range(&self, id: usize) -> Range<usize> {
+        self.partitions[id].1.clone()
+    }
+
+    /// Returns a handle for updating partition `id` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id >= self.partitions()` .
+    ///
+    pub fn partition_mut(&mut self, id: usize) -> Partition<'_, T> {
+        Partition {
+            range: self.range(id),
+            fenwick: &mut self.fenwick,
+        }
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` over all partitions. Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        prefix_sum(&self.fenwick, i)
+    }
+
+    /// Conceptually calculates the sum of `a[i]` over `range` , which may span partitions. Takes
+    /// `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn range_sum(&self, range: impl RangeBounds<usize>) -> T {
+        let range = to_range(&range, self.len());
+        if range.is_empty() {
+            return T::default();
+        }
+        let mut sum = prefix_sum(&self.fenwick, range.end - 1);
+        if range.start > 0 {
+            sum -= prefix_sum(&self.fenwick, range.start - 1);
+        }
+        sum
+    }
+
+    /// Returns the sum over partition `id` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id >= self.partitions()` .
+    ///
+    pub fn partition_sum(&self, id: usize) -> T {
+        self.range_sum(self.range(id))
+    }
+}
+
+/// Handle for updating one partition of a [`PartitionedFenwick`] .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Debug)]
+pub struct Partition<'a, T> {
+    range: Range<usize>,
+    fenwick: &'a mut Vec<T>,
+}
+
+impl<T> Partition<'_, T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Returns the range of indices of the partition.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` if `i` is in the partition,
+    /// or returns an error otherwise. Takes `O(log(N))` .
+    pub fn update(&mut self, i: usize, delta: T) -> Result<(), OutOfPartition> {
+        if !self.range.contains(&i) {
+            return Err(OutOfPartition {
+                index: i,
+                range: self.range(),
+            });
+        }
+        update(self.fenwick, i, delta);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use std::format;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        let mut fw = PartitionedFenwick::new();
+        let mut naive: std::vec::Vec<i64> = std::vec![];
+        let mut ranges = std::vec![];
+        for p in 0..8 {
+            let len = rng.gen_range(0..8);
+            let id = fw.add_partition(&format!("p{}", p), len);
+            assert_eq!(id, p);
+            ranges.push(naive.len()..naive.len() + len);
+            naive.resize(naive.len() + len, 0);
+            for _ in 0..20 {
+                let id = rng.gen_range(0..=p);
+                let i = rng.gen_range(0..naive.len().max(1));
+                let x = rng.gen_range(-9..=9);
+                let result = fw.partition_mut(id).update(i, x);
+                if ranges[id].contains(&i) {
+                    assert_eq!(result, Ok(()));
+                    naive[i] += x;
+                } else {
+                    assert_eq!(result, Err(OutOfPartition { index: i, range: ranges[id].clone() }));
+                }
+            }
+            for (id, range) in ranges.iter().enumerate() {
+                assert_eq!(fw.range(id), *range);
+                assert_eq!(fw.find(&format!("p{}", id)), Some(id));
+                assert_eq!(fw.partition_sum(id), naive[range.clone()].iter().sum());
+            }
+            for a in 0..=naive.len() {
+                for b in a..=naive.len() {
+                    assert_eq!(fw.range_sum(a..b), naive[a..b].iter().sum());
+                }
+            }
+        }
+    }
+}
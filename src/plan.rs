@@ -0,0 +1,130 @@
+//! Gather plans for executing batches of prefix sum queries outside of this crate (e.g. on a GPU
+//! or with SIMD).
+//!
+//! A prefix sum on a Fenwick tree is a sum of the nodes at a fixed list of indices, which only
+//! depends on the queried index. A [`QueryPlan`] lists these indices for a batch of queries in
+//! compressed sparse row form: the nodes summed by query `q` are at
+//! `indices[offsets[q]..offsets[q + 1]]` of the backing array (see [`crate::array`]). Plans are
+//! deterministic: the same queries always produce the same plan, with indices in the order of
+//! [`down`].
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{build, prefix_sum};
+//! use fenwick::plan::QueryPlan;
+//!
+//! let fw = &mut [1, 2, 3, 4, 5, 6, 7, 8];
+//! build(fw);
+//! let plan = QueryPlan::new(&[0, 6, 7]);
+//! assert_eq!(plan.len(), 3);
+//! assert_eq!(plan.indices(2), [7]);
+//! assert_eq!(plan.indices(1), [6, 5, 3]);
+//!
+//! // gather and sum externally, e.g.:
+//! let sums: Vec<i32> = (0..plan.len())
+//!     .map(|q| plan.indices(q).iter().map(|&i| fw[i]).sum())
+//!     .collect();
+//! assert_eq!(sums, [1, 28, 36]);
+//! assert_eq!(plan.execute(fw), sums);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use alloc::vec::Vec;
+
+use crate::index::zero_based::down;
+
+/// Node indices gathered by a batch of prefix sum queries.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct QueryPlan {
+    /// `offsets[q]..offsets[q + 1]` is the range of `indices` for query `q` .
+    pub offsets: Vec<usize>,
+    /// Node indices of all queries, concatenated.
+    pub indices: Vec<usize>,
+}
+
+impl QueryPlan {
+    /// Plans the prefix sums up to and including each index in `queries` . Takes `O(Q log(N))` for
+    /// `Q` queries.
+    pub fn new(queries: &[usize]) -> Self {
+        let mut offsets = Vec::with_capacity(queries.len() + 1);
+        let mut indices = Vec::new();
+        offsets.push(0);
+        for &i in queries {
+            indices.extend(down(i));
+            offsets.push(indices.len());
+        }
+        Self { offsets, indices }
+    }
+
+    /// Returns the number of queries.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns `true` if there are no queries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the node indices summed by query `q` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q >= self.len()` .
+    ///
+    pub fn indices(&self, q: usize) -> &[usize] {
+        &self.indices[self.offsets[q]..self.offsets[q + 1]]
+    }
+
+    /// Executes the plan on the Fenwick tree stored in a borrowed slice (zero-based), as a
+    /// reference for external executors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any queried index is out of bound.
+    ///
+    pub fn execute<T>(&self, fenwick: &[T]) -> Vec<T>
+    where
+        T: AddAssign + Clone + Default
+    {
+        (0..self.len()).map(|q| {
+            let mut sum = T::default();
+            for &i in self.indices(q) {
+                sum += fenwick[i].clone();
+            }
+            sum
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    use crate::array::{build, prefix_sum};
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..128 {
+            let mut fenwick = (0..len).map(|_| rng.gen_range(-100..=100)).collect_vec();
+            build(&mut fenwick);
+            let queries = (0..len).map(|_| rng.gen_range(0..len)).collect_vec();
+            let plan = QueryPlan::new(&queries);
+            assert_eq!(plan, QueryPlan::new(&queries));
+            let expected = queries.iter().map(|&i| prefix_sum(&fenwick, i)).collect_vec();
+            assert_eq!(plan.execute(&fenwick), expected);
+        }
+        assert!(QueryPlan::new(&[]).is_empty());
+    }
+}
@@ -0,0 +1,418 @@
+//! Differentially private releases of prefix sums, with noise added to query results and a privacy
+//! budget spent per release.
+//!
+//! A [`NoisyFenwick`] wraps the backing array of an exact tree. Updates go to the exact tree, which
+//! is never perturbed; each released prefix or range sum adds noise drawn from a user-supplied
+//! sampler, scaled for the configured [`Mechanism`] , and spends the cost of the mechanism from a
+//! [`Budget`] under basic sequential composition (costs of releases add up). Once the budget is
+//! spent, further releases fail with [`BudgetExhausted`] .
+//!
+//! The sampler draws standard noise: a Laplace sample with scale `1` for
+//! [`Mechanism::Laplace`] , or a standard normal sample for [`Mechanism::Gaussian`] . Keeping the
+//! source of randomness outside of the crate lets callers choose a cryptographically secure one.
+//!
+//! Released sums are `f64` : elements are converted with [`ToF64`] , which is implemented for all
+//! primitive integers and floats, so that e.g. `u64` counters can be released.
+//!
+//! `sensitivity` is the largest change one individual can make to a released sum, e.g. `1` if each
+//! individual contributes at most one count. Range sums are released with the same sensitivity as
+//! prefix sums.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::privacy::{Budget, Mechanism, NoisyFenwick};
+//!
+//! // a sampler that always returns zero, for the sake of a deterministic example
+//! let sampler = || 0.0;
+//! let mechanism = Mechanism::Laplace { sensitivity: 1.0, epsilon: 0.5 };
+//! let budget = Budget { epsilon: 1.0, delta: 0.0 };
+//! let mut fw = NoisyFenwick::new(vec![0u32; 8], mechanism, budget, sampler);
+//! fw.update(2, 10);
+//! fw.update(5, 3);
+//!
+//! assert_eq!(fw.release_prefix_sum(4), Ok(10.0));
+//! assert_eq!(fw.release_range_sum(3..8), Ok(3.0));
+//! assert!(fw.release_prefix_sum(7).is_err()); // budget spent
+//! assert_eq!(fw.remaining(), Budget { epsilon: 0.0, delta: 0.0 });
+//! assert_eq!(fenwick::array::prefix_sum(fw.exact(), 7), 13); // exact tree intact
+//! ```
+//!
+
+use core::fmt;
+use core::ops::{AddAssign, RangeBounds, SubAssign};
+
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, update};
+use crate::index::to_range;
+use crate::stats::sqrt;
+
+/// Relative tolerance when comparing spent and total budget, so that e.g. ten releases of
+/// `epsilon = 0.1` fit in a budget of `1.0` despite rounding.
+const BUDGET_TOLERANCE: f64 = 1e-9;
+
+/// Noise added to each release, and its privacy cost.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mechanism {
+    /// Laplace noise with scale `sensitivity / epsilon` , costing `(epsilon, 0)` per release.
+    Laplace {
+        /// Largest change one individual can make to a released sum.
+        sensitivity: f64,
+        /// Privacy loss per release.
+        epsilon: f64,
+    },
+    /// Gaussian noise with standard deviation `sensitivity * sqrt(2 ln(1.25 / delta)) / epsilon` ,
+    /// costing `(epsilon, delta)` per release. The calibration requires `epsilon < 1` .
+    Gaussian {
+        /// Largest change one individual can make to a released sum.
+        sensitivity: f64,
+        /// Privacy loss per release.
+        epsilon: f64,
+        /// Probability of exceeding the privacy loss per release.
+        delta: f64,
+    },
+}
+
+impl Mechanism {
+    /// Returns the privacy cost of one release.
+    pub fn cost(&self) -> Budget {
+        match *self {
+            Mechanism::Laplace { epsilon, .. } => Budget { epsilon, delta: 0.0 },
+            Mechanism::Gaussian { epsilon, delta, .. } => Budget { epsilon, delta },
+        }
+    }
+
+    /// Returns the factor applied to standard noise, i.e. the scale of the Laplace noise or the
+    /// standard deviation of the Gaussian noise.
+    pub fn scale(&self) -> f64 {
+        match *self {
+            Mechanism::Laplace { sensitivity, epsilon } => sensitivity / epsilon,
+            Mechanism::Gaussian { sensitivity, epsilon, delta } => {
+                sensitivity * sqrt(2.0 * ln(1.25 / delta)) / epsilon
+            }
+        }
+    }
+
+    /// Panics unless the parameters are in range.
+    fn validate(&self) {
+        match *self {
+            Mechanism::Laplace { sensitivity, epsilon } => {
+                assert!(sensitivity > 0.0 && sensitivity.is_finite());
+                assert!(epsilon > 0.0 && epsilon.is_finite());
+            }
+            Mechanism::Gaussian { sensitivity, epsilon, delta } => {
+                assert!(sensitivity > 0.0 && sensitivity.is_finite());
+                assert!(epsilon > 0.0 && epsilon < 1.0);
+                assert!((f64::MIN_POSITIVE..1.0).contains(&delta));
+            }
+        }
+    }
+}
+
+/// Privacy budget `(epsilon, delta)` .
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Budget {
+    /// Total privacy loss.
+    pub epsilon: f64,
+    /// Total probability of exceeding the privacy loss.
+    pub delta: f64,
+}
+
+/// Release rejected because it would exceed the privacy budget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BudgetExhausted {
+    /// Budget left, which is less than the cost of one release.
+    pub remaining: Budget,
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "privacy budget exhausted (remaining epsilon {}, delta {})",
+            self.remaining.epsilon, self.remaining.delta
+        )
+    }
+}
+
+impl core::error::Error for BudgetExhausted {}
+
+/// Conversion of an element to `f64` for release, rounding to the nearest `f64` if needed (e.g.
+/// for `u64` values above `2^53`).
+pub trait ToF64 {
+    /// Returns `self` as the nearest `f64` .
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_to_f64 {
+    ($($t:ty),*) => {
+        $(
+            impl ToF64 for $t {
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_to_f64!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// Exact Fenwick tree releasing noisy sums within a privacy budget.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug)]
+pub struct NoisyFenwick<T, S> {
+    fenwick: Vec<T>,
+    mechanism: Mechanism,
+    budget: Budget,
+    releases: usize,
+    sampler: S,
+}
+
+impl<T, S> NoisyFenwick<T, S>
+where
+    T: AddAssign + SubAssign + Clone + Default + ToF64,
+    S: FnMut() -> f64
+{
+    /// Wraps the backing array of an exact tree (see [`crate::array`]), releasing sums with
+    /// `mechanism` within `budget` , and drawing standard noise from `sampler` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parameters of `mechanism` are out of range: `sensitivity` and `epsilon` must
+    /// be positive and finite, and for [`Mechanism::Gaussian`] , `epsilon < 1` and
+    /// `0 < delta < 1` .
+    ///
+    pub fn new(fenwick: Vec<T>, mechanism: Mechanism, budget: Budget, sampler: S) -> Self {
+        mechanism.validate();
+        Self {
+            fenwick,
+            mechanism,
+            budget,
+            releases: 0,
+            sampler,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.fenwick.is_empty()
+    }
+
+    /// Returns the backing array of the exact tree. It must not be released.
+    pub fn exact(&self) -> &[T] {
+        &self.fenwick
+    }
+
+    /// Returns the backing array of the exact tree, ending the releases.
+    pub fn into_exact(self) -> Vec<T> {
+        self.fenwick
+    }
+
+    /// Returns the configured mechanism.
+    pub fn mechanism(&self) -> Mechanism {
+        self.mechanism
+    }
+
+    /// Returns the number of releases so far.
+    pub fn releases(&self) -> usize {
+        self.releases
+    }
+
+    /// Returns the budget spent by the releases so far.
+    pub fn spent(&self) -> Budget {
+        self.spent_after(self.releases)
+    }
+
+    /// Returns the budget left, clamped at zero.
+    pub fn remaining(&self) -> Budget {
+        let spent = self.spent();
+        Budget {
+            epsilon: (self.budget.epsilon - spent.epsilon).max(0.0),
+            delta: (self.budget.delta - spent.delta).max(0.0),
+        }
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` of the exact tree. Costs no
+    /// budget.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, delta: T) {
+        update(&mut self.fenwick, i, delta);
+    }
+
+    /// Releases `a[0] + ... + a[i]` plus noise, spending the cost of one release.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BudgetExhausted`] (without spending anything) if the release would exceed the
+    /// budget.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn release_prefix_sum(&mut self, i: usize) -> Result<f64, BudgetExhausted> {
+        let exact = prefix_sum(&self.fenwick, i);
+        self.release(exact)
+    }
+
+    /// Releases the sum of `a[range]` plus noise, spending the cost of one release.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BudgetExhausted`] (without spending anything) if the release would exceed the
+    /// budget.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound (see [`crate::array`]).
+    ///
+    pub fn release_range_sum(
+        &mut self,
+        range: impl RangeBounds<usize>,
+    ) -> Result<f64, BudgetExhausted> {
+        let range = to_range(&range, self.len());
+        let mut exact = T::default();
+        if range.start < range.end {
+            exact = prefix_sum(&self.fenwick, range.end - 1);
+            if range.start > 0 {
+                exact -= prefix_sum(&self.fenwick, range.start - 1);
+            }
+        }
+        self.release(exact)
+    }
+
+    /// Returns the budget spent by `releases` releases.
+    fn spent_after(&self, releases: usize) -> Budget {
+        let cost = self.mechanism.cost();
+        Budget {
+            epsilon: releases as f64 * cost.epsilon,
+            delta: releases as f64 * cost.delta,
+        }
+    }
+
+    /// Spends one release and returns `exact` plus noise.
+    fn release(&mut self, exact: T) -> Result<f64, BudgetExhausted> {
+        let spent = self.spent_after(self.releases + 1);
+        let fits = |spent: f64, total: f64| spent <= total * (1.0 + BUDGET_TOLERANCE);
+        if !fits(spent.epsilon, self.budget.epsilon) || !fits(spent.delta, self.budget.delta) {
+            return Err(BudgetExhausted { remaining: self.remaining() });
+        }
+        span!("release", releases = self.releases);
+        self.releases += 1;
+        Ok(exact.to_f64() + self.mechanism.scale() * (self.sampler)())
+    }
+}
+
+/// Natural logarithm of a positive normal `x` , since `core` has none.
+fn ln(x: f64) -> f64 {
+    debug_assert!(x.is_normal() && x > 0.0);
+    // x = m * 2^e with m in [sqrt(1/2), sqrt(2)) , then ln(m) = 2 atanh((m - 1) / (m + 1))
+    let bits = x.to_bits();
+    let mut e = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mut m = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    if m > core::f64::consts::SQRT_2 {
+        m /= 2.0;
+        e += 1;
+    }
+    let s = (m - 1.0) / (m + 1.0);
+    let (mut term, mut sum, mut k) = (s, 0.0, 1.0);
+    while term.abs() > f64::EPSILON * f64::EPSILON {
+        sum += term / k;
+        term *= s * s;
+        k += 2.0;
+    }
+    2.0 * sum + e as f64 * core::f64::consts::LN_2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn math() {
+        for x in [1e-300, 0.001, 0.5, 1.0, 1.25, 2.0, 3.0, 1e5, 1.25e10, 1e300] {
+            assert!((ln(x) - x.ln()).abs() <= 1e-14 * x.ln().abs().max(1.0));
+        }
+        let gaussian = Mechanism::Gaussian { sensitivity: 2.0, epsilon: 0.5, delta: 1e-5 };
+        let expected = 2.0 * (2.0 * (1.25f64 / 1e-5).ln()).sqrt() / 0.5;
+        assert!((gaussian.scale() - expected).abs() < 1e-12 * expected);
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = StdRng::seed_from_u64(1161);
+        let len = 100;
+        let mut naive = std::vec![0i32; len];
+        // standard Laplace noise by inverse transform sampling
+        let mut noise = StdRng::seed_from_u64(1);
+        let sampler = move || {
+            let u: f64 = noise.gen_range(-0.5..0.5);
+            -u.signum() * (1.0 - 2.0 * u.abs()).ln()
+        };
+        let mechanism = Mechanism::Laplace { sensitivity: 1.0, epsilon: 0.01 };
+        let budget = Budget { epsilon: 10.0, delta: 0.0 };
+        let mut fw = NoisyFenwick::new(std::vec![0i32; len], mechanism, budget, sampler);
+        let mut error = 0.0;
+        for _ in 0..1000 {
+            let i = rng.gen_range(0..len);
+            let delta = rng.gen_range(-5..=5);
+            fw.update(i, delta);
+            naive[i] += delta;
+            let exact = naive[..=i].iter().sum::<i32>();
+            assert_eq!(prefix_sum(fw.exact(), i), exact);
+            error += fw.release_prefix_sum(i).unwrap() - exact as f64;
+        }
+        // noise has zero mean and a standard deviation of `sqrt(2) * 100` per release
+        assert!((error / 1000.0).abs() < 20.0);
+        assert_eq!(fw.releases(), 1000);
+        assert_eq!(fw.release_range_sum(..), Err(BudgetExhausted { remaining: fw.remaining() }));
+        assert_eq!(fw.releases(), 1000);
+        assert!(fw.remaining().epsilon < 1e-9);
+    }
+
+    #[test]
+    fn budget() {
+        let mechanism = Mechanism::Gaussian { sensitivity: 1.0, epsilon: 0.1, delta: 1e-6 };
+        let budget = Budget { epsilon: 1.0, delta: 1e-5 };
+        let mut fw = NoisyFenwick::new(std::vec![1.0f64; 4], mechanism, budget, || 0.0);
+        for _ in 0..10 {
+            assert_eq!(fw.release_range_sum(..1), Ok(1.0));
+        }
+        assert!(fw.release_range_sum(2..2).is_err());
+        assert_eq!(fw.spent().epsilon, 1.0);
+    }
+
+    #[test]
+    fn wide_counters() {
+        let mechanism = Mechanism::Laplace { sensitivity: 1.0, epsilon: 0.1 };
+        let budget = Budget { epsilon: 1.0, delta: 0.0 };
+        let mut fw = NoisyFenwick::new(std::vec![0u64; 4], mechanism, budget, || 1.0);
+        fw.update(1, 1 << 40);
+        fw.update(3, 7);
+        assert_eq!(fw.release_prefix_sum(2), Ok((1u64 << 40) as f64 + 10.0));
+        assert_eq!(fw.release_range_sum(2..), Ok(17.0));
+        let mut fw = NoisyFenwick::new(std::vec![0usize; 4], mechanism, budget, || 0.0);
+        fw.update(0, usize::MAX);
+        assert_eq!(fw.release_prefix_sum(3), Ok(usize::MAX as f64));
+        let mut fw = NoisyFenwick::new(std::vec![0i64; 4], mechanism, budget, || 0.0);
+        fw.update(2, -5);
+        assert_eq!(fw.release_range_sum(..), Ok(-5.0));
+    }
+}
@@ -0,0 +1,191 @@
+//! Resampling of particles by weight, e.g. in the resampling step of a particle filter.
+//!
+//! Weights are `f64` , kept in a Fenwick tree stored in a slice (see [`crate::array`]), so that a
+//! filter can update the weights of individual particles in `O(log(N))` as observations arrive,
+//! instead of rebuilding a cumulative array of `N` weights before every resampling step. Each
+//! function draws `k` particle indices with probability proportional to their weights, by `k`
+//! inverse-CDF walks over the tree, taking `O(k log(N))` :
+//!
+//! - [`multinomial`] draws `k` independent indices.
+//! - [`stratified`] divides the total weight into `k` equal strata, and draws one independent
+//!   index from each.
+//! - [`systematic`] draws one random offset, and takes the index at the same offset in each of
+//!   the `k` strata.
+//!
+//! Stratified and systematic resampling return indices in increasing order, and have lower
+//! variance than multinomial resampling. For integer weights, see also
+//! [`WeightedSampler`](crate::sampler::WeightedSampler).
+//!
+//! All weights must be non-negative and finite.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{build, update};
+//! use fenwick::resample::{multinomial, stratified, systematic};
+//!
+//! let mut rng = rand::thread_rng();
+//! let mut weights = [0.5, 0.0, 1.5, 2.0];
+//! build(&mut weights);
+//! assert_eq!(systematic(&weights, 8, &mut rng), [0, 2, 2, 2, 3, 3, 3, 3]);
+//! // particle 1 becomes likely after an observation
+//! update(&mut weights, 1, 12.0);
+//! let drawn = stratified(&weights, 8, &mut rng);
+//! assert!(drawn.iter().filter(|&&i| i == 1).count() >= 5);
+//! assert!(multinomial(&weights, 8, &mut rng).iter().all(|&i| i < 4));
+//! ```
+//!
+
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use crate::array::{lower_bound, prefix_sum, upper_bound};
+
+/// Draws `k` independent indices, each with probability proportional to its weight, from the
+/// Fenwick tree of weights stored in a borrowed slice. Returns an empty vector if the total weight
+/// is zero. Takes `O(k log(N))` .
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn multinomial<R: Rng + ?Sized>(fenwick: &[f64], k: usize, rng: &mut R) -> Vec<usize> {
+    let Some(walk) = Walk::new(fenwick) else { return Vec::new() };
+    (0..k).map(|_| walk.select(rng.gen::<f64>())).collect()
+}
+
+/// Divides the total weight into `k` equal strata, and draws one index from each, with probability
+/// proportional to its weight within the stratum, from the Fenwick tree of weights stored in a
+/// borrowed slice. Returns the indices in increasing order, or an empty vector if the total weight
+/// is zero. Takes `O(k log(N))` .
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn stratified<R: Rng + ?Sized>(fenwick: &[f64], k: usize, rng: &mut R) -> Vec<usize> {
+    let Some(walk) = Walk::new(fenwick) else { return Vec::new() };
+    (0..k).map(|j| walk.select((j as f64 + rng.gen::<f64>()) / k as f64)).collect()
+}
+
+/// Draws one random offset, and selects the index at that offset within each of `k` equal strata
+/// of the total weight, from the Fenwick tree of weights stored in a borrowed slice. Each index `i`
+/// is drawn either `floor(k * w[i] / total)` or `ceil(k * w[i] / total)` times (up to rounding).
+/// Returns the indices in increasing order, or an empty vector if the total weight is zero. Takes
+/// `O(k log(N))` .
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn systematic<R: Rng + ?Sized>(fenwick: &[f64], k: usize, rng: &mut R) -> Vec<usize> {
+    let Some(walk) = Walk::new(fenwick) else { return Vec::new() };
+    let offset = rng.gen::<f64>();
+    (0..k).map(|j| walk.select((j as f64 + offset) / k as f64)).collect()
+}
+
+/// Inverse-CDF walks over a Fenwick tree of weights with positive total.
+struct Walk<'a> {
+    fenwick: &'a [f64],
+    total: f64,
+    /// The last index with positive weight, selected if a target rounds up to the total.
+    last: usize,
+}
+
+impl<'a> Walk<'a> {
+    fn new(fenwick: &'a [f64]) -> Option<Self> {
+        let total = match fenwick.len() {
+            0 => 0.0,
+            len => prefix_sum(fenwick, len - 1),
+        };
+        assert!(total >= 0.0 && total.is_finite(), "invalid total weight");
+        let last = lower_bound(fenwick, total).min(fenwick.len().saturating_sub(1));
+        (total > 0.0).then_some(Self { fenwick, total, last })
+    }
+
+    /// Returns the index at quantile `u` in `0.0..1.0` of the total weight.
+    fn select(&self, u: f64) -> usize {
+        upper_bound(self.fenwick, u * self.total).min(self.last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use rand::prelude::*;
+
+    use crate::array::build;
+
+    /// Selects the index at quantile `u` by scanning the cumulative array of weights.
+    fn naive_select(cumulative: &[f64], u: f64) -> usize {
+        let target = u * cumulative.last().unwrap();
+        cumulative.iter().position(|&c| c > target).unwrap()
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..48 {
+            // integral weights, so that sums are exact
+            let weights: Vec<f64> = (0..len).map(|_| rng.gen_range(0..5) as f64).collect();
+            let mut fenwick = weights.clone();
+            build(&mut fenwick);
+            let cumulative: Vec<f64> = weights.iter()
+                .scan(0.0, |sum, w| {
+                    *sum += w;
+                    Some(*sum)
+                })
+                .collect();
+            if cumulative[len - 1] == 0.0 {
+                assert!(multinomial(&fenwick, 4, &mut rng).is_empty());
+                assert!(stratified(&fenwick, 4, &mut rng).is_empty());
+                assert!(systematic(&fenwick, 4, &mut rng).is_empty());
+                continue;
+            }
+            for k in 0..len * 2 {
+                let seed = rng.gen();
+                let kf = k as f64;
+
+                let mut r = StdRng::seed_from_u64(seed);
+                let expected: Vec<usize> =
+                    (0..k).map(|_| naive_select(&cumulative, r.gen())).collect();
+                let drawn = multinomial(&fenwick, k, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(drawn, expected);
+
+                let mut r = StdRng::seed_from_u64(seed);
+                let expected: Vec<usize> = (0..k)
+                    .map(|j| naive_select(&cumulative, (j as f64 + r.gen::<f64>()) / kf))
+                    .collect();
+                let drawn = stratified(&fenwick, k, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(drawn, expected);
+
+                let offset: f64 = StdRng::seed_from_u64(seed).gen();
+                let expected: Vec<usize> = (0..k)
+                    .map(|j| naive_select(&cumulative, (j as f64 + offset) / kf))
+                    .collect();
+                let drawn = systematic(&fenwick, k, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(drawn, expected);
+                for (i, &w) in weights.iter().enumerate() {
+                    let count = drawn.iter().filter(|&&d| d == i).count() as f64;
+                    let share = kf * w / cumulative[len - 1];
+                    assert!(share.floor() - 1.0 < count && count < share.ceil() + 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rounding() {
+        // the last particle has zero weight, and must not be drawn even if a target rounds up
+        let mut fenwick = [0.1, 0.2, 0.3, 0.0];
+        build(&mut fenwick);
+        let walk = Walk::new(&fenwick).unwrap();
+        assert_eq!(walk.select(1.0), 2);
+        assert_eq!(walk.select(0.0), 0);
+    }
+}
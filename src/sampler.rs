@@ -0,0 +1,344 @@
+//! Weighted random sampling over a changing set of weights.
+//!
+//! [`WeightedSampler`] keeps non-negative integer weights in a Fenwick tree, so changing a weight
+//! and selecting the index that a uniform random number in `0..total` falls on both take
+//! `O(log(N))` . Several indices can also be selected at evenly spaced targets, as in systematic
+//! resampling. With the optional `rand` feature it can also draw indices directly from a random
+//! number generator, with or without replacement.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::sampler::WeightedSampler;
+//!
+//! let mut sampler = WeightedSampler::from_weights(&[3, 0, 1, 4]);
+//! assert_eq!(sampler.total(), 8);
+//! // `0..3` selects index 0, `3..4` selects index 2, `4..8` selects index 3
+//! assert_eq!(sampler.select(2), 0);
+//! assert_eq!(sampler.select(3), 2);
+//! assert_eq!(sampler.select(4), 3);
+//! sampler.set_weight(1, 2);
+//! assert_eq!(sampler.select(3), 1);
+//! ```
+//!
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{build, get, prefix_sum, upper_bound};
+use crate::index::zero_based::up;
+
+/// Weighted sampler over indices `0..N` .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WeightedSampler {
+    fenwick: Vec<u64>,
+}
+
+impl WeightedSampler {
+    /// Creates a sampler over `len` indices, all with zero weight.
+    pub fn new(len: usize) -> Self {
+        Self {
+            fenwick: vec![0; len],
+        }
+    }
+
+    /// Creates a sampler with the given weights. Takes `O(N)` .
+    pub fn from_weights(weights: &[u64]) -> Self {
+        let mut fenwick = weights.to_vec();
+        build(&mut fenwick);
+        Self { fenwick }
+    }
+
+    /// Returns the number of indices, i.e. `N` .
+    pub fn len(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns `true` if there are no indices.
+    pub fn is_empty(&self) -> bool {
+        self.fenwick.is_empty()
+    }
+
+    /// Returns the weight of index `i` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn weight(&self, i: usize) -> u64 {
+        get(&self.fenwick, i)
+    }
+
+    /// Adds `delta` to the weight of index `i` .
+    fn add(&mut self, i: usize, delta: u64) {
+        for ii in up(i, self.len()) {
+            self.fenwick[ii] += delta;
+        }
+    }
+
+    /// Subtracts `delta` from the weight of index `i` .
+    fn sub(&mut self, i: usize, delta: u64) {
+        for ii in up(i, self.len()) {
+            self.fenwick[ii] -= delta;
+        }
+    }
+
+    /// Sets the weight of index `i` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn set_weight(&mut self, i: usize, weight: u64) {
+        let old = self.weight(i);
+        if weight >= old {
+            self.add(i, weight - old);
+        } else {
+            self.sub(i, old - weight);
+        }
+    }
+
+    /// Returns the total weight.
+    pub fn total(&self) -> u64 {
+        match self.len() {
+            0 => 0,
+            n => prefix_sum(&self.fenwick, n - 1),
+        }
+    }
+
+    /// Returns the index that `target` falls on when the weights are laid out in order, i.e. the
+    /// smallest `i` such that `w[0] + ... + w[i] > target` . A uniform random `target` in
+    /// `0..self.total()` selects each index with probability proportional to its weight. Takes
+    /// `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target >= self.total()` .
+    ///
+    pub fn select(&self, target: u64) -> usize {
+        let i = upper_bound(&self.fenwick, target);
+        assert!(i < self.len(), "target out of range");
+        i
+    }
+
+    /// Selects `k` indices at the evenly spaced targets `(j * total + offset) / k` for `j` in
+    /// `0..k` (rounded down), in increasing order. Takes `O(k log(N))` .
+    ///
+    /// Each index `i` is selected either `floor(k * w[i] / total)` or `ceil(k * w[i] / total)`
+    /// times, and with a uniform random `offset` in `0..total` , exactly `k * w[i] / total` times
+    /// on average. See `sample_stratified` (with the `rand` feature) for drawing the offset.
+    ///
+    /// Returns an empty vector if the total weight is zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total weight is positive and `offset >= self.total()` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::sampler::WeightedSampler;
+    ///
+    /// let sampler = WeightedSampler::from_weights(&[3, 0, 1, 4]);
+    /// // targets 0, 2, 4, 6
+    /// assert_eq!(sampler.select_stratified(4, 0), [0, 0, 3, 3]);
+    /// // targets 1, 3, 5, 7
+    /// assert_eq!(sampler.select_stratified(4, 6), [0, 2, 3, 3]);
+    /// ```
+    ///
+    pub fn select_stratified(&self, k: usize, offset: u64) -> Vec<usize> {
+        let total = self.total();
+        if total == 0 {
+            return Vec::new();
+        }
+        assert!(offset < total, "offset out of range");
+        (0..k as u128)
+            .map(|j| self.select(((j * total as u128 + offset as u128) / k as u128) as u64))
+            .collect()
+    }
+
+    /// Draws one index with probability proportional to its weight, or returns `None` if the total
+    /// weight is zero. Takes `O(log(N))` .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::sampler::WeightedSampler;
+    ///
+    /// let sampler = WeightedSampler::from_weights(&[0, 5, 0]);
+    /// assert_eq!(sampler.sample(&mut rand::thread_rng()), Some(1));
+    /// ```
+    ///
+    #[cfg(feature = "rand")]
+    pub fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let total = self.total();
+        (total > 0).then(|| self.select(rng.gen_range(0..total)))
+    }
+
+    /// Draws `k` indices at evenly spaced quantiles with a single random offset (systematic
+    /// sampling), as in the resampling step of a particle filter. Each index is drawn about
+    /// `k * w[i] / total` times, like `k` independent draws on average but with much lower
+    /// variance. Returns an empty vector if the total weight is zero. Takes `O(k log(N))` .
+    ///
+    /// See [`WeightedSampler::select_stratified`] for the deterministic version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::sampler::WeightedSampler;
+    ///
+    /// let sampler = WeightedSampler::from_weights(&[1, 0, 2, 1]);
+    /// let drawn = sampler.sample_stratified(&mut rand::thread_rng(), 8);
+    /// assert_eq!(drawn, [0, 0, 2, 2, 2, 2, 3, 3]);
+    /// ```
+    ///
+    #[cfg(feature = "rand")]
+    pub fn sample_stratified<R: rand::Rng + ?Sized>(&self, rng: &mut R, k: usize) -> Vec<usize> {
+        match self.total() {
+            0 => Vec::new(),
+            total => self.select_stratified(k, rng.gen_range(0..total)),
+        }
+    }
+
+    /// Draws up to `k` distinct indices, each time with probability proportional to its weight
+    /// among the indices not drawn yet (successive sampling). Indices with zero weight are never
+    /// drawn, so fewer than `k` are returned if there are not enough indices with positive weight.
+    /// Takes `O(k log(N))` .
+    ///
+    /// Drawn indices are temporarily given zero weight, and their weights are restored before
+    /// returning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fenwick::sampler::WeightedSampler;
+    ///
+    /// let mut sampler = WeightedSampler::from_weights(&[1, 0, 100, 3]);
+    /// let mut drawn = sampler.sample_k_without_replacement(&mut rand::thread_rng(), 5);
+    /// drawn.sort();
+    /// assert_eq!(drawn, [0, 2, 3]);
+    /// assert_eq!(sampler.total(), 104);
+    /// ```
+    ///
+    #[cfg(feature = "rand")]
+    pub fn sample_k_without_replacement<R: rand::Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        k: usize,
+    ) -> Vec<usize> {
+        let mut drawn = Vec::with_capacity(k.min(self.len()));
+        let mut weights = Vec::with_capacity(drawn.capacity());
+        while drawn.len() < k {
+            let Some(i) = self.sample(rng) else { break };
+            let w = self.weight(i);
+            self.sub(i, w);
+            drawn.push(i);
+            weights.push(w);
+        }
+        for (&i, w) in drawn.iter().zip(weights) {
+            self.add(i, w);
+        }
+        drawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..48 {
+            let mut weights: std::vec::Vec<u64> = (0..len).map(|_| rng.gen_range(0..5)).collect();
+            let mut sampler = WeightedSampler::from_weights(&weights);
+            for _ in 0..len {
+                let i = rng.gen_range(0..len);
+                let w = rng.gen_range(0..5);
+                sampler.set_weight(i, w);
+                weights[i] = w;
+            }
+            assert_eq!(sampler.total(), weights.iter().sum::<u64>());
+            let mut target = 0;
+            for (i, &w) in weights.iter().enumerate() {
+                assert_eq!(sampler.weight(i), w);
+                for t in target..target + w {
+                    assert_eq!(sampler.select(t), i);
+                }
+                target += w;
+            }
+        }
+    }
+
+    #[test]
+    fn select_stratified_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..32 {
+            let weights: std::vec::Vec<u64> = (0..len).map(|_| rng.gen_range(0..5)).collect();
+            let sampler = WeightedSampler::from_weights(&weights);
+            let total = sampler.total();
+            if total == 0 {
+                assert!(sampler.select_stratified(4, 0).is_empty());
+                continue;
+            }
+            let mut cumulative = std::vec::Vec::new();
+            let mut sum = 0;
+            for &w in &weights {
+                sum += w;
+                cumulative.push(sum);
+            }
+            for k in 0..len * 2 {
+                let offset = rng.gen_range(0..total);
+                let selected = sampler.select_stratified(k, offset);
+                let mut counts = std::vec![0u64; len];
+                for (j, &i) in selected.iter().enumerate() {
+                    let target = (j as u64 * total + offset) / k as u64;
+                    assert_eq!(i, cumulative.partition_point(|&c| c <= target));
+                    counts[i] += 1;
+                }
+                for (&count, &w) in counts.iter().zip(&weights) {
+                    let expected = k as u64 * w;
+                    assert!(count * total <= expected + total && expected <= count * total + total);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn sample_k_randoms() {
+        let mut rng = thread_rng();
+        for len in 1..32 {
+            let weights: std::vec::Vec<u64> = (0..len).map(|_| rng.gen_range(0..3)).collect();
+            let mut sampler = WeightedSampler::from_weights(&weights);
+            let positive = weights.iter().filter(|&&w| w > 0).count();
+            for k in 0..=len {
+                let mut drawn = sampler.sample_k_without_replacement(&mut rng, k);
+                assert_eq!(drawn.len(), k.min(positive));
+                assert!(drawn.iter().all(|&i| weights[i] > 0));
+                drawn.sort_unstable();
+                drawn.dedup();
+                assert_eq!(drawn.len(), k.min(positive));
+            }
+            assert_eq!(sampler, WeightedSampler::from_weights(&weights));
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn sample_k_distribution() {
+        // first draw from weights [1, 3] picks index 1 about 3/4 of the time
+        let mut rng = thread_rng();
+        let mut sampler = WeightedSampler::from_weights(&[1, 3]);
+        let firsts = (0..4000)
+            .filter(|_| sampler.sample_k_without_replacement(&mut rng, 1)[0] == 1)
+            .count();
+        assert!((2700..3300).contains(&firsts));
+    }
+}
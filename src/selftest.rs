@@ -0,0 +1,237 @@
+//! Deterministic self-test of the Fenwick tree implementations, and golden test vectors.
+//!
+//! [`run`] cross-checks every enabled backend (the [`array`](mod@array) functions, and with
+//! `alloc` the owned trees) against a naive array, over randomized operations generated from a
+//! seed, and returns a [`Report`] . The same seed always performs the same operations, so a failure
+//! can be reproduced exactly. This is cheap enough to run at startup in debug builds.
+//!
+//! [`GOLDEN_VECTORS`] are fixed original arrays with the backing arrays they build into (see
+//! [`build`](crate::array::build)), for validating other implementations and bindings.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::build;
+//! use fenwick::selftest::{run, GOLDEN_VECTORS};
+//!
+//! let report = run(42);
+//! assert!(report.is_ok(), "{}", report);
+//! assert!(report.checks > 0);
+//!
+//! for golden in GOLDEN_VECTORS {
+//!     let mut nodes = golden.values.to_vec();
+//!     build(&mut nodes);
+//!     assert_eq!(nodes, golden.nodes);
+//! }
+//! ```
+//!
+
+use core::fmt;
+
+use crate::array;
+use crate::conformance::FenwickConformance;
+
+/// Fixed original array and the backing array of the Fenwick tree it builds into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GoldenVector {
+    /// Original array.
+    pub values: &'static [i64],
+    /// Backing array of the Fenwick tree (zero-based).
+    pub nodes: &'static [i64],
+}
+
+/// Golden test vectors.
+pub const GOLDEN_VECTORS: &[GoldenVector] = &[
+    GoldenVector { values: &[], nodes: &[] },
+    GoldenVector { values: &[5], nodes: &[5] },
+    GoldenVector { values: &[1, 2], nodes: &[1, 3] },
+    GoldenVector {
+        values: &[3, 1, 4, 1, 5, 9, 2, 6],
+        nodes: &[3, 4, 4, 9, 5, 14, 2, 31],
+    },
+    GoldenVector {
+        values: &[1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+        nodes: &[1, 2, 1, 4, 1, 2, 1, 8, 1, 2],
+    },
+    GoldenVector {
+        values: &[-2, 7, 0, 0, -1, 8, 3, -5, 4, 0, 6, -9, 2, 1, 1, -3, 10],
+        nodes: &[-2, 5, 0, 5, -1, 7, 3, 10, 4, 4, 6, 1, 2, 3, 1, 12, 10],
+    },
+];
+
+/// First mismatch found by [`run`] .
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Failure {
+    /// Name of the backend.
+    pub backend: &'static str,
+    /// Number of elements of the tree.
+    pub len: usize,
+    /// Index of the mismatched prefix sum.
+    pub index: usize,
+    /// Prefix sum of the naive array.
+    pub expected: i64,
+    /// Prefix sum returned by the backend.
+    pub actual: i64,
+}
+
+/// Result of [`run`] .
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Report {
+    /// Seed the operations were generated from.
+    pub seed: u64,
+    /// Number of prefix sums compared.
+    pub checks: u64,
+    /// Number of mismatched prefix sums.
+    pub failures: u64,
+    /// First mismatch, if any.
+    pub first_failure: Option<Failure>,
+}
+
+impl Report {
+    /// Returns `true` if there are no mismatches.
+    pub fn is_ok(&self) -> bool {
+        self.failures == 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {}: {} checks, {} failures", self.seed, self.checks, self.failures)?;
+        if let Some(x) = &self.first_failure {
+            write!(
+                f,
+                " (first: {} with len {}, prefix sum at {}: expected {}, got {})",
+                x.backend, x.len, x.index, x.expected, x.actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// SplitMix64 generator, so that results do not depend on any external RNG.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n` (slightly biased, which does not matter here).
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+const MAX_LEN: usize = 64;
+const ROUNDS: usize = 16;
+
+/// Backing array on the stack, for the [`array`] functions.
+struct Array {
+    len: usize,
+    nodes: [i64; MAX_LEN],
+}
+
+impl FenwickConformance for Array {
+    const NAME: &'static str = "array";
+    fn new(len: usize) -> Self {
+        Self { len, nodes: [0; MAX_LEN] }
+    }
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn update(&mut self, i: usize, delta: i64) {
+        array::update(&mut self.nodes[..self.len], i, delta);
+    }
+    fn prefix_sum(&self, i: usize) -> i64 {
+        array::prefix_sum(&self.nodes[..self.len], i)
+    }
+}
+
+/// Runs randomized operations on backend `B` and a naive array, comparing all prefix sums after
+/// each round.
+fn check<B: FenwickConformance>(rng: &mut SplitMix64, report: &mut Report) {
+    for _ in 0..ROUNDS {
+        let len = 1 + rng.below(MAX_LEN);
+        let mut tree = B::new(len);
+        let mut naive = [0i64; MAX_LEN];
+        for _ in 0..len * 2 {
+            let i = rng.below(len);
+            let delta = rng.below(201) as i64 - 100;
+            tree.update(i, delta);
+            naive[i] += delta;
+        }
+        let mut expected = 0;
+        for (i, x) in naive[..len].iter().enumerate() {
+            expected += x;
+            let actual = tree.prefix_sum(i);
+            report.checks += 1;
+            if actual != expected {
+                report.failures += 1;
+                report.first_failure.get_or_insert(Failure {
+                    backend: B::NAME,
+                    len,
+                    index: i,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+}
+
+/// Cross-checks all enabled backends against a naive array over randomized operations generated
+/// from `seed` .
+///
+/// # Examples
+///
+/// See [module-level documentation](self).
+///
+pub fn run(seed: u64) -> Report {
+    let mut rng = SplitMix64(seed);
+    let mut report = Report {
+        seed,
+        checks: 0,
+        failures: 0,
+        first_failure: None,
+    };
+    check::<Array>(&mut rng, &mut report);
+    #[cfg(feature = "alloc")]
+    {
+        check::<crate::sparse::SparseFenwick<i64>>(&mut rng, &mut report);
+        check::<crate::cow::CowFenwick<i64>>(&mut rng, &mut report);
+        check::<crate::deque::FenwickDeque<i64>>(&mut rng, &mut report);
+        check::<crate::sharded::ShardedFenwick<i64>>(&mut rng, &mut report);
+        check::<crate::smart::SmartFenwick<i64>>(&mut rng, &mut report);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    #[test]
+    fn seeds() {
+        for seed in 0..16 {
+            let report = run(seed);
+            assert!(report.is_ok(), "{}", report);
+            assert_eq!(report, run(seed));
+        }
+    }
+
+    #[test]
+    fn golden_vectors() {
+        for golden in GOLDEN_VECTORS {
+            let mut nodes = golden.values.to_vec();
+            array::build(&mut nodes);
+            assert_eq!(nodes, golden.nodes);
+            array::unbuild(&mut nodes);
+            assert_eq!(nodes, golden.values);
+        }
+    }
+}
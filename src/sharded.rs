@@ -0,0 +1,203 @@
+//! Fenwick tree split into independently updatable shards over the same index domain.
+//!
+//! Each writer (e.g. thread) updates its own shard without contention, while queries aggregate
+//! over all shards in `O(S log(N))` for `S` shards. Shards can be collapsed into one with
+//! [`ShardedFenwick::merge`] in `O(S N)` to make queries cheap again.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::update;
+//! use fenwick::sharded::ShardedFenwick;
+//!
+//! let mut counter = ShardedFenwick::<u64>::new(100);
+//! for _ in 0..4 {
+//!     counter.register_shard();
+//! }
+//! std::thread::scope(|s| {
+//!     for (id, shard) in counter.shards_mut().enumerate() {
+//!         s.spawn(move || {
+//!             for i in 0..10 {
+//!                 update(shard, id * 10 + i, 1);
+//!             }
+//!         });
+//!     }
+//! });
+//! assert_eq!(counter.prefix_sum(9), 10);
+//! assert_eq!(counter.prefix_sum(99), 40);
+//! counter.merge();
+//! assert_eq!(counter.shards(), 1);
+//! assert_eq!(counter.prefix_sum(99), 40);
+//! ```
+//!
+
+use core::hash::{Hash, Hasher};
+use core::ops::AddAssign;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{merge_add, prefix_sum};
+
+/// Fenwick tree split into shards.
+///
+/// Equality and hashing are defined over the logical array summed over all shards, regardless of
+/// how updates are distributed between shards. Both take `O(S N)` .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default)]
+pub struct ShardedFenwick<T> {
+    len: usize,
+    shards: Vec<Vec<T>>,
+}
+
+impl<T> ShardedFenwick<T>
+where
+    T: AddAssign + Clone + Default
+{
+    /// Creates a sharded tree over `len` elements, with no shards.
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            shards: Vec::new(),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Adds an empty shard and returns its id.
+    pub fn register_shard(&mut self) -> usize {
+        self.shards.push(vec![T::default(); self.len]);
+        self.shards.len() - 1
+    }
+
+    /// Returns the backing array of the Fenwick tree of shard `id` , to be updated with
+    /// [`update`](crate::array::update) .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id >= self.shards()` .
+    ///
+    pub fn shard_mut(&mut self, id: usize) -> &mut [T] {
+        &mut self.shards[id]
+    }
+
+    /// Returns the backing arrays of all shards, which can be handed out to different writers.
+    pub fn shards_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        self.shards.iter_mut().map(|shard| shard.as_mut_slice())
+    }
+
+    /// Calculates the prefix sum up to and including `i` over all shards. Takes `O(S log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        assert!(i < self.len);
+        let mut sum = T::default();
+        for shard in &self.shards {
+            sum += prefix_sum(shard, i);
+        }
+        sum
+    }
+
+    /// Collapses all shards into a single one (shard `0`). Takes `O(S N)` .
+    ///
+    /// Ids of all other shards are invalidated.
+    pub fn merge(&mut self) {
+        span!("merge", len = self.len, shards = self.shards.len());
+        if let Some((first, rest)) = self.shards.split_first_mut() {
+            for shard in rest.iter() {
+                merge_add(first, shard);
+            }
+        }
+        self.shards.truncate(1);
+    }
+
+    /// Returns the backing array of the Fenwick tree summed over all shards. Takes `O(S N)` .
+    fn merged(&self) -> Vec<T> {
+        let mut merged = vec![T::default(); self.len];
+        for shard in &self.shards {
+            merge_add(&mut merged, shard);
+        }
+        merged
+    }
+}
+
+impl<T> PartialEq for ShardedFenwick<T>
+where
+    T: AddAssign + Clone + Default + PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.merged() == other.merged()
+    }
+}
+
+impl<T> Eq for ShardedFenwick<T>
+where
+    T: AddAssign + Clone + Default + Eq
+{}
+
+impl<T> Hash for ShardedFenwick<T>
+where
+    T: AddAssign + Clone + Default + Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.merged().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    use crate::array::update;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..32 {
+            let mut sharded = ShardedFenwick::new(len);
+            let mut naive = std::vec![0i64; len];
+            for _ in 0..4 {
+                let id = sharded.register_shard();
+                for _ in 0..len {
+                    let i = rng.gen_range(0..len);
+                    let x = rng.gen_range(-100..=100);
+                    update(sharded.shard_mut(id), i, x);
+                    naive[i] += x;
+                }
+            }
+            let psum = naive.iter().scan(0, |s, x| {
+                *s += x;
+                Some(*s)
+            });
+            for (i, s) in psum.enumerate() {
+                assert_eq!(sharded.prefix_sum(i), s);
+            }
+            let before = sharded.clone();
+            sharded.merge();
+            assert_eq!(sharded, before);
+            assert_eq!(sharded.shards(), 1);
+            assert_eq!(sharded.prefix_sum(len - 1), naive.iter().sum::<i64>());
+        }
+    }
+}
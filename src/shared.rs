@@ -0,0 +1,277 @@
+//! Fenwick tree of `u64` counters in a caller-provided region of atomics, e.g. in memory shared
+//! between processes.
+//!
+//! A [`SharedFenwick`] does not own its storage: it borrows a region of `AtomicU64` words, which
+//! may be a plain array, or a region of shared memory mapped by several processes (see
+//! [`SharedFenwick::from_ptr`]). The first word is a sequence number, and the rest are the nodes
+//! of the tree (see [`crate::array`]), in native byte order. Atomic operations on lock-free
+//! `AtomicU64` (which this module requires) also synchronize across processes.
+//!
+//! Counts wrap around on overflow, so subtractions can be read back as `i64` with `as` .
+//!
+//! # Protocol
+//!
+//! Any number of writers may call [`add`](SharedFenwick::add) and [`sub`](SharedFenwick::sub)
+//! concurrently, from any number of threads or processes: each is a sequence of atomic additions,
+//! and additions commute. A concurrent [`prefix_sum`](SharedFenwick::prefix_sum) reads exactly one
+//! node touched by each update (or none), so it sees each update either entirely or not at all.
+//! With only additions, it returns a value between the prefix sums at the start and at the end of
+//! the read. Separate reads may however see different sets of updates, so e.g. a range sum from
+//! two prefix sums may be torn.
+//!
+//! For consistent reads of several sums, there must be a single writer, which groups updates with
+//! [`write_batch`](SharedFenwick::write_batch). Readers then use
+//! [`read_consistent`](SharedFenwick::read_consistent), which retries until no batch overlapped the
+//! read (a sequence lock), and thus sees every batch either entirely or not at all.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::atomic::AtomicU64;
+//! use fenwick::shared::SharedFenwick;
+//!
+//! let region: Vec<AtomicU64> = (0..SharedFenwick::words(8)).map(|_| AtomicU64::new(0)).collect();
+//! let counters = SharedFenwick::new(&region);
+//! std::thread::scope(|s| {
+//!     // writer, e.g. the main process
+//!     s.spawn(|| {
+//!         for i in 0..8 {
+//!             counters.write_batch(|w| {
+//!                 w.add(i, 2);
+//!                 w.sub(7 - i, 1);
+//!             });
+//!         }
+//!     });
+//!     // reader, e.g. a sidecar process: each batch adds 1 in total
+//!     s.spawn(|| {
+//!         let (half, total) = counters.read_consistent(|r| (r.prefix_sum(3), r.prefix_sum(7)));
+//!         assert!(half <= 8 && total <= 8);
+//!     });
+//! });
+//! assert_eq!(counters.prefix_sum(7), 8);
+//! assert_eq!(counters.prefix_sum(0), 1);
+//! ```
+//!
+
+use core::sync::atomic::{fence, AtomicU64, Ordering};
+
+use crate::index::zero_based::{down, up};
+
+/// Fenwick tree of `u64` counters in a borrowed region of atomics.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Copy, Debug)]
+pub struct SharedFenwick<'a> {
+    /// Sequence number of the sequence lock: odd while a batch is being written.
+    seq: &'a AtomicU64,
+    nodes: &'a [AtomicU64],
+}
+
+impl<'a> SharedFenwick<'a> {
+    /// Returns the number of words of the region for a tree of `len` counters.
+    pub const fn words(len: usize) -> usize {
+        len + 1
+    }
+
+    /// Uses `region` as a tree of `region.len() - 1` counters. A region of zeros is a tree of zero
+    /// counters; otherwise the region must have been used as a tree before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region` is empty.
+    ///
+    pub fn new(region: &'a [AtomicU64]) -> Self {
+        let (seq, nodes) = region.split_first().expect("region has no sequence number");
+        Self { seq, nodes }
+    }
+
+    /// Uses the region of `words` words at `ptr` , e.g. a region of shared memory, as a tree of
+    /// `words - 1` counters.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads and writes of `words` words and aligned to
+    /// `align_of::<AtomicU64>()` , and during `'a` must only be accessed through atomic operations
+    /// (in this and any other process), as for `AtomicU64::from_ptr` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words == 0` .
+    ///
+    pub unsafe fn from_ptr(ptr: *mut u64, words: usize) -> Self {
+        // SAFETY: guaranteed by the caller; `AtomicU64` has the same size as `u64`
+        Self::new(unsafe { core::slice::from_raw_parts(ptr.cast::<AtomicU64>(), words) })
+    }
+
+    /// Returns the number of counters.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if there are no counters.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds `delta` to counter `i` (wrapping around on overflow). Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn add(&self, i: usize, delta: u64) {
+        assert!(i < self.len());
+        for ii in up(i, self.len()) {
+            self.nodes[ii].fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+
+    /// Subtracts `delta` from counter `i` (wrapping around on overflow). Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn sub(&self, i: usize, delta: u64) {
+        assert!(i < self.len());
+        for ii in up(i, self.len()) {
+            self.nodes[ii].fetch_sub(delta, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the sum of counters `0..=i` (wrapping around on overflow). Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> u64 {
+        assert!(i < self.len());
+        down(i).fold(0, |sum: u64, ii| sum.wrapping_add(self.nodes[ii].load(Ordering::Relaxed)))
+    }
+
+    /// Performs the updates in `f` as one batch, which [`SharedFenwick::read_consistent`] sees
+    /// either entirely or not at all.
+    ///
+    /// There must be no other writer at the same time, in any process; a concurrent batch or
+    /// update would break the consistency of reads (but not the counts themselves).
+    pub fn write_batch<R>(&self, f: impl FnOnce(&Self) -> R) -> R {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        let result = f(self);
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+        result
+    }
+
+    /// Performs the reads in `f` , retrying until no batch was written during them. Reads are
+    /// thus consistent with each other, if all updates are made in batches by a single writer.
+    ///
+    /// `f` may be called several times, and should not have side effects.
+    pub fn read_consistent<R>(&self, f: impl Fn(&Self) -> R) -> R {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq % 2 == 0 {
+                let result = f(self);
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == seq {
+                    return result;
+                }
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use rand::prelude::*;
+
+    fn region(len: usize) -> Vec<AtomicU64> {
+        (0..SharedFenwick::words(len)).map(|_| AtomicU64::new(0)).collect()
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..64 {
+            let region = region(len);
+            let fw = SharedFenwick::new(&region);
+            let mut naive = std::vec![0i64; len];
+            for _ in 0..len * 4 {
+                let i = rng.gen_range(0..len);
+                let delta = rng.gen_range(0..100);
+                if rng.gen_bool(0.5) {
+                    fw.add(i, delta);
+                    naive[i] += delta as i64;
+                } else {
+                    fw.sub(i, delta);
+                    naive[i] -= delta as i64;
+                }
+            }
+            let mut sum = 0;
+            for (i, x) in naive.iter().enumerate() {
+                sum += x;
+                assert_eq!(fw.prefix_sum(i) as i64, sum);
+            }
+        }
+    }
+
+    #[test]
+    fn multi_writer() {
+        let region = region(37);
+        let fw = SharedFenwick::new(&region);
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for k in 0..1000 {
+                        fw.add((t * 1000 + k) % 37, 1);
+                    }
+                });
+            }
+            s.spawn(|| {
+                // only additions: prefix sums never decrease
+                let mut last = 0;
+                for _ in 0..1000 {
+                    let total = fw.prefix_sum(36);
+                    assert!(last <= total && total <= 4000);
+                    last = total;
+                }
+            });
+        });
+        assert_eq!(fw.prefix_sum(36), 4000);
+    }
+
+    #[test]
+    fn single_writer_batches() {
+        let region = region(16);
+        let fw = SharedFenwick::new(&region);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let mut rng = thread_rng();
+                for _ in 0..2000 {
+                    // move one unit between counters: the total stays at zero
+                    let (i, j) = (rng.gen_range(0..16), rng.gen_range(0..16));
+                    fw.write_batch(|w| {
+                        w.add(i, 1);
+                        w.sub(j, 1);
+                    });
+                }
+            });
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        assert_eq!(fw.read_consistent(|r| r.prefix_sum(15)), 0);
+                    }
+                });
+            }
+        });
+        assert_eq!(fw.prefix_sum(15), 0);
+    }
+}
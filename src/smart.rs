@@ -0,0 +1,288 @@
+//! Fenwick tree that switches between dense and sparse storage as its density changes.
+//!
+//! A [`SmartFenwick`] stores its nodes either in a dense backing array (see [`crate::array`]) or
+//! in a [`SparseFenwick`] , and migrates between the two when the number of non-zero nodes crosses
+//! a fraction of the length. A mostly-empty tree thus takes memory proportional to its non-zero
+//! nodes, and a dense tree takes one element per node with `O(log(N))` operations, without
+//! choosing a backend up front. The two thresholds are apart, so that a tree near one of them does
+//! not migrate back and forth.
+//!
+//! The node values are the same in both forms, so migrating takes `O(N)` without rebuilding, and
+//! is amortized over the updates that changed the density.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::smart::SmartFenwick;
+//!
+//! let mut fw = SmartFenwick::new(1024);
+//! fw.update(3, 5);
+//! assert!(!fw.is_dense());
+//! for i in 0..1024 {
+//!     fw.update(i, 1);
+//! }
+//! assert!(fw.is_dense());
+//! assert_eq!(fw.prefix_sum(3), 9);
+//! for i in 0..1024 {
+//!     fw.update(i, -1);
+//! }
+//! assert!(!fw.is_dense());
+//! assert_eq!(fw.prefix_sum(1023), 5);
+//! ```
+//!
+
+use core::hash::{Hash, Hasher};
+use core::ops::AddAssign;
+
+use alloc::vec::Vec;
+
+use crate::array;
+use crate::index::zero_based::up;
+use crate::sparse::SparseFenwick;
+
+/// A sparse tree migrates to dense storage when more than `1 / DENSE_ABOVE` of its nodes are
+/// non-zero, since a map entry takes several times the memory of a dense node.
+const DENSE_ABOVE: usize = 4;
+
+/// A dense tree migrates to sparse storage when less than `1 / SPARSE_BELOW` of its nodes are
+/// non-zero.
+const SPARSE_BELOW: usize = 16;
+
+/// Fenwick tree with dense or sparse storage, chosen by density.
+///
+/// Two trees are equal (and hash the same) if and only if they have the same length and the same
+/// logical contents, regardless of their storage.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug)]
+pub struct SmartFenwick<T> {
+    storage: Storage<T>,
+}
+
+#[derive(Clone, Debug)]
+enum Storage<T> {
+    /// Backing array, and the number of non-zero nodes in it.
+    Dense(Vec<T>, usize),
+    Sparse(SparseFenwick<T>),
+}
+
+impl<T> SmartFenwick<T>
+where
+    T: AddAssign + Clone + Default + PartialEq
+{
+    /// Creates a tree with `len` zero elements, in sparse storage. Does not allocate.
+    pub fn new(len: usize) -> Self {
+        Self {
+            storage: Storage::Sparse(SparseFenwick::new(len)),
+        }
+    }
+
+    /// Creates a tree from the dense backing array of a Fenwick tree (see [`crate::array`]), in the
+    /// storage suited to its density. Takes `O(N)` .
+    pub fn from_slice(fenwick: &[T]) -> Self {
+        let zero = T::default();
+        let nonzero = fenwick.iter().filter(|&x| *x != zero).count();
+        let mut fw = Self {
+            storage: Storage::Dense(fenwick.to_vec(), nonzero),
+        };
+        fw.migrate();
+        fw
+    }
+
+    /// Returns the dense backing array of the tree (see [`crate::array`]). Takes `O(N)` .
+    pub fn to_vec(&self) -> Vec<T> {
+        match &self.storage {
+            Storage::Dense(fenwick, _) => fenwick.clone(),
+            Storage::Sparse(sparse) => sparse.to_vec(),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Dense(fenwick, _) => fenwick.len(),
+            Storage::Sparse(sparse) => sparse.len(),
+        }
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the tree is currently in dense storage.
+    pub fn is_dense(&self) -> bool {
+        matches!(self.storage, Storage::Dense(..))
+    }
+
+    /// Returns the number of non-zero nodes.
+    pub fn nonzero_nodes(&self) -> usize {
+        match &self.storage {
+            Storage::Dense(_, nonzero) => *nonzero,
+            Storage::Sparse(sparse) => sparse.nonzero_nodes(),
+        }
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` . Takes `O(log(N))` in dense
+    /// storage or `O(log(N)^2)` in sparse storage, plus `O(N)` if the tree migrates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, delta: T) {
+        match &mut self.storage {
+            Storage::Dense(fenwick, nonzero) => {
+                assert!(i < fenwick.len());
+                let zero = T::default();
+                for ii in up(i, fenwick.len()) {
+                    let was_zero = fenwick[ii] == zero;
+                    fenwick[ii] += delta.clone();
+                    match (was_zero, fenwick[ii] == zero) {
+                        (true, false) => *nonzero += 1,
+                        (false, true) => *nonzero -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            Storage::Sparse(sparse) => sparse.update(i, delta),
+        }
+        self.migrate();
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the original array `a` . Takes `O(log(N))`
+    /// in dense storage or `O(log(N)^2)` in sparse storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        match &self.storage {
+            Storage::Dense(fenwick, _) => array::prefix_sum(fenwick, i),
+            Storage::Sparse(sparse) => sparse.prefix_sum(i),
+        }
+    }
+
+    /// Migrates to the other storage if the density crossed its threshold.
+    fn migrate(&mut self) {
+        let (len, nonzero) = (self.len(), self.nonzero_nodes());
+        match &self.storage {
+            Storage::Dense(fenwick, _) if nonzero * SPARSE_BELOW < len => {
+                span!("migrate_to_sparse", len, nonzero);
+                self.storage = Storage::Sparse(SparseFenwick::from_slice(fenwick));
+            }
+            Storage::Sparse(sparse) if nonzero * DENSE_ABOVE > len => {
+                span!("migrate_to_dense", len, nonzero);
+                self.storage = Storage::Dense(sparse.to_vec(), nonzero);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> PartialEq for SmartFenwick<T>
+where
+    T: AddAssign + Clone + Default + PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.storage, &other.storage) {
+            (Storage::Dense(a, _), Storage::Dense(b, _)) => a == b,
+            (Storage::Sparse(a), Storage::Sparse(b)) => a == b,
+            _ => self.to_vec() == other.to_vec(),
+        }
+    }
+}
+
+impl<T> Eq for SmartFenwick<T> where T: AddAssign + Clone + Default + Eq {}
+
+impl<T> Hash for SmartFenwick<T>
+where
+    T: AddAssign + Clone + Default + PartialEq + Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // hash the sparse form, which is the same for both storages
+        match &self.storage {
+            Storage::Dense(fenwick, _) => SparseFenwick::from_slice(fenwick).hash(state),
+            Storage::Sparse(sparse) => sparse.hash(state),
+        }
+    }
+}
+
+impl<T> Default for SmartFenwick<T>
+where
+    T: AddAssign + Clone + Default + PartialEq
+{
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn logical_eq() {
+        use std::hash::BuildHasher;
+        let hasher = std::collections::hash_map::RandomState::new();
+
+        // same logical array in dense and sparse storage
+        let mut dense = std::vec![0i64; 64];
+        let mut sparse = SmartFenwick::new(64);
+        for i in [3, 20, 40] {
+            array::update(&mut dense, i, 1);
+            sparse.update(i, 1);
+        }
+        let dense = SmartFenwick::from_slice(&dense);
+        assert!(dense.is_dense() && !sparse.is_dense());
+        assert_eq!(dense, sparse);
+        assert_eq!(hasher.hash_one(&dense), hasher.hash_one(&sparse));
+        sparse.update(3, 1);
+        assert_ne!(dense, sparse);
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in [0, 1, 2, 7, 64, 1000] {
+            let mut fw = SmartFenwick::new(len);
+            let mut dense = std::vec![0i64; len];
+            let mut migrations = 0;
+            // grow the density with random increments, then shrink it by zeroing every element
+            let grow = (0..len.min(300) * 2).map(|_| {
+                let i = rng.gen_range(0..len);
+                (i, Some(rng.gen_range(1..=3)))
+            }).collect::<std::vec::Vec<_>>();
+            for (i, x) in grow.into_iter().chain((0..len).map(|i| (i, None))) {
+                let x = x.unwrap_or_else(|| -array::get(&dense, i));
+                let was_dense = fw.is_dense();
+                fw.update(i, x);
+                array::update(&mut dense, i, x);
+                migrations += (fw.is_dense() != was_dense) as usize;
+                let nonzero = dense.iter().filter(|&&x| x != 0).count();
+                assert_eq!(fw.nonzero_nodes(), nonzero);
+                if fw.is_dense() {
+                    assert!(nonzero * SPARSE_BELOW >= len);
+                } else {
+                    assert!(nonzero * DENSE_ABOVE <= len);
+                }
+                if i % 16 == 0 {
+                    assert_eq!(fw, SmartFenwick::from_slice(&dense));
+                }
+            }
+            assert_eq!(fw.to_vec(), dense);
+            assert_eq!(fw, SmartFenwick::from_slice(&dense));
+            for i in 0..len {
+                assert_eq!(fw.prefix_sum(i), array::prefix_sum(&dense, i));
+            }
+            if len >= 64 {
+                assert!(migrations >= 2);
+            }
+        }
+    }
+}
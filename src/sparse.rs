@@ -0,0 +1,173 @@
+//! Sparse Fenwick tree storing only its non-zero nodes, for large numbers of mostly-empty trees.
+//!
+//! An update touches `O(log(N))` nodes, so a tree with `K` non-zero elements has at most
+//! `O(K log(N))` non-zero nodes. An empty tree takes no heap memory at all, regardless of its
+//! length. Both operations take `O(log(N)^2)` (a map lookup per node).
+//!
+//! The node values are the same as those of the dense backing array used in [`crate::array`] , so
+//! the two forms convert into each other without rebuilding.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::sparse::SparseFenwick;
+//!
+//! let mut fw = SparseFenwick::new(1_000_000);
+//! fw.update(10, 5);
+//! fw.update(999_999, 3);
+//! fw.update(10, -5);
+//! assert_eq!(fw.prefix_sum(999_998), 0);
+//! assert_eq!(fw.prefix_sum(999_999), 3);
+//! assert!(fw.nonzero_nodes() <= 20);
+//!
+//! let mut dense = vec![0; 8];
+//! update(&mut dense, 6, 7);
+//! let mut fw = SparseFenwick::from_slice(&dense);
+//! fw.update(1, 2);
+//! assert_eq!(fw.prefix_sum(7), 9);
+//! let dense = fw.to_vec();
+//! assert_eq!(prefix_sum(&dense, 5), 2);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::index::zero_based::{down, up};
+
+/// Sparse Fenwick tree storing only its non-zero nodes.
+///
+/// Zero nodes are never stored, so two trees are equal if and only if they have the same length and
+/// the same logical contents.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SparseFenwick<T> {
+    len: usize,
+    nodes: BTreeMap<usize, T>,
+}
+
+impl<T> SparseFenwick<T>
+where
+    T: AddAssign + Clone + Default + PartialEq
+{
+    /// Creates a tree with `len` zero elements. Does not allocate.
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a tree from the dense backing array of a Fenwick tree (see [`crate::array`]). Takes
+    /// `O(N)` plus `O(log(N))` for each non-zero node.
+    pub fn from_slice(fenwick: &[T]) -> Self {
+        span!("from_slice", len = fenwick.len());
+        let zero = T::default();
+        Self {
+            len: fenwick.len(),
+            nodes: fenwick.iter().cloned().enumerate().filter(|(_, x)| *x != zero).collect(),
+        }
+    }
+
+    /// Returns the dense backing array of the tree (see [`crate::array`]). Takes `O(N)` .
+    pub fn to_vec(&self) -> Vec<T> {
+        span!("to_vec", len = self.len, nodes = self.nodes.len());
+        let mut fenwick = vec![T::default(); self.len];
+        for (&i, x) in &self.nodes {
+            fenwick[i] = x.clone();
+        }
+        fenwick
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of non-zero nodes actually stored.
+    pub fn nonzero_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Conceptually performs `a[i] += delta` on the original array `a` . Takes `O(log(N)^2)` .
+    ///
+    /// Nodes that become zero are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn update(&mut self, i: usize, delta: T) {
+        assert!(i < self.len);
+        if delta == T::default() {
+            return;
+        }
+        for ii in up(i, self.len) {
+            let node = self.nodes.entry(ii).or_default();
+            *node += delta.clone();
+            if *node == T::default() {
+                self.nodes.remove(&ii);
+            }
+        }
+    }
+
+    /// Conceptually calculates `a[0] + ... + a[i]` on the original array `a` . Takes
+    /// `O(log(N)^2)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix_sum(&self, i: usize) -> T {
+        assert!(i < self.len);
+        let mut sum = T::default();
+        for ii in down(i) {
+            if let Some(x) = self.nodes.get(&ii) {
+                sum += x.clone();
+            }
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    use crate::array;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in [1, 2, 7, 64, 1000] {
+            let mut fw = SparseFenwick::new(len);
+            let mut dense = std::vec![0i64; len];
+            for _ in 0..len.min(100) {
+                let i = rng.gen_range(0..len);
+                let x = rng.gen_range(-3..=3);
+                fw.update(i, x);
+                array::update(&mut dense, i, x);
+                assert_eq!(fw.to_vec(), dense);
+                assert_eq!(fw, SparseFenwick::from_slice(&dense));
+                assert_eq!(fw.nonzero_nodes(), dense.iter().filter(|&&x| x != 0).count());
+            }
+            for i in 0..len {
+                assert_eq!(fw.prefix_sum(i), array::prefix_sum(&dense, i));
+            }
+        }
+    }
+}
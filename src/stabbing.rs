@@ -0,0 +1,193 @@
+//! Dynamic set of intervals over a fixed range of positions, counting intervals that cover a
+//! position or intersect a range.
+//!
+//! The counter keeps two Fenwick trees counting the intervals by start and by end. An interval
+//! `l..r` covers position `i` if `l <= i < r` , and intersects range `a..b` if `l < b && a < r` ;
+//! both are differences of prefix counts over the two trees. Every operation takes `O(log(N))` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::stabbing::StabbingCounter;
+//!
+//! // live sessions over 100 timestamps
+//! let mut sessions = StabbingCounter::new(100);
+//! sessions.insert_interval(10..20);
+//! sessions.insert_interval(15..40);
+//! sessions.insert_interval(30..31);
+//! assert_eq!(sessions.len(), 3);
+//! assert_eq!(sessions.count_covering(15), 2);
+//! assert_eq!(sessions.count_covering(20), 1);
+//! assert_eq!(sessions.count_intersecting(19..31), 3);
+//! assert_eq!(sessions.count_intersecting(31..), 1);
+//! sessions.remove_interval(15..=39);
+//! assert_eq!(sessions.count_intersecting(31..), 0);
+//! ```
+//!
+
+use core::ops::RangeBounds;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, update};
+use crate::index::to_range;
+
+/// Dynamic set of intervals over positions `0..N` .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StabbingCounter {
+    /// Fenwick tree counting intervals by start (`0..N`).
+    starts: Vec<isize>,
+    /// Fenwick tree counting intervals by end (`0..=N`).
+    ends: Vec<isize>,
+    len: usize,
+}
+
+impl StabbingCounter {
+    /// Creates an empty set of intervals over positions `0..positions` .
+    pub fn new(positions: usize) -> Self {
+        Self {
+            starts: vec![0; positions],
+            ends: vec![0; positions + 1],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of positions, i.e. `N` .
+    pub fn positions(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns the number of intervals.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts interval `range` . Takes `O(log(N))` .
+    ///
+    /// Empty intervals cover and intersect nothing, and are not inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn insert_interval(&mut self, range: impl RangeBounds<usize>) {
+        let range = to_range(&range, self.positions());
+        if range.is_empty() {
+            return;
+        }
+        update(&mut self.starts, range.start, 1);
+        update(&mut self.ends, range.end, 1);
+        self.len += 1;
+    }
+
+    /// Removes interval `range` , which must have been inserted before. Takes `O(log(N))` .
+    ///
+    /// Removing an empty interval has no effect. Removing an interval that is not in the set makes
+    /// further results meaningless.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound or the set is empty.
+    ///
+    pub fn remove_interval(&mut self, range: impl RangeBounds<usize>) {
+        let range = to_range(&range, self.positions());
+        if range.is_empty() {
+            return;
+        }
+        assert!(self.len > 0);
+        update(&mut self.starts, range.start, -1);
+        update(&mut self.ends, range.end, -1);
+        self.len -= 1;
+    }
+
+    /// Returns the number of intervals that start before `i` (i.e. `l < i`).
+    fn starts_before(&self, i: usize) -> usize {
+        if i == 0 { 0 } else { prefix_sum(&self.starts, i - 1) as usize }
+    }
+
+    /// Returns the number of intervals that end at or before `i` (i.e. `r <= i`).
+    fn ends_upto(&self, i: usize) -> usize {
+        prefix_sum(&self.ends, i) as usize
+    }
+
+    /// Returns the number of intervals covering position `i` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.positions()` .
+    ///
+    pub fn count_covering(&self, i: usize) -> usize {
+        assert!(i < self.positions());
+        self.starts_before(i + 1) - self.ends_upto(i)
+    }
+
+    /// Returns the number of intervals intersecting `range` . Takes `O(log(N))` .
+    ///
+    /// An empty range intersects no intervals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn count_intersecting(&self, range: impl RangeBounds<usize>) -> usize {
+        let range = to_range(&range, self.positions());
+        if range.is_empty() {
+            return 0;
+        }
+        // intervals that start before the end, minus those that also end before the start
+        self.starts_before(range.end) - self.ends_upto(range.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for positions in 1..48 {
+            let mut counter = StabbingCounter::new(positions);
+            let mut naive: std::vec::Vec<core::ops::Range<usize>> = std::vec![];
+            for _ in 0..positions * 2 {
+                if !naive.is_empty() && rng.gen_bool(0.3) {
+                    let range = naive.swap_remove(rng.gen_range(0..naive.len()));
+                    counter.remove_interval(range);
+                } else {
+                    let a = rng.gen_range(0..=positions);
+                    let b = rng.gen_range(0..=positions);
+                    let range = a.min(b)..a.max(b);
+                    counter.insert_interval(range.clone());
+                    if !range.is_empty() {
+                        naive.push(range);
+                    }
+                }
+                assert_eq!(counter.len(), naive.len());
+            }
+            for i in 0..positions {
+                let expected = naive.iter().filter(|r| r.contains(&i)).count();
+                assert_eq!(counter.count_covering(i), expected);
+            }
+            for a in 0..=positions {
+                for b in a..=positions {
+                    let expected = naive.iter()
+                        .filter(|r| a < b && r.start < b && a < r.end)
+                        .count();
+                    assert_eq!(counter.count_intersecting(a..b), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Running statistics (count, sum, mean, variance and standard deviation) of samples recorded at
+//! indices, over any prefix or range of indices.
+//!
+//! [`StatsFenwick`] keeps the count, sum and sum of squares of the samples in a [`TreeGroup`], so
+//! recording a sample and summarizing a prefix or range both take `O(log(N))` . Computing the
+//! variance from the sum of squares suffers from catastrophic cancellation when the mean is large
+//! compared to the spread (e.g. timestamps or latencies around a large baseline), so all samples
+//! are shifted by the first recorded sample before being summed, which keeps the sums small as long
+//! as the samples stay near it.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::stats::StatsFenwick;
+//!
+//! // response times (ms) recorded per minute
+//! let mut latency = StatsFenwick::new(60);
+//! latency.record(0, 1_000_000_120.0);
+//! latency.record(1, 1_000_000_100.0);
+//! latency.record(1, 1_000_000_140.0);
+//! latency.record(5, 1_000_000_060.0);
+//!
+//! let first_two = latency.prefix(1);
+//! assert_eq!(first_two.count(), 3);
+//! assert_eq!(first_two.mean(), Some(1_000_000_120.0));
+//! assert_eq!(first_two.sample_variance(), Some(400.0));
+//!
+//! let later = latency.range(1..6);
+//! assert_eq!(later.count(), 3);
+//! assert_eq!(later.mean(), Some(1_000_000_100.0));
+//! assert_eq!(later.sample_variance(), Some(1600.0));
+//! assert_eq!(latency.range(2..5).mean(), None);
+//! ```
+//!
+
+use core::ops::RangeBounds;
+
+use crate::group::TreeGroup;
+use crate::index::to_range;
+
+/// Count, sum and sum of squares of the samples in a prefix or range.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    count: f64,
+    shift: f64,
+    /// Sum of `x - shift` .
+    sum: f64,
+    /// Sum of `(x - shift)^2` .
+    sum_sq: f64,
+}
+
+impl Summary {
+    /// Returns the number of samples.
+    pub fn count(&self) -> u64 {
+        self.count as u64
+    }
+
+    /// Returns the sum of the samples.
+    pub fn sum(&self) -> f64 {
+        self.sum + self.count * self.shift
+    }
+
+    /// Returns the mean of the samples, or `None` if there are none.
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0.0).then(|| self.shift + self.sum / self.count)
+    }
+
+    /// Returns the sum of squared deviations from the mean.
+    fn sum_sq_dev(&self) -> f64 {
+        (self.sum_sq - self.sum * self.sum / self.count).max(0.0)
+    }
+
+    /// Returns the population variance of the samples, or `None` if there are none.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0.0).then(|| self.sum_sq_dev() / self.count)
+    }
+
+    /// Returns the sample variance (with Bessel's correction) of the samples, or `None` if there
+    /// are fewer than two.
+    pub fn sample_variance(&self) -> Option<f64> {
+        (self.count > 1.0).then(|| self.sum_sq_dev() / (self.count - 1.0))
+    }
+
+    /// Returns the population standard deviation of the samples, or `None` if there are none.
+    pub fn stddev(&self) -> Option<f64> {
+        self.variance().map(sqrt)
+    }
+}
+
+/// Square root of a non-negative finite number, by Newton's method (`f64::sqrt` is not available
+/// in `core`).
+pub(crate) fn sqrt(x: f64) -> f64 {
+    if x <= 0.0 || x.is_infinite() {
+        return x.max(0.0);
+    }
+    // halving the exponent gives a guess within a factor of 2
+    let mut y = f64::from_bits((x.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..6 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+/// Running statistics of samples recorded at indices `0..N` .
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatsFenwick {
+    /// Count, sum of `x - shift` and sum of `(x - shift)^2` .
+    group: TreeGroup<f64, 3>,
+    /// First sample ever recorded.
+    shift: Option<f64>,
+}
+
+impl StatsFenwick {
+    /// Creates statistics over `len` indices with no samples.
+    pub fn new(len: usize) -> Self {
+        Self {
+            group: TreeGroup::new(len),
+            shift: None,
+        }
+    }
+
+    /// Returns the number of indices, i.e. `N` .
+    pub fn len(&self) -> usize {
+        self.group.len()
+    }
+
+    /// Returns `true` if there are no indices.
+    pub fn is_empty(&self) -> bool {
+        self.group.is_empty()
+    }
+
+    fn shifted(&mut self, x: f64) -> f64 {
+        x - *self.shift.get_or_insert(x)
+    }
+
+    /// Records sample `x` at index `i` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn record(&mut self, i: usize, x: f64) {
+        let d = self.shifted(x);
+        self.group.update(i, [1.0, d, d * d]);
+    }
+
+    /// Removes sample `x` at index `i` , which must have been recorded there before. Takes
+    /// `O(log(N))` .
+    ///
+    /// Removing a sample that was not recorded makes further results meaningless.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn remove(&mut self, i: usize, x: f64) {
+        let d = self.shifted(x);
+        self.group.update(i, [-1.0, -d, -d * d]);
+    }
+
+    fn summary(&self, [count, sum, sum_sq]: [f64; 3]) -> Summary {
+        Summary {
+            count,
+            shift: self.shift.unwrap_or_default(),
+            sum,
+            sum_sq,
+        }
+    }
+
+    /// Summarizes the samples recorded at indices `0..=i` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn prefix(&self, i: usize) -> Summary {
+        self.summary(self.group.prefix_sum(i))
+    }
+
+    /// Summarizes the samples recorded at indices in `range` . Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn range(&self, range: impl RangeBounds<usize>) -> Summary {
+        let range = to_range(&range, self.len());
+        if range.is_empty() {
+            return self.summary([0.0; 3]);
+        }
+        let mut sums = self.group.prefix_sum(range.end - 1);
+        if range.start > 0 {
+            let before = self.group.prefix_sum(range.start - 1);
+            for (s, b) in sums.iter_mut().zip(before) {
+                *s -= b;
+            }
+        }
+        self.summary(sums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-6 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn sqrt_values() {
+        for x in [0.0, 1e-150, 0.25, 1.0, 2.0, 1e10, 1e150] {
+            assert!(close(sqrt(x * x), x));
+        }
+        assert_eq!(sqrt(f64::INFINITY), f64::INFINITY);
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..32 {
+            let mut stats = StatsFenwick::new(len);
+            let mut naive: std::vec::Vec<(usize, f64)> = std::vec![];
+            // large baseline, small spread
+            let base = rng.gen_range(-1e9..1e9);
+            for _ in 0..len * 4 {
+                if !naive.is_empty() && rng.gen_bool(0.3) {
+                    let (i, x) = naive.swap_remove(rng.gen_range(0..naive.len()));
+                    stats.remove(i, x);
+                } else {
+                    let i = rng.gen_range(0..len);
+                    let x = base + rng.gen_range(-10.0..10.0);
+                    stats.record(i, x);
+                    naive.push((i, x));
+                }
+            }
+            for a in 0..len {
+                for b in a..=len {
+                    let xs: std::vec::Vec<f64> = naive.iter()
+                        .filter(|s| (a..b).contains(&s.0))
+                        .map(|s| s.1)
+                        .collect();
+                    let summary = stats.range(a..b);
+                    if b > a {
+                        assert_eq!(stats.prefix(b - 1).count(), stats.range(0..b).count());
+                    }
+                    assert_eq!(summary.count(), xs.len() as u64);
+                    if xs.is_empty() {
+                        assert_eq!(summary.mean(), None);
+                        assert_eq!(summary.variance(), None);
+                        continue;
+                    }
+                    let n = xs.len() as f64;
+                    let mean = xs.iter().sum::<f64>() / n;
+                    let var = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+                    assert!(close(summary.sum(), mean * n));
+                    assert!(close(summary.mean().unwrap(), mean));
+                    assert!((summary.variance().unwrap() - var).abs() < 1e-6);
+                    let stddev = summary.stddev().unwrap();
+                    assert!(close(stddev * stddev, summary.variance().unwrap()));
+                    if xs.len() > 1 {
+                        let sample = var * n / (n - 1.0);
+                        assert!((summary.sample_variance().unwrap() - sample).abs() < 1e-6);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Operations on a mirrored 1D Fenwick tree stored in a zero-based slice, for suffix sums.
+//!
+//! Element `i` of the original array is stored as element `N - 1 - i` of an ordinary Fenwick tree
+//! (see [`array`](mod@array)), so that `a[i] + ... + a[N - 1]` takes `O(log(N))` . The mirroring
+//! is done here instead of at every call site.
+//!
+//! Equivalently, [`update`] may be read as "add `delta` to `b[0..=i]`" on a dual array `b` , in
+//! which case [`suffix_sum`] returns `b[i]` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::suffix::{update, suffix_sum};
+//!
+//! let fw = &mut [0i32; 10]; // backing array of Fenwick tree (NOT original array!)
+//! update(fw, 2, 3); // original array: [0, 0, 3, 0, 0, 0, 0, 0, 0, 0]
+//! update(fw, 7, 5); // original array: [0, 0, 3, 0, 0, 0, 0, 5, 0, 0]
+//! assert_eq!(suffix_sum(fw, 0), 8);
+//! assert_eq!(suffix_sum(fw, 2), 8);
+//! assert_eq!(suffix_sum(fw, 3), 5);
+//! assert_eq!(suffix_sum(fw, 7), 5);
+//! assert_eq!(suffix_sum(fw, 8), 0);
+//! ```
+//!
+
+use core::ops::AddAssign;
+
+use crate::array;
+
+/// Updates one element in the mirrored Fenwick tree stored in a borrowed slice (zero-based).
+///
+/// Conceptually performs `a[i] += delta` on the original array `a`.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn update<T>(fenwick: &mut [T], i: usize, delta: T)
+where
+    T: AddAssign + Clone + Default
+{
+    let len = fenwick.len();
+    assert!(i < len);
+    array::update(fenwick, len - 1 - i, delta);
+}
+
+/// Calculates the suffix sum starting at and including `i` in the mirrored Fenwick tree stored in a
+/// borrowed slice (zero-based).
+///
+/// Conceptually calculates `a[i] + ... + a[N - 1]` on the original array `a`.
+///
+/// # Panics
+///
+/// Panics if `fenwick[i]` is out of bound.
+///
+/// # Examples
+///
+/// See [module-level example](self).
+///
+pub fn suffix_sum<T>(fenwick: &[T], i: usize) -> T
+where
+    T: AddAssign + Clone + Default
+{
+    let len = fenwick.len();
+    assert!(i < len);
+    array::prefix_sum(fenwick, len - 1 - i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for len in 1..128 {
+            let mut fenwick = std::vec![0i32; len];
+            let mut naive = std::vec![0i32; len];
+            for _ in 0..len {
+                let i = rng.gen_range(0..len);
+                let x = rng.gen_range(-100..=100);
+                update(&mut fenwick, i, x);
+                naive[i] += x;
+            }
+            for i in 0..len {
+                assert_eq!(suffix_sum(&fenwick, i), naive[i..].iter().sum());
+            }
+        }
+    }
+}
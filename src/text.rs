@@ -0,0 +1,236 @@
+//! Line index of a text buffer for editors, mapping between byte offsets and (line, column).
+//!
+//! The index keeps a Fenwick tree over the byte length of each line (including its trailing
+//! `'\n'`), without storing the text itself. Mapping in either direction takes `O(log(L))` for
+//! `L` lines. Edits within one line take `O(log(L))` ; edits that add or remove line breaks change
+//! the number of lines, which a Fenwick tree cannot do in place, and take `O(L)` to rebuild the
+//! tree.
+//!
+//! Offsets and columns are in bytes of UTF-8 text, and must lie on `char` boundaries of the text
+//! being indexed. To get a column in `char`s, count the `char`s of the line up to the byte column.
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::text::TextIndex;
+//!
+//! let mut index = TextIndex::new("fn main() {\n    println!(\"héllo\");\n}\n");
+//! assert_eq!(index.lines(), 4);
+//! assert_eq!(index.offset_to_line_col(0), (0, 0));
+//! assert_eq!(index.offset_to_line_col(16), (1, 4));
+//! assert_eq!(index.line_col_to_offset(2, 0), 36);
+//!
+//! index.insert(16, "let x = 1;\n    ");
+//! assert_eq!(index.lines(), 5);
+//! assert_eq!(index.line_col_to_offset(2, 4), 31);
+//! index.delete(12..31);
+//! assert_eq!(index.lines(), 4);
+//! assert_eq!(index.len(), 34);
+//! ```
+//!
+
+use core::ops::{Range, RangeBounds};
+
+use alloc::vec::Vec;
+
+use crate::array::{build, prefix_sum, update, upper_bound};
+use crate::index::to_range;
+use crate::index::zero_based::up;
+
+/// Line index of a text buffer.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextIndex {
+    /// Byte length of each line, including its trailing `'\n'` (if any).
+    lens: Vec<usize>,
+    /// Fenwick tree over `lens` .
+    fenwick: Vec<usize>,
+}
+
+impl Default for TextIndex {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl TextIndex {
+    /// Creates an index of `text` . Takes `O(N)` for `N` bytes.
+    pub fn new(text: &str) -> Self {
+        let mut index = Self {
+            lens: text.split_inclusive('\n').map(str::len).collect(),
+            fenwick: Vec::new(),
+        };
+        if text.is_empty() || text.ends_with('\n') {
+            // the last (empty) line after the last line break
+            index.lens.push(0);
+        }
+        index.rebuild();
+        index
+    }
+
+    fn rebuild(&mut self) {
+        span!("rebuild", lines = self.lens.len());
+        self.fenwick.clone_from(&self.lens);
+        build(&mut self.fenwick);
+    }
+
+    /// Returns the length of the text in bytes.
+    pub fn len(&self) -> usize {
+        prefix_sum(&self.fenwick, self.lines() - 1)
+    }
+
+    /// Returns `true` if the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of lines, i.e. one more than the number of line breaks.
+    pub fn lines(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// Returns the byte offset of the start of `line` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line >= self.lines()` .
+    ///
+    pub fn line_start(&self, line: usize) -> usize {
+        assert!(line < self.lines());
+        if line == 0 { 0 } else { prefix_sum(&self.fenwick, line - 1) }
+    }
+
+    /// Returns the line and the byte column within it of byte offset `offset` .
+    ///
+    /// An offset right after a line break is at column 0 of the next line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset > self.len()` .
+    ///
+    pub fn offset_to_line_col(&self, offset: usize) -> (usize, usize) {
+        assert!(offset <= self.len());
+        let line = upper_bound(&self.fenwick, offset).min(self.lines() - 1);
+        (line, offset - self.line_start(line))
+    }
+
+    /// Returns the byte offset of byte column `col` within `line` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line >= self.lines()` or `col` is beyond the end of the line (including its line
+    /// break).
+    ///
+    pub fn line_col_to_offset(&self, line: usize, col: usize) -> usize {
+        assert!(col <= self.lens[line]);
+        self.line_start(line) + col
+    }
+
+    /// Updates the index after inserting `text` at byte offset `offset` .
+    ///
+    /// Takes `O(log(L))` if `text` has no line breaks, or `O(L)` otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset > self.len()` .
+    ///
+    pub fn insert(&mut self, offset: usize, text: &str) {
+        let (line, col) = self.offset_to_line_col(offset);
+        if !text.contains('\n') {
+            self.lens[line] += text.len();
+            update(&mut self.fenwick, line, text.len());
+            return;
+        }
+        let tail = self.lens[line] - col;
+        let mut pieces: Vec<usize> = text.split_inclusive('\n').map(str::len).collect();
+        if text.ends_with('\n') {
+            pieces.push(0);
+        }
+        pieces[0] += col;
+        *pieces.last_mut().unwrap() += tail;
+        self.lens.splice(line..=line, pieces);
+        self.rebuild();
+    }
+
+    /// Updates the index after deleting the bytes in `range` .
+    ///
+    /// Takes `O(log(L))` if the deleted text has no line breaks, or `O(L)` otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound.
+    ///
+    pub fn delete(&mut self, range: impl RangeBounds<usize>) {
+        let Range { start, end } = to_range(&range, self.len());
+        let (line0, col0) = self.offset_to_line_col(start);
+        let (line1, col1) = self.offset_to_line_col(end);
+        if line0 == line1 {
+            self.lens[line0] -= end - start;
+            for ii in up(line0, self.fenwick.len()) {
+                self.fenwick[ii] -= end - start;
+            }
+            return;
+        }
+        let merged = col0 + (self.lens[line1] - col1);
+        self.lens.splice(line0..=line1, [merged]);
+        self.rebuild();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use std::string::String;
+
+    use rand::prelude::*;
+
+    fn check(index: &TextIndex, text: &str) {
+        assert_eq!(index, &TextIndex::new(text));
+        assert_eq!(index.len(), text.len());
+        let mut line = 0;
+        let mut col = 0;
+        for (offset, c) in text.char_indices().chain([(text.len(), '\0')]) {
+            assert_eq!(index.offset_to_line_col(offset), (line, col));
+            assert_eq!(index.line_col_to_offset(line, col), offset);
+            if c == '\n' {
+                line += 1;
+                col = 0;
+            } else {
+                col += c.len_utf8();
+            }
+        }
+        assert_eq!(index.lines(), line + 1);
+    }
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        let alphabet = ['a', 'b', 'é', '字', '\n'];
+        let mut text = String::new();
+        let mut index = TextIndex::new(&text);
+        check(&index, &text);
+        for _ in 0..500 {
+            let boundaries = text.char_indices().map(|(i, _)| i)
+                .chain([text.len()])
+                .collect::<std::vec::Vec<_>>();
+            let a = *boundaries.choose(&mut rng).unwrap();
+            if rng.gen_bool(0.6) {
+                let inserted = (0..rng.gen_range(0..6))
+                    .map(|_| *alphabet.choose(&mut rng).unwrap())
+                    .collect::<String>();
+                text.insert_str(a, &inserted);
+                index.insert(a, &inserted);
+            } else {
+                let b = *boundaries.choose(&mut rng).unwrap();
+                let range = a.min(b)..a.max(b);
+                text.replace_range(range.clone(), "");
+                index.delete(range);
+            }
+            check(&index, &text);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Version stamps for detecting changes to a tree, e.g. to invalidate caches of derived aggregates.
+//!
+//! [`Versioned<F>`](Versioned) wraps any tree `F` (an owned tree, or the `Vec` backing array of
+//! one) and keeps a counter that is bumped on every mutable access. Reads go through `Deref` and
+//! leave the version unchanged; mutations go through `DerefMut` and bump it once per access, so a
+//! batch of updates through one mutable borrow counts as a single change.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use fenwick::array::{prefix_sum, update};
+//! use fenwick::sparse::SparseFenwick;
+//! use fenwick::version::Versioned;
+//!
+//! let mut fw = Versioned::new(SparseFenwick::new(100));
+//! let v0 = fw.version();
+//! fw.update(10, 1);
+//! assert!(fw.changed_since(v0));
+//!
+//! let v1 = fw.version();
+//! assert_eq!(fw.prefix_sum(99), 1); // reads do not bump the version
+//! assert!(!fw.changed_since(v1));
+//!
+//! // one bump for a whole batch
+//! let batch = &mut *fw;
+//! batch.update(20, 2);
+//! batch.update(30, 3);
+//! assert_eq!(fw.version(), v1 + 1);
+//!
+//! // also works with backing arrays for the `array` functions
+//! let mut fw = Versioned::new(vec![0i32; 8]);
+//! update(&mut fw, 3, 5);
+//! assert_eq!(prefix_sum(&fw, 7), 5);
+//! assert_eq!(fw.version(), 1);
+//! # }
+//! ```
+//!
+
+use core::ops::{Deref, DerefMut};
+
+/// Tree with a version stamp bumped on every mutable access.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default)]
+pub struct Versioned<F> {
+    inner: F,
+    version: u64,
+}
+
+impl<F> Versioned<F> {
+    /// Wraps `inner` , starting at version `0` .
+    pub fn new(inner: F) -> Self {
+        Self { inner, version: 0 }
+    }
+
+    /// Returns the current version. It only ever increases.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns `true` if the tree may have changed since it was at `version` .
+    pub fn changed_since(&self, version: u64) -> bool {
+        self.version != version
+    }
+
+    /// Unwraps the tree.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F> Deref for Versioned<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.inner
+    }
+}
+
+impl<F> DerefMut for Versioned<F> {
+    /// Bumps the version and returns the tree for mutation.
+    fn deref_mut(&mut self) -> &mut F {
+        self.version += 1;
+        &mut self.inner
+    }
+}
+
+impl<F: PartialEq> PartialEq for Versioned<F> {
+    /// Compares the trees, ignoring versions.
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<F: Eq> Eq for Versioned<F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    use crate::array::{prefix_sum, update};
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        let mut fw = Versioned::new(std::vec![0i64; 32]);
+        let mut expected_version = 0;
+        for _ in 0..100 {
+            let v = fw.version();
+            if rng.gen_bool(0.5) {
+                let batch = &mut *fw;
+                for _ in 0..rng.gen_range(1..4) {
+                    update(batch, rng.gen_range(0..32), rng.gen_range(-9..=9));
+                }
+                expected_version += 1;
+                assert!(fw.changed_since(v));
+            } else {
+                prefix_sum(&fw, rng.gen_range(0..32));
+                assert!(!fw.changed_since(v));
+            }
+            assert_eq!(fw.version(), expected_version);
+        }
+        let copy = Versioned::new(fw.clone().into_inner());
+        assert_eq!(copy, fw);
+        assert_ne!(copy.version(), fw.version());
+    }
+}
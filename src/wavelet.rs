@@ -0,0 +1,239 @@
+//! Static wavelet matrix over an array of integers, answering rank and quantile queries by value.
+//!
+//! This is the static companion of the dynamic trees in this crate: it is built once from an array
+//! of `N` values in `O(N B)` (where `B` is the number of significant bits of the largest value),
+//! takes about `B` bits per value (plus rank directories), and answers each query in `O(B)`
+//! regardless of `N` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::wavelet::WaveletMatrix;
+//!
+//! let values = [5, 1, 4, 2, 3, 9, 7, 4];
+//! let wm = WaveletMatrix::new(&values);
+//! assert_eq!(wm.len(), 8);
+//! assert_eq!(wm.get(5), 9);
+//! // occurrences of 4 within values[..3] and values[..8]
+//! assert_eq!(wm.rank(4, 3), 1);
+//! assert_eq!(wm.rank(4, 8), 2);
+//! // k-th smallest (zero-based) value within values[2..5] == [4, 2, 3]
+//! assert_eq!(wm.quantile(2..5, 0), 2);
+//! assert_eq!(wm.quantile(2..5, 2), 4);
+//! assert_eq!(wm.quantile(0..8, 7), 9);
+//! ```
+//!
+
+use core::ops::{Range, RangeBounds};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::index::to_range;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Static wavelet matrix over an array of `u64` values.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WaveletMatrix {
+    len: usize,
+    /// One level per significant bit, from the most significant.
+    levels: Vec<BitLevel>,
+}
+
+/// One level of a wavelet matrix: a bit vector with a rank directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct BitLevel {
+    words: Vec<u64>,
+    /// `ones[w]` is the number of set bits in `words[..w]` .
+    ones: Vec<usize>,
+    /// Total number of zero bits.
+    zeros: usize,
+}
+
+impl BitLevel {
+    fn new(bits: impl ExactSizeIterator<Item = bool>) -> Self {
+        let len = bits.len();
+        let mut words = vec![0u64; len / WORD_BITS + 1];
+        for (i, bit) in bits.enumerate() {
+            words[i / WORD_BITS] |= (bit as u64) << (i % WORD_BITS);
+        }
+        let mut ones = Vec::with_capacity(words.len());
+        let mut count = 0;
+        for word in &words {
+            ones.push(count);
+            count += word.count_ones() as usize;
+        }
+        Self { words, ones, zeros: len - count }
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.words[i / WORD_BITS] >> (i % WORD_BITS) & 1 != 0
+    }
+
+    /// Number of set bits in `[..i]` .
+    fn rank1(&self, i: usize) -> usize {
+        let mask = (1u64 << (i % WORD_BITS)) - 1;
+        self.ones[i / WORD_BITS] + (self.words[i / WORD_BITS] & mask).count_ones() as usize
+    }
+
+    /// Number of zero bits in `[..i]` .
+    fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    /// Maps position `i` on this level to its position on the next level, following bit `bit` .
+    fn next(&self, i: usize, bit: bool) -> usize {
+        if bit {
+            self.zeros + self.rank1(i)
+        } else {
+            self.rank0(i)
+        }
+    }
+}
+
+impl WaveletMatrix {
+    /// Builds a wavelet matrix over `values` . Takes `O(N B)` .
+    pub fn new(values: &[u64]) -> Self {
+        let max = values.iter().copied().max().unwrap_or(0);
+        let bits = (u64::BITS - max.leading_zeros()) as usize;
+        let mut levels = Vec::with_capacity(bits);
+        let mut current = values.to_vec();
+        let mut next = Vec::with_capacity(values.len());
+        for b in (0..bits).rev() {
+            levels.push(BitLevel::new(current.iter().map(|x| x >> b & 1 != 0)));
+            // stable partition: zeros first, then ones
+            next.clear();
+            next.extend(current.iter().filter(|&&x| x >> b & 1 == 0));
+            next.extend(current.iter().filter(|&&x| x >> b & 1 != 0));
+            core::mem::swap(&mut current, &mut next);
+        }
+        Self {
+            len: values.len(),
+            levels,
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bit of the current level, counting from the most significant level.
+    fn bit(&self, level: usize) -> u32 {
+        (self.levels.len() - 1 - level) as u32
+    }
+
+    /// Returns `values[i]` . Takes `O(B)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()` .
+    ///
+    pub fn get(&self, i: usize) -> u64 {
+        assert!(i < self.len);
+        let mut i = i;
+        let mut value = 0;
+        for (d, level) in self.levels.iter().enumerate() {
+            let bit = level.get(i);
+            value |= (bit as u64) << self.bit(d);
+            i = level.next(i, bit);
+        }
+        value
+    }
+
+    /// Returns the number of occurrences of `value` in `values[..end]` . Takes `O(B)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `end > self.len()` .
+    ///
+    pub fn rank(&self, value: u64, end: usize) -> usize {
+        assert!(end <= self.len);
+        if self.levels.len() < u64::BITS as usize && value >> self.levels.len() != 0 {
+            return 0;
+        }
+        let (mut start, mut end) = (0, end);
+        for (d, level) in self.levels.iter().enumerate() {
+            let bit = value >> self.bit(d) & 1 != 0;
+            start = level.next(start, bit);
+            end = level.next(end, bit);
+        }
+        end - start
+    }
+
+    /// Returns the `k`-th smallest (zero-based) value within `values[range]` . Takes `O(B)` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bound, or if `k` is not less than the length of `range` .
+    ///
+    pub fn quantile(&self, range: impl RangeBounds<usize>, k: usize) -> u64 {
+        let Range { start, end } = to_range(&range, self.len);
+        assert!(k < end - start);
+        let (mut start, mut end, mut k) = (start, end, k);
+        let mut value = 0;
+        for (d, level) in self.levels.iter().enumerate() {
+            let zeros = level.rank0(end) - level.rank0(start);
+            let bit = k >= zeros;
+            if bit {
+                k -= zeros;
+                value |= 1 << self.bit(d);
+            }
+            start = level.next(start, bit);
+            end = level.next(end, bit);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use itertools::Itertools;
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for (len, max) in [(0, 0), (1, 0), (10, 1), (100, 7), (300, 1000), (200, u64::MAX)] {
+            let values = (0..len).map(|_| rng.gen_range(0..=max)).collect_vec();
+            let wm = WaveletMatrix::new(&values);
+            assert_eq!(wm.len(), len);
+            for (i, &x) in values.iter().enumerate() {
+                assert_eq!(wm.get(i), x);
+            }
+            for _ in 0..100 {
+                let value = if len > 0 && rng.gen_bool(0.8) {
+                    values[rng.gen_range(0..len)]
+                } else {
+                    rng.gen_range(0..=max.saturating_mul(2))
+                };
+                let end = rng.gen_range(0..=len);
+                let expected = values[..end].iter().filter(|&&x| x == value).count();
+                assert_eq!(wm.rank(value, end), expected);
+            }
+            if len == 0 {
+                continue;
+            }
+            for _ in 0..100 {
+                let a = rng.gen_range(0..len);
+                let b = rng.gen_range(0..len);
+                let range = a.min(b)..a.max(b) + 1;
+                let k = rng.gen_range(0..range.len());
+                let sorted = values[range.clone()].iter().copied().sorted().collect_vec();
+                assert_eq!(wm.quantile(range, k), sorted[k]);
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Sliding-window counter over time buckets, e.g. for rate limiting and load shedding.
+//!
+//! Time is divided into buckets of a fixed duration, and the counter keeps the total weight
+//! recorded in each of the last `N` buckets in a ring, backed by a Fenwick tree. Buckets that fall
+//! out of the window are expired automatically as time advances. Recording takes `O(log(N))`
+//! (plus `O(log(N))` per expired bucket), and summing the last `K <= N` buckets takes `O(log(N))` .
+//!
+//! # Examples
+//!
+//! ```
+//! use fenwick::window::SlidingWindowCounter;
+//!
+//! // 1s buckets, 60s window (times in ms)
+//! let mut requests = SlidingWindowCounter::new(1000, 60);
+//! requests.record(500, 1);
+//! requests.record(1500, 2);
+//! requests.record(59_999, 4);
+//! assert_eq!(requests.sum_window(59_999), 7);
+//! assert_eq!(requests.sum_recent(59_999, 59), 6); // last 59s only
+//! assert_eq!(requests.sum_window(60_000), 6); // the first second has expired
+//! requests.record(61_000, 8);
+//! assert_eq!(requests.sum_window(61_000), 12);
+//! ```
+//!
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::array::{prefix_sum, reset_range, update};
+
+/// Sliding-window counter over the last `N` time buckets.
+///
+/// See [module-level documentation](self).
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SlidingWindowCounter {
+    bucket_duration: u64,
+    /// Fenwick tree over the ring of buckets: bucket `b` is stored in slot `b % N` .
+    fenwick: Vec<u64>,
+    /// Latest bucket seen so far. The ring holds buckets `latest - N + 1 ..= latest` .
+    latest: u64,
+}
+
+impl SlidingWindowCounter {
+    /// Creates a counter with buckets of `bucket_duration` time units, over a window of `buckets`
+    /// buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket_duration` or `buckets` is zero.
+    ///
+    pub fn new(bucket_duration: u64, buckets: usize) -> Self {
+        assert!(bucket_duration > 0 && buckets > 0);
+        Self {
+            bucket_duration,
+            fenwick: vec![0; buckets],
+            latest: 0,
+        }
+    }
+
+    /// Returns the number of buckets in the window, i.e. `N` .
+    pub fn buckets(&self) -> usize {
+        self.fenwick.len()
+    }
+
+    /// Returns the duration of one bucket.
+    pub fn bucket_duration(&self) -> u64 {
+        self.bucket_duration
+    }
+
+    fn slot(&self, bucket: u64) -> usize {
+        (bucket % self.buckets() as u64) as usize
+    }
+
+    /// Records `weight` at time `now` , expiring buckets that fall out of the window first.
+    ///
+    /// Time may go backwards within the window; weight recorded at a time older than the window
+    /// (relative to the latest time seen) is ignored.
+    ///
+    pub fn record(&mut self, now: u64, weight: u64) {
+        let bucket = now / self.bucket_duration;
+        let n = self.buckets() as u64;
+        if bucket > self.latest {
+            if bucket - self.latest >= n {
+                self.fenwick.fill(0);
+            } else {
+                for expired in self.latest + 1..=bucket {
+                    let slot = self.slot(expired);
+                    reset_range(&mut self.fenwick, slot..slot + 1);
+                }
+            }
+            self.latest = bucket;
+        } else if self.latest - bucket >= n {
+            return;
+        }
+        let slot = self.slot(bucket);
+        update(&mut self.fenwick, slot, weight);
+    }
+
+    /// Returns the total weight in the window of `N` buckets ending with the bucket of `now` .
+    /// Takes `O(log(N))` .
+    pub fn sum_window(&self, now: u64) -> u64 {
+        self.sum_recent(now, self.buckets())
+    }
+
+    /// Returns the total weight in the last `buckets` buckets ending with the bucket of `now` .
+    /// Takes `O(log(N))` .
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets > self.buckets()` .
+    ///
+    pub fn sum_recent(&self, now: u64, buckets: usize) -> u64 {
+        assert!(buckets <= self.buckets());
+        let n = self.buckets() as u64;
+        let bucket = now / self.bucket_duration;
+        // intersect `bucket - buckets + 1 ..= bucket` with the ring
+        let hi = bucket.min(self.latest);
+        let lo = (bucket + 1).saturating_sub(buckets as u64)
+            .max((self.latest + 1).saturating_sub(n));
+        if buckets == 0 || lo > hi {
+            return 0;
+        }
+        let (lo, hi) = (self.slot(lo), self.slot(hi));
+        let upto = |slot: usize| prefix_sum(&self.fenwick, slot);
+        let before = |slot: usize| if slot == 0 { 0 } else { upto(slot - 1) };
+        if lo <= hi {
+            upto(hi) - before(lo)
+        } else {
+            // wraps around the end of the ring
+            upto(self.buckets() - 1) - before(lo) + upto(hi)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn randoms() {
+        let mut rng = thread_rng();
+        for buckets in 1..16 {
+            let duration = rng.gen_range(1..5);
+            let mut counter = SlidingWindowCounter::new(duration, buckets);
+            // (bucket, weight) of every accepted record
+            let mut naive: std::vec::Vec<(u64, u64)> = std::vec![];
+            let mut latest = 0u64;
+            let mut now = 0u64;
+            for _ in 0..200 {
+                now += rng.gen_range(0..duration * 3);
+                // sometimes go back in time
+                let t = now.saturating_sub(rng.gen_range(0..=duration * buckets as u64 * 2));
+                let w = rng.gen_range(0..10);
+                counter.record(t, w);
+                let b = t / duration;
+                if b + (buckets as u64) > latest {
+                    naive.push((b, w));
+                    latest = latest.max(b);
+                }
+                let q = now + rng.gen_range(0..duration * 2);
+                let qb = q / duration;
+                for k in 0..=buckets {
+                    let expected: u64 = naive.iter()
+                        .filter(|&&(b, _)| b <= qb && b + k as u64 > qb)
+                        .filter(|&&(b, _)| b + buckets as u64 > latest)
+                        .map(|&(_, w)| w)
+                        .sum();
+                    assert_eq!(counter.sum_recent(q, k), expected);
+                }
+            }
+        }
+    }
+}
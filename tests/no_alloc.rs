@@ -0,0 +1,74 @@
+//! Checks that traversals of multidimensional trees built on the `index` iterators and the slice
+//! operations in `array` never allocate.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use fenwick::array::{prefix_sum, update};
+use fenwick::index::zero_based::{down, up};
+
+struct CountingAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::SeqCst);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const N: usize = 37;
+
+fn update_2d(fw: &mut [[i64; N]; N], i: usize, j: usize, delta: i64) {
+    for ii in up(i, N) {
+        update(&mut fw[ii], j, delta);
+    }
+}
+
+fn prefix_sum_2d(fw: &[[i64; N]; N], i: usize, j: usize) -> i64 {
+    let mut sum = 0;
+    for ii in down(i) {
+        sum += prefix_sum(&fw[ii], j);
+    }
+    sum
+}
+
+fn prefix_sum_3d(fw: &[[[i64; N]; N]; N], i: usize, j: usize, k: usize) -> i64 {
+    let mut sum = 0;
+    for ii in down(i) {
+        for jj in down(j) {
+            for kk in down(k) {
+                sum += fw[ii][jj][kk];
+            }
+        }
+    }
+    sum
+}
+
+#[test]
+fn walks_do_not_allocate() {
+    let mut fw2 = Box::new([[0i64; N]; N]);
+    let fw3 = Box::new([[[1i64; N]; N]; N]);
+
+    let before = ALLOCS.load(Ordering::SeqCst);
+    let mut checksum = 0;
+    for i in 0..N {
+        for j in 0..N {
+            update_2d(&mut fw2, i, j, (i * N + j) as i64);
+            checksum += prefix_sum_2d(&fw2, i, j);
+            checksum += prefix_sum_3d(&fw3, i, j, N - 1);
+        }
+    }
+    let after = ALLOCS.load(Ordering::SeqCst);
+
+    assert_ne!(checksum, 0);
+    assert_eq!(after - before, 0);
+}